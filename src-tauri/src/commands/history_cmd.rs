@@ -0,0 +1,32 @@
+use tracing::trace;
+
+use crate::utils::chat_history::{ChatHistory, HistoryScope, StoredMessage};
+
+#[tauri::command]
+pub fn get_chat_history(
+    server_host: &str,
+    server_port: u16,
+    scope: HistoryScope,
+    before: Option<i64>,
+    limit: u32,
+) -> Result<Vec<StoredMessage>, String> {
+    trace!("Loading {scope:?} chat history of {server_host}:{server_port} before {before:?}");
+    let chat_history = ChatHistory::open(server_host, server_port).map_err(|e| e.to_string())?;
+
+    chat_history
+        .page(scope, before, limit)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn search_chat_history(
+    server_host: &str,
+    server_port: u16,
+    query: &str,
+    limit: u32,
+) -> Result<Vec<StoredMessage>, String> {
+    trace!("Searching chat history of {server_host}:{server_port}");
+    let chat_history = ChatHistory::open(server_host, server_port).map_err(|e| e.to_string())?;
+
+    chat_history.search(query, limit).map_err(|e| e.to_string())
+}
@@ -2,6 +2,7 @@
 #![allow(clippy::used_underscore_binding)]
 
 mod helper;
+pub mod history_cmd;
 pub mod settings_cmd;
 pub mod utils;
 pub mod web_cmd;
@@ -10,11 +11,23 @@ pub mod zip_cmd;
 use std::{borrow::BorrowMut, collections::HashMap, path::Path, sync::Arc};
 
 use crate::{
-    connection::{traits::Shutdown, Connection},
+    connection::{traits::Shutdown, Connection, Credentials},
     errors::string_convertion::ErrorString,
-    manager::user::UpdateableUserState,
+    manager::{
+        acl::ChannelAcl,
+        channel::UpdateableChannelState,
+        user::{UpdateableUserState, UserModeration},
+        voice_target::VoiceTarget,
+    },
     protocol::message_transmitter::MessageTransmitter,
-    utils::{audio::device_manager::AudioDeviceManager, constants::get_project_dirs},
+    utils::{
+        audio::{
+            device_manager::{device_names, watch_devices, AudioDeviceManager, DeviceKind},
+            positional::ListenerPose,
+        },
+        constants::get_project_dirs,
+        shortcut::Shortcut,
+    },
 };
 use tauri::{AppHandle, State};
 use tauri_plugin_window_state::{AppHandleExt, StateFlags};
@@ -26,7 +39,7 @@ use tracing::{error, info, trace};
 
 use self::utils::settings::{
     AudioOptions, AudioOutputSettings, AudioPreviewContainer, AudioUserState, Coordinates,
-    GlobalSettings,
+    GlobalSettings, InputMode, VoiceTargetShortcut,
 };
 use image::{
     imageops::{self, FilterType},
@@ -52,6 +65,7 @@ async fn add_message_handler(
 
 async fn create_settings_channel(state: &State<'_, ConnectionState>) -> Receiver<GlobalSettings> {
     let (sender, recv): (Sender<GlobalSettings>, Receiver<GlobalSettings>) = broadcast::channel(20);
+    watch_devices(sender.clone());
     let mut guard: tokio::sync::MutexGuard<'_, Option<Sender<GlobalSettings>>> =
         state.settings_channel.lock().await;
     let _ = guard.insert(sender);
@@ -65,6 +79,8 @@ pub async fn connect_to_server(
     server_port: u16,
     username: String,
     identity: Option<String>,
+    password: Option<String>,
+    access_tokens: Option<Vec<String>>,
     state: State<'_, ConnectionState>,
 ) -> Result<(), String> {
     info!("Connecting to server: {server_host}:{server_port}, username: {username}, identity: {identity:?}");
@@ -85,6 +101,10 @@ pub async fn connect_to_server(
         server_port,
         &username,
         identity,
+        Credentials {
+            password,
+            access_tokens: access_tokens.unwrap_or_default(),
+        },
         app_info,
         settings_channel,
     ));
@@ -158,6 +178,418 @@ pub async fn logout(state: State<'_, ConnectionState>) -> Result<(), String> {
     Ok(())
 }
 
+// guard can't be dropped any earlier
+#[allow(clippy::significant_drop_tightening)]
+#[tauri::command]
+pub async fn update_access_tokens(
+    access_tokens: Vec<String>,
+    state: State<'_, ConnectionState>,
+) -> Result<(), String> {
+    let guard = state.connection.lock().await;
+    // the tokens would otherwise get lost silently, instead of being used for the next connection
+    let Some(connection) = guard.as_ref() else {
+        return Err("Not connected to a server".to_string());
+    };
+
+    connection
+        .update_access_tokens(access_tokens)
+        .map_err(|e| format!("{e:?}"))
+}
+
+// guard can't be dropped any earlier
+#[allow(clippy::significant_drop_tightening)]
+#[tauri::command]
+pub async fn request_acl(channel_id: u32, state: State<'_, ConnectionState>) -> Result<(), String> {
+    let guard = state.connection.lock().await;
+    let Some(connection) = guard.as_ref() else {
+        return Err("Not connected to a server".to_string());
+    };
+
+    connection
+        .request_acl(channel_id)
+        .map_err(|e| format!("{e:?}"))
+}
+
+// guard can't be dropped any earlier
+#[allow(clippy::significant_drop_tightening)]
+#[tauri::command]
+pub async fn submit_acl(acl: ChannelAcl, state: State<'_, ConnectionState>) -> Result<(), String> {
+    let guard = state.connection.lock().await;
+    let Some(connection) = guard.as_ref() else {
+        return Err("Not connected to a server".to_string());
+    };
+
+    connection.submit_acl(acl).map_err(|e| format!("{e:?}"))
+}
+
+// guard can't be dropped any earlier
+#[allow(clippy::significant_drop_tightening)]
+#[tauri::command]
+pub async fn create_channel(
+    channel: UpdateableChannelState,
+    state: State<'_, ConnectionState>,
+) -> Result<(), String> {
+    let guard = state.connection.lock().await;
+    let Some(connection) = guard.as_ref() else {
+        return Err("Not connected to a server".to_string());
+    };
+
+    connection
+        .create_channel(channel)
+        .map_err(|e| format!("{e:?}"))
+}
+
+// guard can't be dropped any earlier
+#[allow(clippy::significant_drop_tightening)]
+#[tauri::command]
+pub async fn update_channel(
+    channel: UpdateableChannelState,
+    state: State<'_, ConnectionState>,
+) -> Result<(), String> {
+    let guard = state.connection.lock().await;
+    let Some(connection) = guard.as_ref() else {
+        return Err("Not connected to a server".to_string());
+    };
+
+    connection
+        .update_channel(channel)
+        .map_err(|e| format!("{e:?}"))
+}
+
+// guard can't be dropped any earlier
+#[allow(clippy::significant_drop_tightening)]
+#[tauri::command]
+pub async fn remove_channel(
+    channel_id: u32,
+    state: State<'_, ConnectionState>,
+) -> Result<(), String> {
+    let guard = state.connection.lock().await;
+    let Some(connection) = guard.as_ref() else {
+        return Err("Not connected to a server".to_string());
+    };
+
+    connection
+        .remove_channel(channel_id)
+        .map_err(|e| format!("{e:?}"))
+}
+
+// guard can't be dropped any earlier
+#[allow(clippy::significant_drop_tightening)]
+#[tauri::command]
+pub async fn kick_user(
+    session: u32,
+    reason: Option<String>,
+    state: State<'_, ConnectionState>,
+) -> Result<(), String> {
+    let guard = state.connection.lock().await;
+    let Some(connection) = guard.as_ref() else {
+        return Err("Not connected to a server".to_string());
+    };
+
+    connection
+        .kick_user(session, reason)
+        .map_err(|e| format!("{e:?}"))
+}
+
+// guard can't be dropped any earlier
+#[allow(clippy::significant_drop_tightening)]
+#[tauri::command]
+pub async fn ban_user(
+    session: u32,
+    reason: Option<String>,
+    state: State<'_, ConnectionState>,
+) -> Result<(), String> {
+    let guard = state.connection.lock().await;
+    let Some(connection) = guard.as_ref() else {
+        return Err("Not connected to a server".to_string());
+    };
+
+    connection
+        .ban_user(session, reason)
+        .map_err(|e| format!("{e:?}"))
+}
+
+// guard can't be dropped any earlier
+#[allow(clippy::significant_drop_tightening)]
+#[tauri::command]
+pub async fn moderate_user(
+    moderation: UserModeration,
+    state: State<'_, ConnectionState>,
+) -> Result<(), String> {
+    let guard = state.connection.lock().await;
+    let Some(connection) = guard.as_ref() else {
+        return Err("Not connected to a server".to_string());
+    };
+
+    connection
+        .moderate_user(moderation)
+        .map_err(|e| format!("{e:?}"))
+}
+
+// guard can't be dropped any earlier
+#[allow(clippy::significant_drop_tightening)]
+#[tauri::command]
+pub async fn request_ban_list(state: State<'_, ConnectionState>) -> Result<(), String> {
+    let guard = state.connection.lock().await;
+    let Some(connection) = guard.as_ref() else {
+        return Err("Not connected to a server".to_string());
+    };
+
+    connection.request_ban_list().map_err(|e| format!("{e:?}"))
+}
+
+// guard can't be dropped any earlier
+#[allow(clippy::significant_drop_tightening)]
+#[tauri::command]
+pub async fn submit_ban_list(
+    bans: Vec<BanEntry>,
+    state: State<'_, ConnectionState>,
+) -> Result<(), String> {
+    let guard = state.connection.lock().await;
+    let Some(connection) = guard.as_ref() else {
+        return Err("Not connected to a server".to_string());
+    };
+
+    connection
+        .submit_ban_list(bans)
+        .map_err(|e| format!("{e:?}"))
+}
+
+// guard can't be dropped any earlier
+#[allow(clippy::significant_drop_tightening)]
+#[tauri::command]
+pub async fn request_registered_users(state: State<'_, ConnectionState>) -> Result<(), String> {
+    let guard = state.connection.lock().await;
+    let Some(connection) = guard.as_ref() else {
+        return Err("Not connected to a server".to_string());
+    };
+
+    connection
+        .request_registered_users()
+        .map_err(|e| format!("{e:?}"))
+}
+
+// guard can't be dropped any earlier
+#[allow(clippy::significant_drop_tightening)]
+#[tauri::command]
+pub async fn rename_registered_user(
+    user_id: u32,
+    name: String,
+    state: State<'_, ConnectionState>,
+) -> Result<(), String> {
+    let guard = state.connection.lock().await;
+    let Some(connection) = guard.as_ref() else {
+        return Err("Not connected to a server".to_string());
+    };
+
+    connection
+        .update_registered_user(user_id, Some(name))
+        .map_err(|e| format!("{e:?}"))
+}
+
+// guard can't be dropped any earlier
+#[allow(clippy::significant_drop_tightening)]
+#[tauri::command]
+pub async fn deregister_user(
+    user_id: u32,
+    state: State<'_, ConnectionState>,
+) -> Result<(), String> {
+    let guard = state.connection.lock().await;
+    let Some(connection) = guard.as_ref() else {
+        return Err("Not connected to a server".to_string());
+    };
+
+    connection
+        .update_registered_user(user_id, None)
+        .map_err(|e| format!("{e:?}"))
+}
+
+// guard can't be dropped any earlier
+#[allow(clippy::significant_drop_tightening)]
+#[tauri::command]
+pub async fn register_self(state: State<'_, ConnectionState>) -> Result<(), String> {
+    let guard = state.connection.lock().await;
+    let Some(connection) = guard.as_ref() else {
+        return Err("Not connected to a server".to_string());
+    };
+
+    connection.register_self().map_err(|e| format!("{e:?}"))
+}
+
+// guard can't be dropped any earlier
+#[allow(clippy::significant_drop_tightening)]
+#[tauri::command]
+pub async fn request_user_stats(
+    session: u32,
+    state: State<'_, ConnectionState>,
+) -> Result<(), String> {
+    let guard = state.connection.lock().await;
+    let Some(connection) = guard.as_ref() else {
+        return Err("Not connected to a server".to_string());
+    };
+
+    connection
+        .request_user_stats(session)
+        .map_err(|e| format!("{e:?}"))
+}
+
+// guard can't be dropped any earlier
+#[allow(clippy::significant_drop_tightening)]
+#[tauri::command]
+pub async fn stop_user_stats(state: State<'_, ConnectionState>) -> Result<(), String> {
+    let guard = state.connection.lock().await;
+    let Some(connection) = guard.as_ref() else {
+        return Err("Not connected to a server".to_string());
+    };
+    connection.stop_user_stats();
+
+    Ok(())
+}
+
+// guard can't be dropped any earlier
+#[allow(clippy::significant_drop_tightening)]
+#[tauri::command]
+pub async fn trigger_context_action(
+    action: String,
+    session: Option<u32>,
+    channel_id: Option<u32>,
+    state: State<'_, ConnectionState>,
+) -> Result<(), String> {
+    let guard = state.connection.lock().await;
+    let Some(connection) = guard.as_ref() else {
+        return Err("Not connected to a server".to_string());
+    };
+
+    connection
+        .trigger_context_action(&action, session, channel_id)
+        .map_err(|e| format!("{e:?}"))
+}
+
+// guard can't be dropped any earlier
+#[allow(clippy::significant_drop_tightening)]
+#[tauri::command]
+pub async fn listen_to_channels(
+    channel_ids: Vec<u32>,
+    state: State<'_, ConnectionState>,
+) -> Result<(), String> {
+    let guard = state.connection.lock().await;
+    let Some(connection) = guard.as_ref() else {
+        return Err("Not connected to a server".to_string());
+    };
+
+    connection
+        .listen_to_channels(channel_ids)
+        .map_err(|e| format!("{e:?}"))
+}
+
+// guard can't be dropped any earlier
+#[allow(clippy::significant_drop_tightening)]
+#[tauri::command]
+pub async fn stop_listening_to_channels(
+    channel_ids: Vec<u32>,
+    state: State<'_, ConnectionState>,
+) -> Result<(), String> {
+    let guard = state.connection.lock().await;
+    let Some(connection) = guard.as_ref() else {
+        return Err("Not connected to a server".to_string());
+    };
+
+    connection
+        .stop_listening_to_channels(channel_ids)
+        .map_err(|e| format!("{e:?}"))
+}
+
+// guard can't be dropped any earlier
+#[allow(clippy::significant_drop_tightening)]
+#[tauri::command]
+pub async fn set_listener_volume(
+    channel_id: u32,
+    volume: f32,
+    state: State<'_, ConnectionState>,
+) -> Result<(), String> {
+    let guard = state.connection.lock().await;
+    let Some(connection) = guard.as_ref() else {
+        return Err("Not connected to a server".to_string());
+    };
+
+    connection
+        .set_listener_volume(channel_id, volume)
+        .map_err(|e| format!("{e:?}"))
+}
+
+// guard can't be dropped any earlier
+#[allow(clippy::significant_drop_tightening)]
+#[tauri::command]
+pub async fn set_voice_target(
+    voice_target: VoiceTarget,
+    state: State<'_, ConnectionState>,
+) -> Result<(), String> {
+    let shortcut = voice_target
+        .shortcut
+        .as_deref()
+        .map(str::parse::<Shortcut>)
+        .transpose()
+        .map_err(|e| format!("{e:?}"))?;
+    let target_id = voice_target.id;
+
+    let guard = state.connection.lock().await;
+    // the server wouldn't know the target, so holding the shortcut would transmit to nobody
+    let Some(connection) = guard.as_ref() else {
+        return Err("Not connected to a server".to_string());
+    };
+    if let Err(e) = connection.set_voice_target(voice_target) {
+        return Err(format!("{e:?}"));
+    }
+
+    state.settings_channel.lock().await.as_ref().map(|x| {
+        x.send(GlobalSettings::VoiceTargetShortcut(VoiceTargetShortcut {
+            target_id,
+            shortcut,
+        }))
+    });
+
+    Ok(())
+}
+
+// guard can't be dropped any earlier
+#[allow(clippy::significant_drop_tightening)]
+#[tauri::command]
+pub async fn remove_voice_target(id: u32, state: State<'_, ConnectionState>) -> Result<(), String> {
+    let guard = state.connection.lock().await;
+    let Some(connection) = guard.as_ref() else {
+        return Err("Not connected to a server".to_string());
+    };
+    if let Err(e) = connection.remove_voice_target(id) {
+        return Err(format!("{e:?}"));
+    }
+
+    state.settings_channel.lock().await.as_ref().map(|x| {
+        x.send(GlobalSettings::VoiceTargetShortcut(VoiceTargetShortcut {
+            target_id: id,
+            shortcut: None,
+        }))
+    });
+
+    Ok(())
+}
+
+// guard can't be dropped any earlier
+#[allow(clippy::significant_drop_tightening)]
+#[tauri::command]
+pub async fn query_permissions(
+    channel_id: u32,
+    state: State<'_, ConnectionState>,
+) -> Result<(), String> {
+    let guard = state.connection.lock().await;
+    let Some(connection) = guard.as_ref() else {
+        return Err("Not connected to a server".to_string());
+    };
+
+    connection
+        .query_permissions(channel_id)
+        .map_err(|e| format!("{e:?}"))
+}
+
 // guard can't be dropped any earlier
 #[allow(clippy::significant_drop_tightening)]
 #[tauri::command]
@@ -288,12 +720,65 @@ pub async fn get_audio_devices(
     Err(ErrorString("Failed to get audio devices".to_string()))
 }
 
+#[tauri::command]
+pub fn get_audio_output_devices() -> Result<Vec<String>, ErrorString> {
+    device_names(DeviceKind::Output)
+        .map_err(|e| ErrorString(format!("Failed to get audio devices: {e}")))
+}
+
+#[tauri::command]
+pub async fn set_input_device(
+    state: State<'_, ConnectionState>,
+    device: Option<String>,
+) -> Result<(), String> {
+    trace!("Set input device: {:?}", device);
+    state
+        .settings_channel
+        .lock()
+        .await
+        .as_ref()
+        .map(|x| x.send(GlobalSettings::InputDevice(device)));
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn set_output_device(
+    state: State<'_, ConnectionState>,
+    device: Option<String>,
+) -> Result<(), String> {
+    trace!("Set output device: {:?}", device);
+    state
+        .settings_channel
+        .lock()
+        .await
+        .as_ref()
+        .map(|x| x.send(GlobalSettings::OutputDevice(device)));
+    Ok(())
+}
+
+/// Checks a recorded shortcut before the frontend stores it
+#[tauri::command]
+pub fn validate_shortcut(shortcut: &str) -> Result<(), String> {
+    shortcut
+        .parse::<Shortcut>()
+        .map(|_| ())
+        .map_err(|e| format!("{e:?}"))
+}
+
 #[tauri::command]
 pub async fn set_audio_input_setting(
     state: State<'_, ConnectionState>,
     settings: AudioOptions,
 ) -> Result<(), String> {
     trace!("Set setting: {:?}", settings);
+    // without a valid shortcut we would never transmit
+    if settings.input_mode != InputMode::VoiceActivation {
+        let shortcut = settings
+            .push_to_talk_options
+            .as_ref()
+            .ok_or("Missing push to talk shortcut")?;
+        validate_shortcut(&shortcut.shortcut)?;
+    }
     state
         .settings_channel
         .lock()
@@ -318,6 +803,21 @@ pub async fn set_audio_output_setting(
     Ok(())
 }
 
+#[tauri::command]
+pub async fn set_listener_pose(
+    state: State<'_, ConnectionState>,
+    pose: ListenerPose,
+) -> Result<(), String> {
+    trace!("Set listener pose: {:?}", pose);
+    state
+        .settings_channel
+        .lock()
+        .await
+        .as_ref()
+        .map(|x| x.send(GlobalSettings::ListenerPose(pose)));
+    Ok(())
+}
+
 #[tauri::command]
 pub async fn set_audio_user_state(
     state: State<'_, ConnectionState>,
@@ -3,11 +3,14 @@ use std::{
     io::{Seek, SeekFrom, Write},
 };
 
-use tracing::{info, trace};
+use tracing::{info, trace, warn};
 
 use crate::{
     errors::certificate_error::CertificateError,
-    utils::{constants::get_project_dirs, server::Server},
+    utils::{
+        constants::get_project_dirs, pinned_certificate_store::PinnedCertificateStore,
+        server::Server,
+    },
 };
 
 const SERVER_SETTINS_FILE: &str = "server.json";
@@ -32,6 +35,8 @@ pub fn save_server(
     server_port: u16,
     username: &str,
     identity: Option<String>,
+    password: Option<String>,
+    access_tokens: Option<Vec<String>>,
 ) -> Result<(), String> {
     info!("Saving server: {server_host}:{server_port}");
     let mut server_file = get_settings_file(SERVER_SETTINS_FILE)?;
@@ -47,13 +52,29 @@ pub fn save_server(
     //     }
     // }
 
-    server_list.push(Server {
+    // servers saved by older versions may still contain their password
+    for server in &mut server_list {
+        if let Err(e) = server.store_password() {
+            warn!(
+                "Unable to move the password of {}:{} to the keyring: {e}",
+                server.host, server.port
+            );
+        }
+    }
+
+    let mut server = Server {
         description: description.to_string(),
         host: server_host.to_string(),
         port: server_port,
         username: username.to_string(),
         identity,
-    });
+        password,
+        access_tokens: access_tokens.unwrap_or_default(),
+    };
+    server
+        .store_password()
+        .map_err(|e| format!("Unable to store the password: {e}"))?;
+    server_list.push(server);
 
     trace!("Server list: {:#?}", server_list);
 
@@ -61,6 +82,7 @@ pub fn save_server(
     server_file
         .seek(SeekFrom::Start(0))
         .map_err(|e| format!("{e:?}"))?;
+    server_file.set_len(0).map_err(|e| format!("{e:?}"))?;
     server_file
         .write_all(
             serde_json::to_string_pretty(&server_list)
@@ -91,11 +113,20 @@ pub fn get_server_list() -> Result<Vec<Server>, String> {
         .map_err(|e| format!("Error opening file: {e:?}"))?;
 
     // read the json content using serde
-    let server_list =
+    let mut server_list =
         serde_json::from_reader::<&std::fs::File, Vec<Server>>(&server_file).unwrap_or_default();
 
     trace!("Server list: {:#?}", server_list);
 
+    for server in &mut server_list {
+        if let Err(e) = server.load_password() {
+            warn!(
+                "Unable to read the password of {}:{} from the keyring: {e}",
+                server.host, server.port
+            );
+        }
+    }
+
     Ok(server_list)
 }
 
@@ -131,3 +162,17 @@ pub fn get_identity_certs() -> Result<Vec<String>, String> {
 
     Ok(certs)
 }
+
+#[tauri::command]
+pub fn accept_server_certificate(
+    server_host: &str,
+    server_port: u16,
+    fingerprint: &str,
+) -> Result<(), String> {
+    info!("Accepting certificate {fingerprint} for {server_host}:{server_port}");
+    let mut pinned_certificates = PinnedCertificateStore::load().map_err(|e| format!("{e:?}"))?;
+
+    pinned_certificates
+        .accept(server_host, server_port, fingerprint)
+        .map_err(|e| format!("{e:?}"))
+}
@@ -3,6 +3,11 @@ use std::sync::Arc;
 use serde::{Deserialize, Serialize};
 use tokio::sync::Mutex;
 
+use crate::utils::{
+    audio::{device_manager::AvailableDevices, positional::ListenerPose},
+    shortcut::Shortcut,
+};
+
 #[allow(clippy::module_name_repetitions)]
 #[derive(serde::Deserialize, Serialize, Debug)]
 pub enum FrontendSettings {
@@ -43,6 +48,15 @@ pub struct CompressorOptions {
 pub enum InputMode {
     VoiceActivation = 0,
     PushToTalk = 1,
+    ToggleToTalk = 2,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PushToTalkOptions {
+    pub shortcut: String,
+    /// Keeps transmitting for this many milliseconds after the key was released
+    pub release_delay: usize,
+    pub audio_cue: bool,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -50,7 +64,15 @@ pub struct AudioOptions {
     pub amplification: f32,
     pub input_mode: InputMode,
     pub voice_activation_options: Option<VoiceActivationOptions>,
+    pub push_to_talk_options: Option<PushToTalkOptions>,
     pub compressor_options: Option<CompressorOptions>,
+    #[serde(default = "default_noise_suppression")]
+    pub noise_suppression: bool,
+}
+
+/// Noise suppression is enabled unless the user disables it, like in the frontend
+const fn default_noise_suppression() -> bool {
+    true
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -62,7 +84,10 @@ pub struct UserVoiceAdjustment {
 #[allow(clippy::module_name_repetitions)]
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct AudioOutputSettings {
+    #[serde(default)]
     pub voice_adjustment: Vec<UserVoiceAdjustment>,
+    /// Spatializes users who send their position, `None` keeps the current mode
+    pub positional_audio: Option<bool>,
 }
 
 #[derive(Clone, Debug)]
@@ -77,6 +102,13 @@ pub struct AudioUserState {
     pub mute: bool,
 }
 
+/// Binds a registered voice target to a shortcut, without a shortcut the binding is removed
+#[derive(Clone, Debug)]
+pub struct VoiceTargetShortcut {
+    pub target_id: u32,
+    pub shortcut: Option<Shortcut>,
+}
+
 #[allow(clippy::module_name_repetitions)]
 #[allow(clippy::enum_variant_names)]
 #[derive(Clone, Debug)]
@@ -85,6 +117,14 @@ pub enum GlobalSettings {
     AudioOutputSettings(AudioOutputSettings),
     AudioPreview(AudioPreviewContainer),
     AudioUserState(AudioUserState),
+    /// Name of the device, `None` uses the default device
+    InputDevice(Option<String>),
+    OutputDevice(Option<String>),
+    AvailableDevices(AvailableDevices),
+    VoiceTargetShortcut(VoiceTargetShortcut),
+    /// Position and orientation of the local user, set by an integration like a game plugin.
+    /// Positional audio stays unpanned until the first pose arrives
+    ListenerPose(ListenerPose),
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
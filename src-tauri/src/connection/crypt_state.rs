@@ -0,0 +1,385 @@
+use std::cmp::Ordering;
+use std::time::Instant;
+
+use openssl::symm::{Cipher, Crypter, Mode};
+
+use crate::errors::{crypt_error::CryptError, AnyError};
+
+pub const AES_BLOCK_SIZE: usize = 16;
+const AES_KEY_SIZE: usize = 16;
+const HEADER_SIZE: usize = 4;
+const TAG_SIZE: usize = 3;
+const MAX_LATE_PACKETS: i32 = 30;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PacketStats {
+    pub good: u32,
+    pub late: u32,
+    pub lost: u32,
+    pub resync: u32,
+}
+
+struct Aes128 {
+    encrypter: Crypter,
+    decrypter: Crypter,
+}
+
+impl Aes128 {
+    fn new(key: &[u8]) -> AnyError<Self> {
+        let mut encrypter = Crypter::new(Cipher::aes_128_ecb(), Mode::Encrypt, key, None)?;
+        encrypter.pad(false);
+        let mut decrypter = Crypter::new(Cipher::aes_128_ecb(), Mode::Decrypt, key, None)?;
+        decrypter.pad(false);
+
+        Ok(Self {
+            encrypter,
+            decrypter,
+        })
+    }
+
+    fn encrypt(&mut self, block: u128) -> AnyError<u128> {
+        Self::apply(&mut self.encrypter, block)
+    }
+
+    fn decrypt(&mut self, block: u128) -> AnyError<u128> {
+        Self::apply(&mut self.decrypter, block)
+    }
+
+    fn apply(crypter: &mut Crypter, block: u128) -> AnyError<u128> {
+        // openssl requires room for an additional block, even without padding
+        let mut output = [0u8; AES_BLOCK_SIZE * 2];
+        let written = crypter.update(&block.to_be_bytes(), &mut output)?;
+        if written != AES_BLOCK_SIZE {
+            return Err(Box::new(CryptError::new(format!(
+                "Unexpected AES output size: {written}"
+            ))));
+        }
+
+        Ok(read_block(&output[..AES_BLOCK_SIZE]))
+    }
+}
+
+/// OCB2-AES128 state of the voice channel, compatible with the Mumble `CryptStateOCB2`.
+///
+/// The key and both nonces are handed to us by the server through `CryptSetup`.
+pub struct CryptState {
+    aes: Option<Aes128>,
+    encrypt_iv: [u8; AES_BLOCK_SIZE],
+    decrypt_iv: [u8; AES_BLOCK_SIZE],
+    decrypt_history: [u8; 256],
+    stats: PacketStats,
+    last_good: Instant,
+}
+
+impl Default for CryptState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CryptState {
+    pub fn new() -> Self {
+        Self {
+            aes: None,
+            encrypt_iv: [0; AES_BLOCK_SIZE],
+            decrypt_iv: [0; AES_BLOCK_SIZE],
+            decrypt_history: [0; 256],
+            stats: PacketStats::default(),
+            last_good: Instant::now(),
+        }
+    }
+
+    pub const fn is_valid(&self) -> bool {
+        self.aes.is_some()
+    }
+
+    pub const fn stats(&self) -> PacketStats {
+        self.stats
+    }
+
+    pub const fn last_good(&self) -> Instant {
+        self.last_good
+    }
+
+    pub const fn encrypt_iv(&self) -> &[u8; AES_BLOCK_SIZE] {
+        &self.encrypt_iv
+    }
+
+    pub fn set_key(
+        &mut self,
+        key: &[u8],
+        client_nonce: &[u8],
+        server_nonce: &[u8],
+    ) -> AnyError<()> {
+        if key.len() != AES_KEY_SIZE {
+            return Err(Box::new(CryptError::new(format!(
+                "Invalid key size: {}",
+                key.len()
+            ))));
+        }
+
+        self.encrypt_iv = to_iv(client_nonce)?;
+        self.decrypt_iv = to_iv(server_nonce)?;
+        self.decrypt_history = [0; 256];
+        self.stats = PacketStats::default();
+        self.last_good = Instant::now();
+        self.aes = Some(Aes128::new(key)?);
+
+        Ok(())
+    }
+
+    pub fn set_decrypt_iv(&mut self, server_nonce: &[u8]) -> AnyError<()> {
+        self.decrypt_iv = to_iv(server_nonce)?;
+
+        Ok(())
+    }
+
+    /// Counts a resync, like mumble we count the request and not the answer of the server
+    pub fn count_resync(&mut self) {
+        self.stats.resync += 1;
+    }
+
+    pub fn encrypt(&mut self, source: &[u8]) -> AnyError<Vec<u8>> {
+        let aes = self
+            .aes
+            .as_mut()
+            .ok_or_else(|| CryptError::new("Crypt state is not initialized"))?;
+
+        increment_iv(&mut self.encrypt_iv, 0);
+        let (encrypted, tag) = ocb_encrypt(aes, source, &self.encrypt_iv)?;
+
+        let mut packet = Vec::with_capacity(HEADER_SIZE + encrypted.len());
+        packet.push(self.encrypt_iv[0]);
+        packet.extend_from_slice(&tag[..TAG_SIZE]);
+        packet.extend(encrypted);
+
+        Ok(packet)
+    }
+
+    pub fn decrypt(&mut self, source: &[u8]) -> AnyError<Vec<u8>> {
+        if source.len() < HEADER_SIZE {
+            return Err(Box::new(CryptError::new("Packet is too short")));
+        }
+
+        let aes = self
+            .aes
+            .as_mut()
+            .ok_or_else(|| CryptError::new("Crypt state is not initialized"))?;
+
+        let saved_iv = self.decrypt_iv;
+        let iv_byte = source[0];
+        let mut restore = false;
+        let mut late = 0;
+        let mut lost = 0;
+
+        if self.decrypt_iv[0].wrapping_add(1) == iv_byte {
+            // in order as expected
+            match iv_byte.cmp(&self.decrypt_iv[0]) {
+                Ordering::Greater => self.decrypt_iv[0] = iv_byte,
+                Ordering::Less => {
+                    self.decrypt_iv[0] = iv_byte;
+                    increment_iv(&mut self.decrypt_iv, 1);
+                }
+                Ordering::Equal => return Err(Box::new(CryptError::new("Invalid packet iv"))),
+            }
+        } else {
+            // out of order or a repeat
+            let current = i32::from(self.decrypt_iv[0]);
+            let incoming = i32::from(iv_byte);
+            let mut diff = incoming - current;
+            if diff > 128 {
+                diff -= 256;
+            } else if diff < -128 {
+                diff += 256;
+            }
+
+            if incoming < current && diff > -MAX_LATE_PACKETS && diff < 0 {
+                // late packet, but no wraparound
+                late = 1;
+                lost = -1;
+                self.decrypt_iv[0] = iv_byte;
+                restore = true;
+            } else if incoming > current && diff > -MAX_LATE_PACKETS && diff < 0 {
+                // late packet from the previous round
+                late = 1;
+                lost = -1;
+                self.decrypt_iv[0] = iv_byte;
+                decrement_iv(&mut self.decrypt_iv, 1);
+                restore = true;
+            } else if incoming > current && diff > 0 {
+                // lost a few packets, but beyond that we're good
+                lost = incoming - current - 1;
+                self.decrypt_iv[0] = iv_byte;
+            } else if incoming < current && diff > 0 {
+                // lost a few packets and wrapped around
+                lost = 256 - current + incoming - 1;
+                self.decrypt_iv[0] = iv_byte;
+                increment_iv(&mut self.decrypt_iv, 1);
+            } else {
+                return Err(Box::new(CryptError::new("Invalid packet iv")));
+            }
+
+            if self.decrypt_history[usize::from(self.decrypt_iv[0])] == self.decrypt_iv[1] {
+                self.decrypt_iv = saved_iv;
+                return Err(Box::new(CryptError::new("Received a replayed packet")));
+            }
+        }
+
+        let (plain, tag, valid) = match ocb_decrypt(aes, &source[HEADER_SIZE..], &self.decrypt_iv) {
+            Ok(result) => result,
+            Err(e) => {
+                self.decrypt_iv = saved_iv;
+                return Err(e);
+            }
+        };
+
+        if !valid || tag[..TAG_SIZE] != source[1..HEADER_SIZE] {
+            self.decrypt_iv = saved_iv;
+            return Err(Box::new(CryptError::new("Packet authentication failed")));
+        }
+
+        self.decrypt_history[usize::from(self.decrypt_iv[0])] = self.decrypt_iv[1];
+
+        if restore {
+            self.decrypt_iv = saved_iv;
+        }
+
+        self.stats.good += 1;
+        self.stats.late = self.stats.late.saturating_add_signed(late);
+        self.stats.lost = self.stats.lost.saturating_add_signed(lost);
+        self.last_good = Instant::now();
+
+        Ok(plain)
+    }
+}
+
+fn to_iv(nonce: &[u8]) -> AnyError<[u8; AES_BLOCK_SIZE]> {
+    nonce
+        .try_into()
+        .map_err(|_| CryptError::new(format!("Invalid nonce size: {}", nonce.len())).into())
+}
+
+fn read_block(data: &[u8]) -> u128 {
+    let mut block = [0u8; AES_BLOCK_SIZE];
+    block.copy_from_slice(data);
+    u128::from_be_bytes(block)
+}
+
+fn increment_iv(iv: &mut [u8; AES_BLOCK_SIZE], start: usize) {
+    for byte in &mut iv[start..] {
+        *byte = byte.wrapping_add(1);
+        if *byte != 0 {
+            break;
+        }
+    }
+}
+
+fn decrement_iv(iv: &mut [u8; AES_BLOCK_SIZE], start: usize) {
+    for byte in &mut iv[start..] {
+        let previous = *byte;
+        *byte = previous.wrapping_sub(1);
+        if previous != 0 {
+            break;
+        }
+    }
+}
+
+// multiplication by two in GF(2^128)
+const fn times_two(block: u128) -> u128 {
+    let carry = block >> 127;
+    (block << 1) ^ (carry * 0x87)
+}
+
+const fn times_three(block: u128) -> u128 {
+    times_two(block) ^ block
+}
+
+// the length of the final block in bits, always fits into the lowest byte
+fn length_block(remaining: usize) -> u128 {
+    (remaining * 8) as u128
+}
+
+fn ocb_encrypt(
+    aes: &mut Aes128,
+    plain: &[u8],
+    nonce: &[u8; AES_BLOCK_SIZE],
+) -> AnyError<(Vec<u8>, [u8; AES_BLOCK_SIZE])> {
+    let mut delta = aes.encrypt(u128::from_be_bytes(*nonce))?;
+    let mut checksum = 0u128;
+    let mut encrypted = Vec::with_capacity(plain.len());
+
+    let mut remaining = plain;
+    while remaining.len() > AES_BLOCK_SIZE {
+        let (chunk, rest) = remaining.split_at(AES_BLOCK_SIZE);
+        let mut block = read_block(chunk);
+
+        // Counter-cryptanalysis described in section 9 of https://eprint.iacr.org/2019/311
+        // The second to last block must not look like a length block, so we flip a bit of it.
+        if rest.len() <= AES_BLOCK_SIZE && chunk[..AES_BLOCK_SIZE - 1].iter().all(|b| *b == 0) {
+            block ^= 1_u128 << 120;
+        }
+
+        delta = times_two(delta);
+        let tmp = aes.encrypt(delta ^ block)?;
+        encrypted.extend_from_slice(&(delta ^ tmp).to_be_bytes());
+        checksum ^= block;
+
+        remaining = rest;
+    }
+
+    delta = times_two(delta);
+    let pad = aes.encrypt(length_block(remaining.len()) ^ delta)?;
+
+    let mut last = pad.to_be_bytes();
+    last[..remaining.len()].copy_from_slice(remaining);
+    let last = u128::from_be_bytes(last);
+    checksum ^= last;
+    encrypted.extend_from_slice(&(last ^ pad).to_be_bytes()[..remaining.len()]);
+
+    delta = times_three(delta);
+    let tag = aes.encrypt(delta ^ checksum)?;
+
+    Ok((encrypted, tag.to_be_bytes()))
+}
+
+fn ocb_decrypt(
+    aes: &mut Aes128,
+    encrypted: &[u8],
+    nonce: &[u8; AES_BLOCK_SIZE],
+) -> AnyError<(Vec<u8>, [u8; AES_BLOCK_SIZE], bool)> {
+    let mut delta = aes.encrypt(u128::from_be_bytes(*nonce))?;
+    let mut checksum = 0u128;
+    let mut plain = Vec::with_capacity(encrypted.len());
+
+    let mut remaining = encrypted;
+    while remaining.len() > AES_BLOCK_SIZE {
+        let (chunk, rest) = remaining.split_at(AES_BLOCK_SIZE);
+
+        delta = times_two(delta);
+        let tmp = aes.decrypt(delta ^ read_block(chunk))?;
+        let block = delta ^ tmp;
+        plain.extend_from_slice(&block.to_be_bytes());
+        checksum ^= block;
+
+        remaining = rest;
+    }
+
+    delta = times_two(delta);
+    let pad = aes.encrypt(length_block(remaining.len()) ^ delta)?;
+
+    let mut last = [0u8; AES_BLOCK_SIZE];
+    last[..remaining.len()].copy_from_slice(remaining);
+    let last = u128::from_be_bytes(last) ^ pad;
+    checksum ^= last;
+
+    let last = last.to_be_bytes();
+    plain.extend_from_slice(&last[..remaining.len()]);
+
+    // An attacker would need the last block to equal `delta ^ len(128)`, see the comment in `ocb_encrypt`
+    let valid = last[..AES_BLOCK_SIZE - 1] != delta.to_be_bytes()[..AES_BLOCK_SIZE - 1];
+
+    delta = times_three(delta);
+    let tag = aes.encrypt(delta ^ checksum)?;
+
+    Ok((plain, tag.to_be_bytes(), valid))
+}
@@ -1,46 +1,90 @@
+pub mod crypt_state;
+pub mod ping_stats;
 pub mod threads;
 pub mod traits;
+pub mod udp;
+
+#[cfg(test)]
+mod tests;
 use crate::commands::utils::settings::GlobalSettings;
 use crate::connection::traits::Shutdown;
+use crate::errors::application_error::ApplicationError;
+use crate::errors::certificate_error::CertificateError;
 use crate::errors::AnyError;
-use crate::manager::user::UpdateableUserState;
+use crate::manager::acl::{permission, ChannelAcl};
+use crate::manager::ban_list::{ban_list_submission, BanEntry};
+use crate::manager::channel::UpdateableChannelState;
+use crate::manager::pending_action::PendingAction;
+use crate::manager::session::SessionState;
+use crate::manager::user::{UpdateableUserState, UserModeration};
+use crate::manager::user_list::RegisteredUser;
+use crate::manager::voice_target::{VoiceTarget, VOICE_TARGET_IDS};
 use crate::mumble;
-use crate::protocol::init_connection;
+use crate::protocol::serialize::message_container::FrontendMessage;
 use crate::protocol::stream_reader::StreamReader;
-use crate::utils::certificate_store::CertificateBuilder;
+use crate::protocol::{init_connection, update_access_tokens};
+use crate::utils::certificate_store::{CertificateBuilder, ClientIdentity};
 use crate::utils::file::read_image_as_thumbnail;
+use crate::utils::frontend::send_to_frontend;
 use crate::utils::messages::message_builder;
+use crate::utils::pinned_certificate_store::{
+    fingerprint, CertificateMismatch, PinStatus, PinnedCertificateStore,
+};
 use async_trait::async_trait;
 use base64::engine::general_purpose;
 use base64::Engine;
+use openssl::ssl::{SslConnector, SslMethod, SslVerifyMode};
+use openssl::stack::Stack;
+use openssl::x509::store::X509StoreBuilder;
+use openssl::x509::verify::X509VerifyParam;
+use openssl::x509::{X509StoreContext, X509};
 use std::collections::HashMap;
+use std::error::Error;
+use std::net::IpAddr;
+use std::pin::Pin;
 use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 use tauri::PackageInfo;
-use threads::{InputThread, MainThread, OutputThread, PingThread};
+use threads::{InputThread, MainThread, OutputThread, PingThread, UdpThread};
 use tokio::net::TcpStream;
 use tokio::sync::broadcast::{self, Receiver, Sender};
-use tokio::sync::Mutex;
+use tokio::sync::{watch, Mutex};
 use tokio::task::JoinHandle;
-use tokio_native_tls::native_tls::TlsConnector;
-use tracing::{info, trace};
+use tokio_openssl::SslStream;
+use tracing::{debug, info, trace, warn};
+use uuid::Uuid;
 
+use self::ping_stats::{current_timestamp, PingReply};
 use self::threads::ConnectionThread;
 
+pub type TlsStream = SslStream<TcpStream>;
+
 const QUEUE_SIZE: usize = 256;
 const BUFFER_SIZE: usize = 8192;
 const FANCY_MUMBLE_DATA_ID: &str = "fancy_mumble";
 
+#[derive(Debug, Clone)]
 struct ServerData {
     username: String,
     server_host: String,
     server_port: u16,
     identity: Option<String>,
+    password: Option<String>,
+    access_tokens: watch::Receiver<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Credentials {
+    pub password: Option<String>,
+    pub access_tokens: Vec<String>,
 }
 
 #[derive(Debug, Clone)]
 pub struct MessageChannels {
     pub message_channel: Sender<String>,
+    pub voice_channel: Sender<Vec<u8>>,
+    pub crypt_setup_channel: Sender<mumble::proto::CryptSetup>,
+    pub ping_channel: Sender<PingReply>,
 }
 
 #[derive(Debug, Clone)]
@@ -48,21 +92,30 @@ pub struct TextMessage {
     message: String,
     channel_id: Option<u32>,
     reciever: Option<u32>,
+    message_id: String,
+    timestamp: u64,
 }
 
 pub struct Connection {
     server_data: ServerData,
     tx_in: Sender<Vec<u8>>,
     tx_out: Sender<Vec<u8>>,
+    tx_voice_in: Sender<Vec<u8>>,
+    tx_reconnect: Sender<()>,
+    tx_access_tokens: watch::Sender<Vec<String>>,
+    tx_user_stats: watch::Sender<Option<u32>>,
 
     tx_message_channel: Sender<TextMessage>,
 
     running: Arc<AtomicBool>,
+    // set while the main thread tries to get the lost connection back
+    reconnecting: Arc<AtomicBool>,
     threads: HashMap<ConnectionThread, JoinHandle<()>>,
     message_channels: MessageChannels,
     package_info: PackageInfo,
     stream_reader: Arc<Mutex<Option<StreamReader>>>,
     settings_channel: Receiver<GlobalSettings>,
+    session: SessionState,
 }
 
 impl Connection {
@@ -71,15 +124,28 @@ impl Connection {
         server_port: u16,
         username: &str,
         identity: Option<String>,
+        credentials: Credentials,
         package_info: PackageInfo,
         settings_channel: Receiver<GlobalSettings>,
     ) -> Self {
         let (tx_in, _): (Sender<Vec<u8>>, Receiver<Vec<u8>>) = broadcast::channel(QUEUE_SIZE);
         let (tx_out, _): (Sender<Vec<u8>>, Receiver<Vec<u8>>) = broadcast::channel(QUEUE_SIZE);
+        let (tx_voice_in, _): (Sender<Vec<u8>>, Receiver<Vec<u8>>) = broadcast::channel(QUEUE_SIZE);
+        let (tx_reconnect, _): (Sender<()>, Receiver<()>) = broadcast::channel(QUEUE_SIZE);
+        let (tx_access_tokens, access_tokens) = watch::channel(credentials.access_tokens);
+        let (tx_user_stats, _) = watch::channel(None);
         let (tx_message_channel, _): (Sender<TextMessage>, Receiver<TextMessage>) =
             broadcast::channel(QUEUE_SIZE);
         let (message_channel, _): (Sender<String>, Receiver<String>) =
             broadcast::channel(QUEUE_SIZE);
+        let (voice_channel, _): (Sender<Vec<u8>>, Receiver<Vec<u8>>) =
+            broadcast::channel(QUEUE_SIZE);
+        let (crypt_setup_channel, _): (
+            Sender<mumble::proto::CryptSetup>,
+            Receiver<mumble::proto::CryptSetup>,
+        ) = broadcast::channel(QUEUE_SIZE);
+        let (ping_channel, _): (Sender<PingReply>, Receiver<PingReply>) =
+            broadcast::channel(QUEUE_SIZE);
 
         Self {
             package_info,
@@ -88,42 +154,33 @@ impl Connection {
                 server_host: server_host.to_string(),
                 server_port,
                 identity,
+                password: credentials.password,
+                access_tokens,
             },
             tx_in,
             tx_out,
+            tx_voice_in,
+            tx_reconnect,
+            tx_access_tokens,
+            tx_user_stats,
             tx_message_channel,
             running: Arc::new(AtomicBool::new(false)),
+            reconnecting: Arc::new(AtomicBool::new(false)),
             threads: HashMap::new(),
-            message_channels: MessageChannels { message_channel },
+            message_channels: MessageChannels {
+                message_channel,
+                voice_channel,
+                crypt_setup_channel,
+                ping_channel,
+            },
             stream_reader: Arc::new(Mutex::new(None)),
             settings_channel,
+            session: SessionState::default(),
         }
     }
 
-    async fn setup_connection(
-        &mut self,
-    ) -> AnyError<Option<tokio_native_tls::TlsStream<TcpStream>>> {
-        let server_uri = format!(
-            "{}:{}",
-            self.server_data.server_host, self.server_data.server_port
-        );
-
-        let mut certificate_store = CertificateBuilder::try_from(&self.server_data.identity)
-            .load_or_generate_new(true)
-            .store_to_project_dir(true)
-            .build()?;
-
-        //TODO: Check for ECDHE-RSA-AES256-GCM-SHA384
-        let socket = TcpStream::connect(server_uri).await?;
-        let cx = TlsConnector::builder()
-            .identity(certificate_store.get_client_certificate()?)
-            .danger_accept_invalid_certs(true)
-            .build()?;
-        let cx = tokio_native_tls::TlsConnector::from(cx);
-
-        Ok(Some(
-            cx.connect(&self.server_data.server_host, socket).await?,
-        ))
+    async fn setup_connection(&mut self) -> AnyError<Option<TlsStream>> {
+        Ok(Some(connect_tls(&self.server_data).await?))
     }
 
     pub async fn connect(&mut self) -> AnyError<()> {
@@ -131,14 +188,27 @@ impl Connection {
             self.running
                 .store(true, std::sync::atomic::Ordering::Relaxed);
         }
-        let stream = self.setup_connection().await?;
+        let stream = match self.setup_connection().await {
+            Ok(stream) => stream,
+            Err(e) => {
+                notify_certificate_mismatch(e.as_ref(), &self.message_channels.message_channel);
+                return Err(e);
+            }
+        };
 
         self.spawn_ping_thread();
         self.spawn_input_thread();
         self.spawn_output_thread();
+        self.spawn_udp_thread();
 
         self.init_main_thread(stream).await?;
-        init_connection(&self.server_data.username, &self.tx_out, &self.package_info);
+        init_connection(
+            &self.server_data.username,
+            self.server_data.password.clone(),
+            self.server_data.access_tokens.borrow().clone(),
+            &self.tx_out,
+            &self.package_info,
+        );
 
         Ok(())
     }
@@ -149,15 +219,388 @@ impl Connection {
         reciever: Option<u32>,
         message: &str,
     ) -> AnyError<()> {
+        // the message would be dropped with the old connection and never show up in the log
+        if self.reconnecting.load(std::sync::atomic::Ordering::Relaxed) {
+            return Err(Box::new(ApplicationError::new(
+                "Not connected to the server, trying to reconnect",
+            )));
+        }
+        let message = self
+            .session
+            .server_config
+            .read(|config| config.prepare_message(message))?;
+
+        // the output thread marks it as pending, once it is actually sent
+        let message_id = Uuid::new_v4().to_string();
         self.tx_message_channel.send(TextMessage {
-            message: message.to_string(),
+            message,
             channel_id,
             reciever,
+            message_id,
+            timestamp: current_timestamp()?,
         })?;
 
         Ok(())
     }
 
+    pub fn update_access_tokens(&self, access_tokens: Vec<String>) -> AnyError<()> {
+        // keep them around, so they are used again after a reconnect
+        self.tx_access_tokens.send_replace(access_tokens.clone());
+        update_access_tokens(access_tokens, &self.tx_out)?;
+
+        Ok(())
+    }
+
+    pub fn request_acl(&self, channel_id: u32) -> AnyError<()> {
+        let acl = mumble::proto::Acl {
+            channel_id,
+            query: Some(true),
+            ..Default::default()
+        };
+        self.tx_out.send(message_builder(&acl)?)?;
+
+        Ok(())
+    }
+
+    /// Sends the message and keeps the action, until the server confirmed or denied it
+    fn send_action(&self, action: PendingAction, message: Vec<u8>) -> AnyError<()> {
+        self.session
+            .pending_actions
+            .write(|actions| actions.send(action, &self.tx_out, message))
+    }
+
+    fn check_permission(&self, channel_id: u32, permission: u32, message: &str) -> AnyError<()> {
+        self.session
+            .check_permission(channel_id, permission, message)
+    }
+
+    pub fn submit_acl(&self, acl: ChannelAcl) -> AnyError<()> {
+        self.check_permission(
+            acl.channel_id,
+            permission::WRITE,
+            "Missing permission to edit the ACL of this channel",
+        )?;
+
+        let action = PendingAction::SubmitAcl {
+            channel_id: acl.channel_id,
+        };
+        let acl: mumble::proto::Acl = acl.into();
+        self.send_action(action, message_builder(&acl)?)?;
+
+        Ok(())
+    }
+
+    pub fn create_channel(&self, channel: UpdateableChannelState) -> AnyError<()> {
+        let action = channel.creation(&self.session)?;
+        let channel_state: mumble::proto::ChannelState = channel.into();
+        self.send_action(action, message_builder(&channel_state)?)?;
+
+        Ok(())
+    }
+
+    pub fn update_channel(&self, channel: UpdateableChannelState) -> AnyError<()> {
+        let action = channel.update(&self.session)?;
+        let channel_state: mumble::proto::ChannelState = channel.into();
+        self.send_action(action, message_builder(&channel_state)?)?;
+
+        Ok(())
+    }
+
+    pub fn remove_channel(&self, channel_id: u32) -> AnyError<()> {
+        self.check_permission(
+            channel_id,
+            permission::WRITE,
+            "Missing permission to delete this channel",
+        )?;
+
+        let action = PendingAction::RemoveChannel { channel_id };
+        let channel_remove = mumble::proto::ChannelRemove { channel_id };
+        self.send_action(action, message_builder(&channel_remove)?)?;
+
+        Ok(())
+    }
+
+    pub fn kick_user(&self, session: u32, reason: Option<String>) -> AnyError<()> {
+        // kicking and banning is only granted on the root channel
+        self.check_permission(0, permission::KICK, "Missing permission to kick users")?;
+
+        let action = PendingAction::KickUser { session };
+        let user_remove = mumble::proto::UserRemove {
+            session,
+            reason,
+            ban: Some(false),
+            ..Default::default()
+        };
+        self.send_action(action, message_builder(&user_remove)?)?;
+
+        Ok(())
+    }
+
+    pub fn ban_user(&self, session: u32, reason: Option<String>) -> AnyError<()> {
+        self.check_permission(0, permission::BAN, "Missing permission to ban users")?;
+
+        let action = PendingAction::BanUser { session };
+        let user_remove = mumble::proto::UserRemove {
+            session,
+            reason,
+            ban: Some(true),
+            ..Default::default()
+        };
+        self.send_action(action, message_builder(&user_remove)?)?;
+
+        Ok(())
+    }
+
+    pub fn moderate_user(&self, moderation: UserModeration) -> AnyError<()> {
+        // the server checks the permissions in the channel the user is in
+        let channel_id = self
+            .session
+            .user_channel(moderation.session)
+            .ok_or_else(|| ApplicationError::new("Unknown user"))?;
+
+        let changes_voice = moderation.mute.is_some()
+            || moderation.deaf.is_some()
+            || moderation.suppress.is_some()
+            || moderation.priority_speaker.is_some();
+        if changes_voice {
+            self.check_permission(
+                channel_id,
+                permission::MUTE_DEAFEN,
+                "Missing permission to mute or deafen users in this channel",
+            )?;
+        }
+        // the target channel is left to the server, it also allows the move if the moved user may enter it
+        if moderation.move_to.is_some() {
+            self.check_permission(
+                channel_id,
+                permission::MOVE,
+                "Missing permission to move users out of this channel",
+            )?;
+        }
+
+        let action = PendingAction::ModerateUser {
+            session: moderation.session,
+        };
+        let user_state: mumble::proto::UserState = moderation.into();
+        self.send_action(action, message_builder(&user_state)?)?;
+
+        Ok(())
+    }
+
+    pub fn request_ban_list(&self) -> AnyError<()> {
+        let ban_list = mumble::proto::BanList {
+            bans: Vec::new(),
+            query: Some(true),
+        };
+        self.tx_out.send(message_builder(&ban_list)?)?;
+
+        Ok(())
+    }
+
+    /// Replaces the ban list of the server, so `bans` has to contain all bans which should be kept.
+    /// Bans we couldn't show are kept as they are.
+    pub fn submit_ban_list(&self, bans: Vec<BanEntry>) -> AnyError<()> {
+        self.check_permission(
+            0,
+            permission::BAN,
+            "Missing permission to edit the ban list",
+        )?;
+
+        let ban_list = self
+            .session
+            .unreadable_bans
+            .read(|unreadable_bans| ban_list_submission(bans, unreadable_bans))?;
+        self.send_action(PendingAction::SubmitBanList, message_builder(&ban_list)?)?;
+
+        Ok(())
+    }
+
+    pub fn request_registered_users(&self) -> AnyError<()> {
+        self.check_permission(
+            0,
+            permission::REGISTER,
+            "Missing permission to list the registered users",
+        )?;
+
+        let user_list = mumble::proto::UserList { users: Vec::new() };
+        self.tx_out.send(message_builder(&user_list)?)?;
+
+        Ok(())
+    }
+
+    /// Renames a registered user, without a name the user gets deregistered
+    pub fn update_registered_user(&self, user_id: u32, name: Option<String>) -> AnyError<()> {
+        self.check_permission(
+            0,
+            permission::REGISTER,
+            "Missing permission to edit the registered users",
+        )?;
+
+        let action = PendingAction::UpdateUserList { user_id };
+        let user_list = mumble::proto::UserList {
+            users: vec![RegisteredUser {
+                user_id,
+                name,
+                ..Default::default()
+            }
+            .into()],
+        };
+        self.send_action(action, message_builder(&user_list)?)?;
+
+        Ok(())
+    }
+
+    /// Registers the certificate we are connected with under the current name
+    pub fn register_self(&self) -> AnyError<()> {
+        self.check_permission(
+            0,
+            permission::SELF_REGISTER,
+            "Missing permission to register on this server",
+        )?;
+        let user_state = mumble::proto::UserState {
+            user_id: Some(0),
+            ..Default::default()
+        };
+        self.send_action(PendingAction::RegisterSelf, message_builder(&user_state)?)?;
+
+        Ok(())
+    }
+
+    /// Requests the statistics of a user, they are refreshed with every ping until stopped
+    pub fn request_user_stats(&self, session: u32) -> AnyError<()> {
+        let user_stats = mumble::proto::UserStats {
+            session: Some(session),
+            stats_only: Some(false),
+            ..Default::default()
+        };
+        self.tx_out.send(message_builder(&user_stats)?)?;
+        self.tx_user_stats.send_replace(Some(session));
+
+        Ok(())
+    }
+
+    pub fn stop_user_stats(&self) {
+        self.tx_user_stats.send_replace(None);
+    }
+
+    /// Triggers a context action of the server for a user, a channel or the server itself
+    pub fn trigger_context_action(
+        &self,
+        action: &str,
+        session: Option<u32>,
+        channel_id: Option<u32>,
+    ) -> AnyError<()> {
+        self.session
+            .context_actions
+            .read(|actions| actions.check(action, session, channel_id))?;
+
+        let context_action = mumble::proto::ContextAction {
+            session,
+            channel_id,
+            action: action.to_string(),
+        };
+        self.tx_out.send(message_builder(&context_action)?)?;
+
+        Ok(())
+    }
+
+    fn check_listen_permission(&self, channel_ids: &[u32]) -> AnyError<()> {
+        for channel_id in channel_ids {
+            self.check_permission(
+                *channel_id,
+                permission::LISTEN,
+                "Missing permission to listen to this channel",
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Hears the given channels in addition to the one we are in
+    pub fn listen_to_channels(&self, channel_ids: Vec<u32>) -> AnyError<()> {
+        self.check_listen_permission(&channel_ids)?;
+
+        let action = PendingAction::ListenToChannels {
+            channel_ids: channel_ids.clone(),
+        };
+        let user_state = mumble::proto::UserState {
+            listening_channel_add: channel_ids,
+            ..Default::default()
+        };
+        self.send_action(action, message_builder(&user_state)?)?;
+
+        Ok(())
+    }
+
+    pub fn stop_listening_to_channels(&self, channel_ids: Vec<u32>) -> AnyError<()> {
+        self.check_listen_permission(&channel_ids)?;
+
+        let action = PendingAction::StopListeningToChannels {
+            channel_ids: channel_ids.clone(),
+        };
+        let user_state = mumble::proto::UserState {
+            listening_channel_remove: channel_ids,
+            ..Default::default()
+        };
+        self.send_action(action, message_builder(&user_state)?)?;
+
+        Ok(())
+    }
+
+    /// Sets the linear volume factor of a listened channel, the server keeps it for registered users
+    pub fn set_listener_volume(&self, channel_id: u32, volume: f32) -> AnyError<()> {
+        self.check_listen_permission(&[channel_id])?;
+
+        let user_state = mumble::proto::UserState {
+            listening_volume_adjustment: vec![mumble::proto::user_state::VolumeAdjustment {
+                listening_channel: Some(channel_id),
+                volume_adjustment: Some(volume),
+            }],
+            ..Default::default()
+        };
+        self.send_action(
+            PendingAction::SetListenerVolume { channel_id },
+            message_builder(&user_state)?,
+        )?;
+
+        Ok(())
+    }
+
+    /// Registers the users and channels we talk to while the voice target is active
+    pub fn set_voice_target(&self, voice_target: VoiceTarget) -> AnyError<()> {
+        if !VOICE_TARGET_IDS.contains(&voice_target.id) {
+            return Err(Box::new(ApplicationError::new(
+                format!("Invalid voice target id {}", voice_target.id).as_str(),
+            )));
+        }
+
+        let voice_target: mumble::proto::VoiceTarget = voice_target.into();
+        // kept, so the message router registers it again after a reconnect
+        self.session
+            .voice_targets
+            .write(|targets| targets.register(&voice_target));
+        self.tx_out.send(message_builder(&voice_target)?)?;
+
+        Ok(())
+    }
+
+    pub fn remove_voice_target(&self, id: u32) -> AnyError<()> {
+        self.set_voice_target(VoiceTarget {
+            id,
+            ..Default::default()
+        })
+    }
+
+    pub fn query_permissions(&self, channel_id: u32) -> AnyError<()> {
+        let permission_query = mumble::proto::PermissionQuery {
+            channel_id: Some(channel_id),
+            ..Default::default()
+        };
+        self.tx_out.send(message_builder(&permission_query)?)?;
+
+        Ok(())
+    }
+
     pub fn get_message_channel(&self) -> Receiver<String> {
         self.message_channels.message_channel.subscribe()
     }
@@ -186,16 +629,21 @@ impl Connection {
             "background" => {
                 let mime_type = image.format;
                 let background = general_purpose::STANDARD.encode(image_data);
-                let img: Option<String> = Some(format!(
-                    "<img src='data:image/{mime_type};base64,{background}' />"
-                ));
+                let img = format!("<img src='data:image/{mime_type};base64,{background}' />");
+                // the comment is limited like an image message
+                let img = self
+                    .session
+                    .server_config
+                    .read(|config| config.prepare_message(&img))?;
 
                 let set_profile_background = mumble::proto::UserState {
-                    comment: img,
+                    comment: Some(img),
                     ..Default::default()
                 };
-                self.tx_out
-                    .send(message_builder(&set_profile_background)?)?;
+                self.send_action(
+                    PendingAction::SetUserImage,
+                    message_builder(&set_profile_background)?,
+                )?;
             }
             "profile" => {
                 let image_vec = Some(image_data);
@@ -203,8 +651,10 @@ impl Connection {
                     texture: image_vec,
                     ..Default::default()
                 };
-                self.tx_out
-                    .send(message_builder(&set_profile_background)?)?;
+                self.send_action(
+                    PendingAction::SetUserImage,
+                    message_builder(&set_profile_background)?,
+                )?;
             }
             _ => {}
         }
@@ -238,12 +688,133 @@ impl Connection {
             listening_channel_remove: Vec::new(),
             listening_volume_adjustment: Vec::new(),
         };
-        self.tx_out.send(message_builder(&updated_state)?)?;
+        self.send_action(
+            PendingAction::UpdateUserState,
+            message_builder(&updated_state)?,
+        )?;
 
         Ok(())
     }
 }
 
+async fn tls_handshake(server_data: &ServerData, identity: &ClientIdentity) -> AnyError<TlsStream> {
+    let server_uri = format!("{}:{}", server_data.server_host, server_data.server_port);
+
+    //TODO: Check for ECDHE-RSA-AES256-GCM-SHA384
+    let socket = TcpStream::connect(server_uri).await?;
+    let mut connector = SslConnector::builder(SslMethod::tls_client())?;
+    connector.set_certificate(&identity.certificate)?;
+    connector.set_private_key(&identity.private_key)?;
+    // the certificate is verified afterwards, either by the system CA store or by its pinned fingerprint
+    connector.set_verify(SslVerifyMode::NONE);
+    let ssl = connector
+        .build()
+        .configure()?
+        .verify_hostname(false)
+        .into_ssl(&server_data.server_host)?;
+
+    let mut stream = SslStream::new(ssl, socket)?;
+    Pin::new(&mut stream).connect().await?;
+
+    Ok(stream)
+}
+
+/// The leaf certificate of the server and the intermediates it sent along
+fn peer_certificates(stream: &TlsStream) -> AnyError<(X509, Vec<X509>)> {
+    let ssl = stream.ssl();
+    let certificate = ssl
+        .peer_certificate()
+        .ok_or_else(|| CertificateError::new("Server did not send a certificate"))?;
+    let chain = ssl
+        .peer_cert_chain()
+        .map(|chain| chain.iter().map(ToOwned::to_owned).collect())
+        .unwrap_or_default();
+
+    Ok((certificate, chain))
+}
+
+/// Checks the certificate chain the server presented against the system CA store and the host name
+fn trusted_by_system(certificate: &X509, presented_chain: &[X509], host: &str) -> AnyError<bool> {
+    let mut param = X509VerifyParam::new()?;
+    match host.parse::<IpAddr>() {
+        Ok(ip) => param.set_ip(ip)?,
+        Err(_) => param.set_host(host)?,
+    }
+    let mut store = X509StoreBuilder::new()?;
+    store.set_default_paths()?;
+    store.set_param(&param)?;
+    let store = store.build();
+
+    let mut chain = Stack::new()?;
+    for intermediate in presented_chain {
+        chain.push(intermediate.clone())?;
+    }
+    let mut context = X509StoreContext::new()?;
+
+    Ok(context.init(&store, certificate, &chain, |context| context.verify_cert())?)
+}
+
+async fn connect_tls(server_data: &ServerData) -> AnyError<TlsStream> {
+    let host = &server_data.server_host;
+    let port = server_data.server_port;
+
+    let mut certificate_store = CertificateBuilder::try_from(&server_data.identity)
+        .load_or_generate_new(true)
+        .store_to_project_dir(true)
+        .build()?;
+    let identity = certificate_store.get_client_certificate()?;
+
+    let stream = tls_handshake(server_data, &identity).await?;
+    let (certificate, chain) = peer_certificates(&stream)?;
+    let fingerprint = fingerprint(&certificate.to_der()?);
+    let mut pinned_certificates = PinnedCertificateStore::load()?;
+
+    // a chain which is valid for the system CA store is always trusted, even if it differs from the pinned one
+    match trusted_by_system(&certificate, &chain, host) {
+        Ok(true) => {
+            pinned_certificates.pin(host, port, &fingerprint)?;
+            return Ok(stream);
+        }
+        Ok(false) => debug!("Server certificate of {host}:{port} is not trusted by the system"),
+        Err(e) => {
+            warn!("Unable to check the certificate of {host}:{port} with the system CA store: {e}")
+        }
+    }
+
+    match pinned_certificates.check(host, port, &fingerprint) {
+        PinStatus::Trusted => {}
+        PinStatus::FirstUse => {
+            info!("First connection to {host}:{port}, trusting certificate {fingerprint}");
+            pinned_certificates.pin(host, port, &fingerprint)?;
+        }
+        PinStatus::Mismatch { pinned } => {
+            warn!("Certificate of {host}:{port} changed from {pinned} to {fingerprint}");
+            pinned_certificates.remember_presented(host, port, &fingerprint)?;
+            return Err(Box::new(CertificateError::mismatch(CertificateMismatch {
+                host: host.clone(),
+                port,
+                pinned,
+                presented: fingerprint,
+            })));
+        }
+    }
+
+    Ok(stream)
+}
+
+/// Tells the frontend about a changed server certificate, so the user can decide to accept it
+fn notify_certificate_mismatch(error: &(dyn Error + 'static), frontend_channel: &Sender<String>) {
+    if let Some(mismatch) = error
+        .downcast_ref::<CertificateError>()
+        .and_then(CertificateError::certificate_mismatch)
+    {
+        send_to_frontend(
+            frontend_channel,
+            &FrontendMessage::new("certificate_mismatch", mismatch),
+        );
+    }
+}
+
 #[async_trait]
 impl Shutdown for Connection {
     async fn shutdown(&mut self) -> AnyError<()> {
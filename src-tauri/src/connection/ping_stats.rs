@@ -0,0 +1,142 @@
+use std::{
+    collections::VecDeque,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+
+use crate::{errors::AnyError, mumble};
+
+use super::crypt_state::PacketStats;
+
+const WINDOW_SIZE: usize = 24;
+
+#[derive(Debug, Clone, Copy)]
+pub enum PingReply {
+    Tcp {
+        timestamp: u64,
+    },
+    Udp {
+        timestamp: u64,
+        packets: PacketStats,
+    },
+}
+
+pub fn current_timestamp() -> AnyError<u64> {
+    Ok(u64::try_from(
+        SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis(),
+    )?)
+}
+
+/// Average and variance over the last few round trip times
+#[derive(Debug, Default)]
+pub struct RollingStatistics {
+    samples: VecDeque<f32>,
+    packets: u32,
+}
+
+impl RollingStatistics {
+    #[allow(clippy::cast_precision_loss)] // a round trip time doesn't need more than f32 precision
+    pub fn add_sample(&mut self, sent_timestamp: u64, now: u64) {
+        let round_trip = now.saturating_sub(sent_timestamp) as f32;
+
+        if self.samples.len() == WINDOW_SIZE {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(round_trip);
+        self.packets = self.packets.saturating_add(1);
+    }
+
+    pub const fn packets(&self) -> u32 {
+        self.packets
+    }
+
+    #[allow(clippy::cast_precision_loss)] // the window is way smaller than f32::MAX
+    pub fn average(&self) -> f32 {
+        if self.samples.is_empty() {
+            return 0.0;
+        }
+
+        self.samples.iter().sum::<f32>() / self.samples.len() as f32
+    }
+
+    #[allow(clippy::cast_precision_loss)] // the window is way smaller than f32::MAX
+    pub fn variance(&self) -> f32 {
+        if self.samples.len() < 2 {
+            return 0.0;
+        }
+
+        let average = self.average();
+        self.samples
+            .iter()
+            .map(|sample| (sample - average).powi(2))
+            .sum::<f32>()
+            / (self.samples.len() - 1) as f32
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+pub struct ConnectionStats {
+    pub tcp_packets: u32,
+    pub tcp_ping_avg: f32,
+    pub tcp_ping_var: f32,
+    pub udp_packets: u32,
+    pub udp_ping_avg: f32,
+    pub udp_ping_var: f32,
+    pub good: u32,
+    pub late: u32,
+    pub lost: u32,
+    pub resync: u32,
+}
+
+#[derive(Debug, Default)]
+pub struct PingStatistics {
+    tcp: RollingStatistics,
+    udp: RollingStatistics,
+    packets: PacketStats,
+}
+
+impl PingStatistics {
+    pub fn handle_reply(&mut self, reply: PingReply, now: u64) {
+        match reply {
+            PingReply::Tcp { timestamp } => self.tcp.add_sample(timestamp, now),
+            PingReply::Udp { timestamp, packets } => {
+                self.udp.add_sample(timestamp, now);
+                self.packets = packets;
+            }
+        }
+    }
+
+    pub fn stats(&self) -> ConnectionStats {
+        ConnectionStats {
+            tcp_packets: self.tcp.packets(),
+            tcp_ping_avg: self.tcp.average(),
+            tcp_ping_var: self.tcp.variance(),
+            udp_packets: self.udp.packets(),
+            udp_ping_avg: self.udp.average(),
+            udp_ping_var: self.udp.variance(),
+            good: self.packets.good,
+            late: self.packets.late,
+            lost: self.packets.lost,
+            resync: self.packets.resync,
+        }
+    }
+
+    pub fn create_ping(&self, timestamp: u64) -> mumble::proto::Ping {
+        let stats = self.stats();
+
+        mumble::proto::Ping {
+            timestamp: Some(timestamp),
+            good: Some(stats.good),
+            late: Some(stats.late),
+            lost: Some(stats.lost),
+            resync: Some(stats.resync),
+            tcp_packets: Some(stats.tcp_packets),
+            tcp_ping_avg: Some(stats.tcp_ping_avg),
+            tcp_ping_var: Some(stats.tcp_ping_var),
+            udp_packets: Some(stats.udp_packets),
+            udp_ping_avg: Some(stats.udp_ping_avg),
+            udp_ping_var: Some(stats.udp_ping_var),
+        }
+    }
+}
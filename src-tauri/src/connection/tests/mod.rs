@@ -0,0 +1,10 @@
+macro_rules! aw {
+    ($e:expr) => {
+        tokio_test::block_on($e)
+    };
+}
+
+mod test_crypt_state;
+mod test_ping_stats;
+mod test_reconnect;
+mod test_udp_transport;
@@ -0,0 +1,195 @@
+mod tests {
+    use crate::connection::crypt_state::{CryptState, PacketStats};
+
+    const KEY: [u8; 16] = [
+        0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0A, 0x0B, 0x0C, 0x0D, 0x0E,
+        0x0F,
+    ];
+    const CLIENT_NONCE: [u8; 16] = [0x42; 16];
+    const SERVER_NONCE: [u8; 16] = [0x13; 16];
+
+    fn create_pair() -> (CryptState, CryptState) {
+        let mut client = CryptState::new();
+        client
+            .set_key(&KEY, &CLIENT_NONCE, &SERVER_NONCE)
+            .expect("Failed to set client key");
+        let mut server = CryptState::new();
+        server
+            .set_key(&KEY, &SERVER_NONCE, &CLIENT_NONCE)
+            .expect("Failed to set server key");
+
+        (client, server)
+    }
+
+    // the nonce is incremented before encryption, so the first packet is encrypted with 00..0F
+    fn create_vector_state() -> CryptState {
+        let mut nonce = KEY;
+        nonce[0] = 0xFF;
+        nonce[1] = 0x00;
+
+        let mut state = CryptState::new();
+        state
+            .set_key(&KEY, &nonce, &KEY)
+            .expect("Failed to set key");
+        state
+    }
+
+    #[test]
+    fn test_uninitialized_state() {
+        let mut state = CryptState::new();
+        assert!(!state.is_valid());
+        assert!(state.encrypt(&[1, 2, 3]).is_err());
+        assert!(state.decrypt(&[1, 2, 3, 4, 5]).is_err());
+    }
+
+    #[test]
+    fn test_invalid_key() {
+        let mut state = CryptState::new();
+        assert!(state
+            .set_key(&[0; 8], &CLIENT_NONCE, &SERVER_NONCE)
+            .is_err());
+        assert!(state.set_key(&KEY, &[0; 8], &SERVER_NONCE).is_err());
+        assert!(!state.is_valid());
+    }
+
+    #[test]
+    fn test_empty_vector() {
+        let mut state = create_vector_state();
+        let packet = state.encrypt(&[]).expect("Failed to encrypt");
+
+        assert_eq!(packet, vec![0x00, 0xBF, 0x31, 0x08]);
+    }
+
+    #[test]
+    fn test_block_vector() {
+        let mut state = create_vector_state();
+        let source = (0u8..40).collect::<Vec<u8>>();
+        let packet = state.encrypt(&source).expect("Failed to encrypt");
+
+        let expected_ciphertext = [
+            0xF7, 0x5D, 0x6B, 0xC8, 0xB4, 0xDC, 0x8D, 0x66, 0xB8, 0x36, 0xA2, 0xB0, 0x8B, 0x32,
+            0xA6, 0x36, 0x9F, 0x1C, 0xD3, 0xC5, 0x22, 0x8D, 0x79, 0xFD, 0x6C, 0x26, 0x7F, 0x5F,
+            0x6A, 0xA7, 0xB2, 0x31, 0xC7, 0xDF, 0xB9, 0xD5, 0x99, 0x51, 0xAE, 0x9C,
+        ];
+        assert_eq!(packet[..4], [0x00, 0x9D, 0xB0, 0xCD]);
+        assert_eq!(packet[4..], expected_ciphertext);
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let (mut client, mut server) = create_pair();
+
+        for length in [0, 1, 15, 16, 17, 31, 32, 33, 127, 960] {
+            let source = (0..length)
+                .map(|i: usize| u8::try_from(i % 251).unwrap_or_default())
+                .collect::<Vec<u8>>();
+            let packet = client.encrypt(&source).expect("Failed to encrypt");
+            assert_eq!(packet.len(), source.len() + 4);
+
+            let decrypted = server.decrypt(&packet).expect("Failed to decrypt");
+            assert_eq!(decrypted, source);
+        }
+
+        assert_eq!(server.stats().good, 10);
+    }
+
+    #[test]
+    fn test_tampered_packet() {
+        let (mut client, mut server) = create_pair();
+        let mut packet = client.encrypt(b"fancy mumble").expect("Failed to encrypt");
+        packet[6] ^= 0x01;
+
+        assert!(server.decrypt(&packet).is_err());
+        assert_eq!(server.stats().good, 0);
+    }
+
+    #[test]
+    fn test_replayed_packet() {
+        let (mut client, mut server) = create_pair();
+        let first = client.encrypt(b"first").expect("Failed to encrypt");
+        let second = client.encrypt(b"second").expect("Failed to encrypt");
+
+        assert!(server.decrypt(&first).is_ok());
+        assert!(server.decrypt(&second).is_ok());
+        assert!(server.decrypt(&first).is_err());
+        assert!(server.decrypt(&second).is_err());
+    }
+
+    #[test]
+    fn test_lost_and_late_packets() {
+        let (mut client, mut server) = create_pair();
+        let packets = (0..5u8)
+            .map(|i| client.encrypt(&[i; 20]).expect("Failed to encrypt"))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            server.decrypt(&packets[0]).expect("Failed to decrypt"),
+            [0; 20]
+        );
+        assert_eq!(
+            server.decrypt(&packets[3]).expect("Failed to decrypt"),
+            [3; 20]
+        );
+        assert_eq!(
+            server.stats(),
+            PacketStats {
+                good: 2,
+                late: 0,
+                lost: 2,
+                resync: 0
+            }
+        );
+
+        assert_eq!(
+            server.decrypt(&packets[1]).expect("Failed to decrypt"),
+            [1; 20]
+        );
+        assert_eq!(
+            server.decrypt(&packets[4]).expect("Failed to decrypt"),
+            [4; 20]
+        );
+        assert_eq!(
+            server.stats(),
+            PacketStats {
+                good: 4,
+                late: 1,
+                lost: 1,
+                resync: 0
+            }
+        );
+    }
+
+    #[test]
+    fn test_nonce_wraparound() {
+        let (mut client, mut server) = create_pair();
+
+        for i in 0..600u32 {
+            let source = i.to_be_bytes();
+            let packet = client.encrypt(&source).expect("Failed to encrypt");
+            assert_eq!(server.decrypt(&packet).expect("Failed to decrypt"), source);
+        }
+
+        assert_eq!(server.stats().lost, 0);
+    }
+
+    #[test]
+    fn test_resync() {
+        let (mut client, mut server) = create_pair();
+        let packet = client.encrypt(b"resync").expect("Failed to encrypt");
+
+        server
+            .set_decrypt_iv(&[0; 16])
+            .expect("Failed to set decrypt iv");
+        assert!(server.decrypt(&packet).is_err());
+
+        server
+            .set_decrypt_iv(&CLIENT_NONCE)
+            .expect("Failed to set decrypt iv");
+        assert_eq!(
+            server.decrypt(&packet).expect("Failed to decrypt"),
+            b"resync"
+        );
+        // only the transport counts the requests, a new nonce isn't a resync on its own
+        assert_eq!(server.stats().resync, 0);
+    }
+}
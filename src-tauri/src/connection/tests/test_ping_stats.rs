@@ -0,0 +1,78 @@
+mod tests {
+    use crate::connection::{
+        crypt_state::PacketStats,
+        ping_stats::{PingReply, PingStatistics, RollingStatistics},
+    };
+
+    #[test]
+    fn test_empty_statistics() {
+        let statistics = RollingStatistics::default();
+
+        assert_eq!(statistics.packets(), 0);
+        assert!(statistics.average().abs() < f32::EPSILON);
+        assert!(statistics.variance().abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn test_average_and_variance() {
+        let mut statistics = RollingStatistics::default();
+        for round_trip in [10, 20, 30, 40] {
+            statistics.add_sample(1000, 1000 + round_trip);
+        }
+
+        assert_eq!(statistics.packets(), 4);
+        assert!((statistics.average() - 25.0).abs() < f32::EPSILON);
+        assert!((statistics.variance() - 166.666_67).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_rolling_window() {
+        let mut statistics = RollingStatistics::default();
+        for _ in 0..100 {
+            statistics.add_sample(0, 500);
+        }
+        for _ in 0..24 {
+            statistics.add_sample(0, 20);
+        }
+
+        assert_eq!(statistics.packets(), 124);
+        assert!((statistics.average() - 20.0).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn test_clock_skew() {
+        let mut statistics = RollingStatistics::default();
+        statistics.add_sample(2000, 1000);
+
+        assert!(statistics.average().abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn test_ping_message() {
+        let mut statistics = PingStatistics::default();
+        statistics.handle_reply(PingReply::Tcp { timestamp: 100 }, 150);
+        statistics.handle_reply(
+            PingReply::Udp {
+                timestamp: 100,
+                packets: PacketStats {
+                    good: 12,
+                    late: 2,
+                    lost: 3,
+                    resync: 1,
+                },
+            },
+            130,
+        );
+
+        let ping = statistics.create_ping(200);
+        assert_eq!(ping.timestamp, Some(200));
+        assert_eq!(ping.tcp_packets, Some(1));
+        assert_eq!(ping.udp_packets, Some(1));
+        assert!((ping.tcp_ping_avg() - 50.0).abs() < f32::EPSILON);
+        assert!((ping.udp_ping_avg() - 30.0).abs() < f32::EPSILON);
+        assert_eq!(ping.good, Some(12));
+        assert_eq!(ping.late, Some(2));
+        assert_eq!(ping.lost, Some(3));
+        assert_eq!(ping.resync, Some(1));
+    }
+}
@@ -0,0 +1,66 @@
+mod tests {
+    use std::time::Duration;
+
+    use tokio::sync::broadcast;
+
+    use crate::{
+        connection::threads::{input_thread::take_pending, reconnect_delay},
+        mumble,
+        protocol::stream_reader::FrameBuffer,
+        utils::messages::{message_builder, MessageTypes},
+    };
+
+    fn frame(message: &str) -> Vec<u8> {
+        message_builder(&mumble::proto::TextMessage {
+            message: message.to_string(),
+            ..Default::default()
+        })
+        .expect("Failed to build message")
+    }
+
+    #[test]
+    fn test_delay_doubles() {
+        assert_eq!(reconnect_delay(1), Duration::from_secs(1));
+        assert_eq!(reconnect_delay(2), Duration::from_secs(2));
+        assert_eq!(reconnect_delay(3), Duration::from_secs(4));
+        assert_eq!(reconnect_delay(6), Duration::from_secs(32));
+    }
+
+    #[test]
+    fn test_delay_is_capped() {
+        assert_eq!(reconnect_delay(7), Duration::from_secs(60));
+        assert_eq!(reconnect_delay(10), Duration::from_secs(60));
+        assert_eq!(reconnect_delay(u32::MAX), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn test_partial_frame_is_dropped_on_reconnect() {
+        let (tx_in, mut rx_in) = broadcast::channel(8);
+        let mut frames = FrameBuffer::default();
+
+        // the old stream broke in the middle of a frame, the reconnect follows right after
+        let old = frame("lost");
+        tx_in
+            .send(old[..old.len() / 2].to_vec())
+            .expect("Failed to send");
+
+        for mut data in take_pending(&mut rx_in) {
+            frames.push(&mut data);
+            assert!(frames.try_read().is_none());
+        }
+        frames.clear();
+
+        let mut fresh = frame("hello");
+        frames.push(&mut fresh);
+
+        let message = frames.try_read().expect("Fresh frame wasn't read");
+        assert_eq!(message.message_type, MessageTypes::TextMessage);
+        let text = message
+            .message_data
+            .downcast::<mumble::proto::TextMessage>()
+            .expect("Wrong message type");
+        assert_eq!(text.message, "hello");
+        assert!(frames.try_read().is_none());
+        assert!(take_pending(&mut rx_in).is_empty());
+    }
+}
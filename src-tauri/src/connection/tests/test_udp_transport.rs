@@ -0,0 +1,204 @@
+mod tests {
+    use std::{
+        net::{Ipv4Addr, SocketAddr},
+        time::Duration,
+    };
+
+    use tokio::net::UdpSocket;
+
+    use crate::{
+        connection::udp::{UdpPacket, UdpTransport},
+        mumble,
+        protocol::VoiceFormat,
+    };
+
+    const KEY: [u8; 16] = [0x17; 16];
+    const NONCE: [u8; 16] = [0x23; 16];
+
+    // with identical nonces we are able to decrypt our own packets, so a plain echo server is enough
+    async fn create_echo_pair() -> (UdpTransport, UdpSocket) {
+        let echo = UdpSocket::bind(SocketAddr::from((Ipv4Addr::LOCALHOST, 0)))
+            .await
+            .expect("Failed to bind echo socket");
+        let mut transport = UdpTransport::connect_to(
+            echo.local_addr()
+                .expect("Failed to get echo socket address"),
+        )
+        .await
+        .expect("Failed to connect transport");
+
+        let crypt_setup = mumble::proto::CryptSetup {
+            key: Some(KEY.to_vec()),
+            client_nonce: Some(NONCE.to_vec()),
+            server_nonce: Some(NONCE.to_vec()),
+        };
+        let reply = transport
+            .apply_crypt_setup(&crypt_setup)
+            .expect("Failed to apply crypt setup");
+        assert!(reply.is_none());
+
+        (transport, echo)
+    }
+
+    async fn echo_once(echo: &UdpSocket) {
+        let mut buffer = [0u8; 1024];
+        let (size, address) = echo
+            .recv_from(&mut buffer)
+            .await
+            .expect("Failed to receive on echo socket");
+        echo.send_to(&buffer[..size], address)
+            .await
+            .expect("Failed to echo packet");
+    }
+
+    #[test]
+    fn test_ping_activates_transport() {
+        aw!(async {
+            let (mut transport, echo) = create_echo_pair().await;
+            assert!(!transport.is_active());
+
+            transport.send_ping().await.expect("Failed to send ping");
+            echo_once(&echo).await;
+
+            let mut buffer = [0u8; 1024];
+            let size = transport
+                .socket()
+                .recv(&mut buffer)
+                .await
+                .expect("Failed to receive ping");
+            let packet = transport.decode(&buffer[..size]).expect("Failed to decode");
+
+            assert!(matches!(packet, UdpPacket::Ping(_)));
+            assert!(transport.is_active());
+        });
+    }
+
+    #[test]
+    fn test_protobuf_ping_activates_transport() {
+        aw!(async {
+            let (mut transport, echo) = create_echo_pair().await;
+            transport.set_voice_format(VoiceFormat::Protobuf);
+
+            transport.send_ping().await.expect("Failed to send ping");
+            echo_once(&echo).await;
+
+            let mut buffer = [0u8; 1024];
+            let size = transport
+                .socket()
+                .recv(&mut buffer)
+                .await
+                .expect("Failed to receive ping");
+            let packet = transport.decode(&buffer[..size]).expect("Failed to decode");
+
+            assert!(matches!(packet, UdpPacket::Ping(timestamp) if timestamp > 0));
+            assert!(transport.is_active());
+        });
+    }
+
+    #[test]
+    fn test_voice_roundtrip() {
+        aw!(async {
+            let (mut transport, echo) = create_echo_pair().await;
+            let voice = vec![0x80, 0x05, 0x01, 0x02, 0x03, 0x04];
+
+            transport.send_voice(&voice).await.expect("Failed to send");
+            echo_once(&echo).await;
+
+            let mut buffer = [0u8; 1024];
+            let size = transport
+                .socket()
+                .recv(&mut buffer)
+                .await
+                .expect("Failed to receive voice");
+
+            match transport.decode(&buffer[..size]) {
+                Ok(UdpPacket::Voice(data)) => assert_eq!(data, voice),
+                _ => panic!("Expected a voice packet"),
+            }
+            assert_eq!(transport.crypt_state().stats().good, 1);
+        });
+    }
+
+    #[test]
+    fn test_header_is_parsed_in_voice_format() {
+        aw!(async {
+            let (mut transport, echo) = create_echo_pair().await;
+            // legacy CELT-alpha voice to target 1 shares its header with a protobuf ping
+            let voice = vec![0x01, 0x05, 0x01, 0x02];
+
+            transport.send_voice(&voice).await.expect("Failed to send");
+            echo_once(&echo).await;
+
+            let mut buffer = [0u8; 1024];
+            let size = transport
+                .socket()
+                .recv(&mut buffer)
+                .await
+                .expect("Failed to receive voice");
+
+            match transport.decode(&buffer[..size]) {
+                Ok(UdpPacket::Voice(data)) => assert_eq!(data, voice),
+                _ => panic!("Expected a voice packet"),
+            }
+            assert!(!transport.is_active());
+        });
+    }
+
+    #[test]
+    fn test_unanswered_pings_disable_transport() {
+        aw!(async {
+            let (mut transport, echo) = create_echo_pair().await;
+
+            transport.send_ping().await.expect("Failed to send ping");
+            echo_once(&echo).await;
+            let mut buffer = [0u8; 1024];
+            let size = transport
+                .socket()
+                .recv(&mut buffer)
+                .await
+                .expect("Failed to receive ping");
+            transport.decode(&buffer[..size]).expect("Failed to decode");
+            assert!(transport.is_active());
+
+            for _ in 0..4 {
+                transport.send_ping().await.expect("Failed to send ping");
+            }
+            assert!(!transport.is_active());
+        });
+    }
+
+    #[test]
+    fn test_resync_requested_once_per_timeout() {
+        aw!(async {
+            let (mut transport, _echo) = create_echo_pair().await;
+            let last_good = transport.crypt_state().last_good();
+
+            assert!(!transport.needs_resync_at(last_good + Duration::from_secs(4)));
+            assert_eq!(transport.crypt_state().stats().resync, 0);
+
+            assert!(transport.needs_resync_at(last_good + Duration::from_secs(6)));
+            assert_eq!(transport.crypt_state().stats().resync, 1);
+
+            // still failing, but we already asked
+            assert!(!transport.needs_resync_at(last_good + Duration::from_secs(10)));
+            assert_eq!(transport.crypt_state().stats().resync, 1);
+
+            assert!(transport.needs_resync_at(last_good + Duration::from_secs(12)));
+            assert_eq!(transport.crypt_state().stats().resync, 2);
+        });
+    }
+
+    #[test]
+    fn test_nonce_request() {
+        aw!(async {
+            let (mut transport, _echo) = create_echo_pair().await;
+            let reply = transport
+                .apply_crypt_setup(&mumble::proto::CryptSetup::default())
+                .expect("Failed to apply crypt setup")
+                .expect("Expected a nonce reply");
+
+            assert_eq!(reply.client_nonce, Some(NONCE.to_vec()));
+            assert!(reply.key.is_none());
+        });
+    }
+}
@@ -1,32 +1,64 @@
 use std::sync::atomic::Ordering;
 
 use crate::connection::Connection;
+use crate::manager::text_message::SentMessage;
 use crate::protocol::message_router::MessageRouter;
 use crate::protocol::stream_reader::StreamReader;
+use crate::utils::chat_history::ChatHistory;
 use tokio::select;
+use tokio::sync::broadcast::{error::TryRecvError, Receiver};
 use tokio::time;
+use tracing::error;
 
 use super::{ConnectionThread, InputThread, DEADMAN_INTERVAL};
 
+/// Data of the old stream which was sent before the reconnect, but not handled yet.
+///
+/// `select!` picks a random ready branch, so the reconnect may be seen first and has to
+/// handle these bytes itself, otherwise a partial frame would end up in the new session.
+pub fn take_pending(rx_in: &mut Receiver<Vec<u8>>) -> Vec<Vec<u8>> {
+    let mut pending = Vec::new();
+    loop {
+        match rx_in.try_recv() {
+            Ok(data) => pending.push(data),
+            Err(TryRecvError::Lagged(_)) => {}
+            Err(_) => return pending,
+        }
+    }
+}
+
 impl InputThread for Connection {
     // reader can'T be moved further in, because otherwise message_reader Result type is causing issues
     #[allow(clippy::significant_drop_tightening)]
     fn spawn_input_thread(&mut self) {
         let mut rx_in = self.tx_in.subscribe();
+        let mut rx_voice_in = self.tx_voice_in.subscribe();
+        let mut rx_reconnect = self.tx_reconnect.subscribe();
+        let mut rx_message_channel = self.tx_message_channel.subscribe();
         let running = self.running.clone();
         let message_channels = self.message_channels.clone();
         let back_channel = self.tx_out.clone();
 
         let reader_copy = self.stream_reader.clone();
         let settings_channel_copy = self.settings_channel.resubscribe();
+        let session = self.session.clone();
+        let chat_history =
+            ChatHistory::open(&self.server_data.server_host, self.server_data.server_port)
+                .map_err(|e| error!("Failed to open chat history: {e}"))
+                .ok();
         self.threads.insert(
             ConnectionThread::Input,
             tokio::spawn(async move {
                 let mut interval = time::interval(DEADMAN_INTERVAL);
                 {
                     let mut reader = reader_copy.lock().await;
-                    let message_reader =
-                        MessageRouter::new(message_channels, back_channel, settings_channel_copy);
+                    let message_reader = MessageRouter::new(
+                        message_channels,
+                        back_channel,
+                        settings_channel_copy,
+                        session,
+                        chat_history,
+                    );
 
                     match message_reader {
                         Ok(message_reader) => {
@@ -47,6 +79,35 @@ impl InputThread for Connection {
                             }
                         }
 
+                        Ok(result) = rx_voice_in.recv() => {
+                            let mut reader = reader_copy.lock().await;
+                            if let Some(reader) = reader.as_mut() {
+                                reader.read_voice(result);
+                            }
+                        }
+
+                        Ok(result) = rx_message_channel.recv() => {
+                            let mut reader = reader_copy.lock().await;
+                            if let Some(reader) = reader.as_mut() {
+                                reader.add_sent_message(SentMessage {
+                                    message: result.message,
+                                    channel_id: result.channel_id,
+                                    message_id: result.message_id,
+                                    timestamp: result.timestamp,
+                                });
+                            }
+                        }
+
+                        Ok(()) = rx_reconnect.recv() => {
+                            let mut reader = reader_copy.lock().await;
+                            if let Some(reader) = reader.as_mut() {
+                                for mut result in take_pending(&mut rx_in) {
+                                    reader.read_next(&mut result);
+                                }
+                                reader.prepare_reconnect();
+                            }
+                        }
+
                         _ = interval.tick() => {}
                     }
                 }
@@ -1,70 +1,199 @@
 use async_trait::async_trait;
+use serde::Serialize;
 
-use crate::connection::{Connection, BUFFER_SIZE};
+use crate::connection::{
+    connect_tls, notify_certificate_mismatch, Connection, ServerData, TlsStream, BUFFER_SIZE,
+};
 use crate::errors::application_error::ApplicationError;
+use crate::errors::certificate_error::CertificateError;
 use crate::errors::AnyError;
+use crate::protocol::init_connection;
+use crate::protocol::serialize::message_container::FrontendMessage;
+use crate::utils::frontend::send_to_frontend;
 
-use super::{ConnectionThread, MainThread, DEADMAN_INTERVAL};
+use super::{reconnect_delay, ConnectionThread, MainThread, DEADMAN_INTERVAL};
 use std::cmp;
-use std::sync::atomic::Ordering;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
 use tokio::io::{AsyncReadExt, AsyncWriteExt, BufReader};
-use tokio::net::TcpStream;
+use tokio::sync::broadcast::{Receiver, Sender};
 use tokio::{select, time};
-use tracing::{error, trace};
+use tracing::{error, info, trace, warn};
 
 pub const MAX_SEND_SIZE: usize = 8192;
+const MAX_RECONNECT_ATTEMPTS: u32 = 10;
+
+#[derive(Debug, Clone, Serialize)]
+struct ReconnectInfo {
+    attempt: u32,
+    delay_ms: u128,
+}
+
+/// Forwards data between the socket and the in/out channels, until either the socket is lost
+/// or the connection is shut down.
+async fn forward_stream(
+    stream: TlsStream,
+    tx_in: &Sender<Vec<u8>>,
+    rx_out: &mut Receiver<Vec<u8>>,
+    running: &AtomicBool,
+) {
+    let mut buffer = [0; BUFFER_SIZE];
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut reader = BufReader::new(reader);
+    let mut interval = time::interval(DEADMAN_INTERVAL);
+
+    while running.load(Ordering::Relaxed) {
+        select! {
+            read = reader.read(&mut buffer) => {
+                let size = match read {
+                    Ok(0) => {
+                        warn!("Server closed the connection");
+                        return;
+                    }
+                    Ok(size) => size,
+                    Err(e) => {
+                        error!("Error while reading from socket: {:?}", e);
+                        return;
+                    }
+                };
+
+                if let Err(e) = tx_in.send(buffer[0..size].to_vec()) {
+                    error!("Error while channeling incomming data: {e:?}");
+                }
+            }
+            Ok(result) = rx_out.recv() => {
+                if result.len() < MAX_SEND_SIZE && result[1] != 0x01 {
+                    trace!("Sending to server: {result:?}");
+                }
+
+                let chunks = result.chunks(cmp::max(1, result.len() / MAX_SEND_SIZE));
+
+                for chunk in chunks {
+                    if let Err(e) = writer.write(chunk).await {
+                        error!("Error while writing to socket: {:?}", e);
+                        return;
+                    }
+                }
+            }
+            _ = interval.tick() => {}
+        }
+    }
+}
+
+/// Sleeps for the given duration, but wakes up early if the connection is shut down
+async fn interruptible_sleep(duration: Duration, running: &AtomicBool) {
+    let mut remaining = duration;
+
+    while running.load(Ordering::Relaxed) && !remaining.is_zero() {
+        let step = cmp::min(remaining, DEADMAN_INTERVAL);
+        time::sleep(step).await;
+        remaining = remaining.saturating_sub(step);
+    }
+}
+
+/// Tries to re-establish the TLS stream with an exponential backoff, returns the reason if it gives up
+async fn reconnect(
+    server_data: &ServerData,
+    running: &AtomicBool,
+    frontend_channel: &Sender<String>,
+) -> Result<TlsStream, String> {
+    for attempt in 1..=MAX_RECONNECT_ATTEMPTS {
+        let delay = reconnect_delay(attempt);
+        let info = ReconnectInfo {
+            attempt,
+            delay_ms: delay.as_millis(),
+        };
+        send_to_frontend(
+            frontend_channel,
+            &FrontendMessage::new("reconnecting", info),
+        );
+
+        interruptible_sleep(delay, running).await;
+        if !running.load(Ordering::Relaxed) {
+            return Err("Connection closed".to_string());
+        }
+
+        match connect_tls(server_data).await {
+            Ok(stream) => return Ok(stream),
+            // retrying won't change the certificate, the user has to accept it first
+            Err(e) if e.is::<CertificateError>() => {
+                error!("Reconnect attempt {attempt} failed: {e}");
+                notify_certificate_mismatch(e.as_ref(), frontend_channel);
+                return Err(e.to_string());
+            }
+            Err(e) => warn!("Reconnect attempt {attempt} failed: {e}"),
+        }
+    }
+
+    Err("Unable to reconnect to the server".to_string())
+}
 
 #[async_trait]
 impl MainThread for Connection {
-    async fn init_main_thread(
-        &mut self,
-        stream: Option<tokio_native_tls::TlsStream<TcpStream>>,
-    ) -> AnyError<()> {
+    async fn init_main_thread(&mut self, stream: Option<TlsStream>) -> AnyError<()> {
         if self.threads.get(&ConnectionThread::Main).is_some() {
             return Err(Box::new(ApplicationError::new(
                 "MainThread already running",
             )));
         }
 
-        let mut buffer = [0; BUFFER_SIZE];
-        let (reader, mut writer) = tokio::io::split(stream.ok_or("No stream found")?);
-        let mut reader = BufReader::new(reader);
+        let mut stream = stream.ok_or("No stream found")?;
 
         let tx_in = self.tx_in.clone();
+        let tx_out = self.tx_out.clone();
+        let tx_reconnect = self.tx_reconnect.clone();
         let mut rx_out = self.tx_out.subscribe();
         let running = self.running.clone();
+        let reconnecting = self.reconnecting.clone();
+        let server_data = self.server_data.clone();
+        let package_info = self.package_info.clone();
+        let frontend_channel = self.message_channels.message_channel.clone();
 
         self.threads.insert(
             ConnectionThread::Main,
             tokio::spawn(async move {
-                let mut interval = time::interval(DEADMAN_INTERVAL);
+                loop {
+                    forward_stream(stream, &tx_in, &mut rx_out, &running).await;
 
-                while running.load(Ordering::Relaxed) {
-                    select! {
-                        Ok(size) = reader.read(&mut buffer) => {
-                            if size == 0 {
-                                return;
-                            }
-                            if let Err(e) = tx_in.send(buffer[0..size].to_vec()) {
-                                error!("Error while channeling incomming data: {e:?}");
-                            }
-                        }
-                        Ok(result) = rx_out.recv() => {
-                            if result.len() < MAX_SEND_SIZE && result[1] != 0x01 {
-                                trace!("Sending to server: {result:?}");
-                            }
+                    if !running.load(Ordering::Relaxed) {
+                        return;
+                    }
 
-                            let chunks = result.chunks(cmp::max(1, result.len() / MAX_SEND_SIZE));
+                    info!("Connection lost, trying to reconnect");
+                    reconnecting.store(true, Ordering::Relaxed);
+                    if let Err(e) = tx_reconnect.send(()) {
+                        error!("Unable to announce reconnect: {e}");
+                    }
 
-                            for chunk in chunks {
-                                if let Err(e) = writer.write(chunk).await {
-                                    error!("Error while writing to socket: {:?}", e);
-                                    return;
+                    let new_stream =
+                        match reconnect(&server_data, &running, &frontend_channel).await {
+                            Ok(stream) => stream,
+                            Err(reason) => {
+                                if running.load(Ordering::Relaxed) {
+                                    let reason = Some(reason);
+                                    send_to_frontend(
+                                        &frontend_channel,
+                                        &FrontendMessage::new("disconnected", &reason),
+                                    );
                                 }
+                                return;
                             }
-                        }
-                        _ = interval.tick() => {}
-                    }
+                        };
+
+                    info!("Reconnected to {}", server_data.server_host);
+                    stream = new_stream;
+                    // anything queued while we were offline belongs to the old session, the
+                    // message router fails the actions which are still pending
+                    rx_out = rx_out.resubscribe();
+                    let access_tokens = server_data.access_tokens.borrow().clone();
+                    init_connection(
+                        &server_data.username,
+                        server_data.password.clone(),
+                        access_tokens,
+                        &tx_out,
+                        &package_info,
+                    );
+                    reconnecting.store(false, Ordering::Relaxed);
                 }
             }),
         );
@@ -1,16 +1,26 @@
 use async_trait::async_trait;
 
-mod input_thread;
+pub mod input_thread;
 mod main_thread;
 mod output_thread;
 mod ping_thread;
+mod udp_thread;
 use std::time::Duration;
-use tokio::net::TcpStream;
 
+use crate::connection::TlsStream;
 use crate::errors::AnyError;
 
 pub const DEADMAN_INTERVAL: Duration = Duration::from_millis(500);
 pub const MAX_PING_FAILURES: u8 = 3;
+const INITIAL_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(60);
+
+/// Delay before the given reconnect attempt, it doubles with every attempt up to a minute
+pub fn reconnect_delay(attempt: u32) -> Duration {
+    INITIAL_RECONNECT_DELAY
+        .checked_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+        .map_or(MAX_RECONNECT_DELAY, |delay| delay.min(MAX_RECONNECT_DELAY))
+}
 
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
 pub enum ConnectionThread {
@@ -18,6 +28,7 @@ pub enum ConnectionThread {
     Output,
     Input,
     Main,
+    Udp,
 }
 
 impl ToString for ConnectionThread {
@@ -27,6 +38,7 @@ impl ToString for ConnectionThread {
             Self::Output => "OutputThread".to_string(),
             Self::Input => "InputThread".to_string(),
             Self::Main => "MainThread".to_string(),
+            Self::Udp => "UdpThread".to_string(),
         }
     }
 }
@@ -35,6 +47,10 @@ pub trait PingThread {
     fn spawn_ping_thread(&mut self);
 }
 
+pub trait UdpThread {
+    fn spawn_udp_thread(&mut self);
+}
+
 pub trait OutputThread {
     fn spawn_output_thread(&mut self);
 }
@@ -45,8 +61,5 @@ pub trait InputThread {
 
 #[async_trait]
 pub trait MainThread {
-    async fn init_main_thread(
-        &mut self,
-        stream: Option<tokio_native_tls::TlsStream<TcpStream>>,
-    ) -> AnyError<()>;
+    async fn init_main_thread(&mut self, stream: Option<TlsStream>) -> AnyError<()>;
 }
@@ -1,6 +1,9 @@
 use std::sync::atomic::Ordering;
 
-use crate::{connection::Connection, mumble, utils::messages::message_builder};
+use crate::{
+    connection::Connection, manager::pending_action::PendingAction, mumble,
+    utils::messages::message_builder,
+};
 use tokio::select;
 use tokio::time;
 use tracing::trace;
@@ -17,6 +20,7 @@ impl OutputThread for Connection {
 
         let tx_out = self.tx_out.clone();
         let running = self.running.clone();
+        let pending_actions = self.session.pending_actions.clone();
         let mut rx_message_channel = self.tx_message_channel.subscribe();
 
         self.threads.insert(
@@ -34,13 +38,14 @@ impl OutputThread for Connection {
                                 channel_id: result.channel_id.iter().copied().collect(),
                                 tree_id: Vec::new(),
                                 message: result.message,
-                                message_id: None,
-                                timestamp: None,
+                                message_id: Some(result.message_id.clone()),
+                                timestamp: Some(result.timestamp),
                             };
                             trace!("Sending message: {:?}", message);
                             let buffer = message_builder(&message).unwrap_or_default();
+                            let action = PendingAction::TextMessage { message_id: result.message_id };
 
-                            if let Err(error) = tx_out.send(buffer) {
+                            if let Err(error) = pending_actions.write(|actions| actions.send(action, &tx_out, buffer)) {
                                 error!("Unable to send message: {}", error);
                             }
                         }
@@ -1,13 +1,15 @@
 use crate::{
-    connection::{threads::MAX_PING_FAILURES, Connection, PingThread},
+    connection::{
+        ping_stats::{current_timestamp, PingStatistics},
+        threads::MAX_PING_FAILURES,
+        Connection, PingThread,
+    },
+    errors::AnyError,
     mumble,
     protocol::serialize::message_container::FrontendMessage,
     utils::{frontend::send_to_frontend, messages::message_builder},
 };
-use std::{
-    sync::atomic::Ordering,
-    time::{Duration, SystemTime},
-};
+use std::{sync::atomic::Ordering, time::Duration};
 use tokio::{select, time};
 use tracing::error;
 
@@ -24,7 +26,10 @@ impl PingThread for Connection {
 
         let tx_out = self.tx_out.clone();
         let running = self.running.clone();
+        let pending_actions = self.session.pending_actions.clone();
         let frontend_channel = self.message_channels.message_channel.clone();
+        let mut rx_ping = self.message_channels.ping_channel.subscribe();
+        let user_stats_session = self.tx_user_stats.subscribe();
 
         // timer thread
         self.threads.insert(
@@ -33,36 +38,44 @@ impl PingThread for Connection {
                 let mut interval = time::interval(PING_INTERVAL);
                 let mut deadman_switch = time::interval(DEADMAN_INTERVAL);
                 let mut deadman_counter = 0;
+                let mut statistics = PingStatistics::default();
 
                 while running.load(Ordering::Relaxed) {
-                    let now = match SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
-                        Ok(duration) => duration.as_secs(),
-                        Err(error) => {
-                            error!("Unable to get timestamp for Ping: {}", error);
-                            continue;
-                        }
-                    };
-
-                    //todo: Add actual ping statistics
-                    let ping = mumble::proto::Ping {
-                        timestamp: Some(now),
-                        good: Some(0),
-                        late: Some(0),
-                        lost: Some(999),
-                        resync: Some(0),
-                        tcp_packets: Some(1),
-                        tcp_ping_avg: Some(1.2f32),
-                        tcp_ping_var: Some(2.0f32),
-                        udp_packets: Some(1),
-                        udp_ping_avg: Some(2.3f32),
-                        udp_ping_var: Some(5.6f32),
-                    };
-
                     select! {
                         _ = deadman_switch.tick() => {}
+                        Ok(reply) = rx_ping.recv() => {
+                            match current_timestamp() {
+                                Ok(now) => statistics.handle_reply(reply, now),
+                                Err(error) => error!("Unable to get timestamp for Ping reply: {}", error),
+                            }
+                        }
                         _ = interval.tick() => {
-                            match tx_out.send(message_builder(&ping).unwrap_or_default()) {
-                                Ok(_) => { deadman_counter = 0; }
+                            let session = *user_stats_session.borrow();
+                            if let Some(session) = session {
+                                let user_stats = mumble::proto::UserStats {
+                                    session: Some(session),
+                                    stats_only: Some(true),
+                                    ..Default::default()
+                                };
+                                if let Err(error) = tx_out.send(message_builder(&user_stats).unwrap_or_default()) {
+                                    error!("Unable to request user stats: {}", error);
+                                }
+                            }
+
+                            // the answer confirms the pending actions, so none may be sent in between
+                            let sent = pending_actions.write(|_| -> AnyError<()> {
+                                let now = current_timestamp()?;
+                                let ping = statistics.create_ping(now);
+                                tx_out.send(message_builder(&ping)?)?;
+                                Ok(())
+                            });
+                            send_to_frontend(
+                                &frontend_channel,
+                                &FrontendMessage::new("connection_stats", statistics.stats()),
+                            );
+
+                            match sent {
+                                Ok(()) => { deadman_counter = 0; }
                                 Err(error) => {
                                     error!("Unable to send Ping: {}", error);
                                     deadman_counter += 1;
@@ -0,0 +1,163 @@
+use std::{
+    sync::{atomic::Ordering, Arc},
+    time::Duration,
+};
+
+use serde::Serialize;
+use tokio::{net::UdpSocket, select, time};
+use tracing::{debug, error, info, trace, warn};
+
+use crate::{
+    connection::{
+        ping_stats::PingReply,
+        udp::{UdpPacket, UdpTransport},
+        Connection,
+    },
+    manager::server_config::ServerConfig,
+    mumble::{self, proto::UdpTunnel},
+    protocol::serialize::message_container::FrontendMessage,
+    utils::{
+        frontend::send_to_frontend,
+        messages::{message_builder, raw_message_builder},
+    },
+};
+
+use super::{ConnectionThread, UdpThread, DEADMAN_INTERVAL};
+
+const UDP_PING_INTERVAL: Duration = Duration::from_millis(5000);
+const UDP_BUFFER_SIZE: usize = 1024;
+
+#[derive(Debug, Clone, Serialize)]
+struct VoiceTransport {
+    udp: bool,
+}
+
+async fn receive(socket: Option<&Arc<UdpSocket>>, buffer: &mut [u8]) -> std::io::Result<usize> {
+    match socket {
+        Some(socket) => socket.recv(buffer).await,
+        None => std::future::pending().await,
+    }
+}
+
+impl UdpThread for Connection {
+    fn spawn_udp_thread(&mut self) {
+        if self.threads.get(&ConnectionThread::Udp).is_some() {
+            error!("UdpThread already running");
+            return;
+        }
+
+        let server_uri = format!(
+            "{}:{}",
+            self.server_data.server_host, self.server_data.server_port
+        );
+        let tx_out = self.tx_out.clone();
+        let tx_voice_in = self.tx_voice_in.clone();
+        let running = self.running.clone();
+        let frontend_channel = self.message_channels.message_channel.clone();
+        let ping_channel = self.message_channels.ping_channel.clone();
+        let server_config = self.session.server_config.clone();
+        let mut rx_voice_out = self.message_channels.voice_channel.subscribe();
+        let mut rx_crypt_setup = self.message_channels.crypt_setup_channel.subscribe();
+
+        self.threads.insert(
+            ConnectionThread::Udp,
+            tokio::spawn(async move {
+                let mut transport = match UdpTransport::connect(&server_uri).await {
+                    Ok(transport) => Some(transport),
+                    Err(error) => {
+                        warn!("Unable to open UDP socket, tunneling voice over TCP: {error}");
+                        None
+                    }
+                };
+                let socket = transport.as_ref().map(UdpTransport::socket);
+
+                let mut interval = time::interval(DEADMAN_INTERVAL);
+                let mut ping_interval = time::interval(UDP_PING_INTERVAL);
+                let mut buffer = [0u8; UDP_BUFFER_SIZE];
+                let mut udp_active = false;
+
+                while running.load(Ordering::Relaxed) {
+                    select! {
+                        Ok(size) = receive(socket.as_ref(), &mut buffer) => {
+                            let Some(transport) = transport.as_mut() else {
+                                continue;
+                            };
+
+                            match transport.decode(&buffer[..size]).map_err(|e| e.to_string()) {
+                                Ok(UdpPacket::Voice(data)) => {
+                                    if let Err(error) = tx_voice_in.send(data) {
+                                        error!("Unable to forward UDP voice: {error}");
+                                    }
+                                }
+                                Ok(UdpPacket::Ping(timestamp)) => {
+                                    trace!("Received UDP ping: {timestamp}");
+                                    let packets = transport.crypt_state().stats();
+                                    let _ = ping_channel.send(PingReply::Udp { timestamp, packets });
+                                }
+                                Err(error) => {
+                                    debug!("Unable to decrypt UDP packet: {error}");
+                                    if transport.needs_resync() {
+                                        info!("Requesting crypt resync");
+                                        let resync = mumble::proto::CryptSetup::default();
+                                        let _ = tx_out.send(message_builder(&resync).unwrap_or_default());
+                                    }
+                                }
+                            }
+                        }
+
+                        Ok(data) = rx_voice_out.recv() => {
+                            match transport.as_mut() {
+                                Some(transport) if transport.is_active() => {
+                                    if let Err(error) = transport.send_voice(&data).await.map_err(|e| e.to_string()) {
+                                        error!("Unable to send UDP voice: {error}");
+                                    }
+                                }
+                                _ => {
+                                    let tunnel = raw_message_builder::<UdpTunnel>(&data).unwrap_or_default();
+                                    if let Err(error) = tx_out.send(tunnel) {
+                                        error!("Unable to tunnel voice: {error}");
+                                    }
+                                }
+                            }
+                        }
+
+                        Ok(crypt_setup) = rx_crypt_setup.recv() => {
+                            let Some(transport) = transport.as_mut() else {
+                                continue;
+                            };
+
+                            match transport.apply_crypt_setup(&crypt_setup).map_err(|e| e.to_string()) {
+                                Ok(Some(reply)) => {
+                                    let _ = tx_out.send(message_builder(&reply).unwrap_or_default());
+                                }
+                                Ok(None) => {}
+                                Err(error) => error!("Invalid crypt setup: {error}"),
+                            }
+                        }
+
+                        _ = ping_interval.tick() => {
+                            if let Some(transport) = transport.as_mut().filter(|t| t.crypt_state().is_valid()) {
+                                transport.set_voice_format(server_config.read(ServerConfig::voice_format));
+                                if let Err(error) = transport.send_ping().await.map_err(|e| e.to_string()) {
+                                    error!("Unable to send UDP ping: {error}");
+                                }
+                            }
+                        }
+
+                        _ = interval.tick() => {}
+                    }
+
+                    let active = transport.as_ref().is_some_and(UdpTransport::is_active);
+                    if active != udp_active {
+                        udp_active = active;
+                        info!("Voice transport changed, using UDP: {udp_active}");
+                        send_to_frontend(
+                            &frontend_channel,
+                            &FrontendMessage::new("voice_transport", VoiceTransport { udp: udp_active }),
+                        );
+                    }
+                }
+            }),
+        );
+    }
+}
@@ -0,0 +1,205 @@
+use std::{
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use prost::Message;
+use tokio::net::{lookup_host, UdpSocket};
+use tracing::debug;
+
+use crate::{
+    errors::{crypt_error::CryptError, AnyError},
+    mumble::{self, udp_proto},
+    protocol::{VoiceFormat, UDP_PING_MESSAGE},
+    utils::varint,
+};
+
+use super::{crypt_state::CryptState, ping_stats::current_timestamp, threads::MAX_PING_FAILURES};
+
+const UDP_PING_TYPE: u8 = 1;
+const RESYNC_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub enum UdpPacket {
+    Ping(u64),
+    Voice(Vec<u8>),
+}
+
+/// Encrypted voice transport next to the TLS control channel.
+///
+/// The transport is only considered active while the server answers our UDP pings,
+/// otherwise voice has to be tunneled through TCP.
+pub struct UdpTransport {
+    socket: Arc<UdpSocket>,
+    crypt_state: CryptState,
+    missed_pings: u8,
+    active: bool,
+    last_resync_request: Option<Instant>,
+    voice_format: VoiceFormat,
+}
+
+impl UdpTransport {
+    pub async fn connect(server_uri: &str) -> AnyError<Self> {
+        let server_address = lookup_host(server_uri)
+            .await?
+            .next()
+            .ok_or_else(|| CryptError::new(format!("Unable to resolve {server_uri}")))?;
+
+        Self::connect_to(server_address).await
+    }
+
+    pub async fn connect_to(server_address: SocketAddr) -> AnyError<Self> {
+        let local_address: SocketAddr = if server_address.is_ipv4() {
+            (Ipv4Addr::UNSPECIFIED, 0).into()
+        } else {
+            (Ipv6Addr::UNSPECIFIED, 0).into()
+        };
+
+        let socket = UdpSocket::bind(local_address).await?;
+        socket.connect(server_address).await?;
+        debug!("UDP socket bound to {:?}", socket.local_addr());
+
+        Ok(Self {
+            socket: Arc::new(socket),
+            crypt_state: CryptState::new(),
+            missed_pings: 0,
+            active: false,
+            last_resync_request: None,
+            voice_format: VoiceFormat::default(),
+        })
+    }
+
+    pub const fn is_active(&self) -> bool {
+        self.active && self.crypt_state.is_valid()
+    }
+
+    pub const fn crypt_state(&self) -> &CryptState {
+        &self.crypt_state
+    }
+
+    /// Applies a `CryptSetup` received from the server.
+    ///
+    /// Returns a message which has to be sent back, if the server asked for our nonce.
+    pub fn apply_crypt_setup(
+        &mut self,
+        crypt_setup: &mumble::proto::CryptSetup,
+    ) -> AnyError<Option<mumble::proto::CryptSetup>> {
+        match (
+            &crypt_setup.key,
+            &crypt_setup.client_nonce,
+            &crypt_setup.server_nonce,
+        ) {
+            (Some(key), Some(client_nonce), Some(server_nonce)) => {
+                self.crypt_state.set_key(key, client_nonce, server_nonce)?;
+                self.missed_pings = 0;
+                self.active = false;
+                Ok(None)
+            }
+            (_, _, Some(server_nonce)) => {
+                self.crypt_state.set_decrypt_iv(server_nonce)?;
+                Ok(None)
+            }
+            _ => Ok(Some(mumble::proto::CryptSetup {
+                client_nonce: Some(self.crypt_state.encrypt_iv().to_vec()),
+                ..Default::default()
+            })),
+        }
+    }
+
+    /// Pings are sent and incoming packets are parsed in the format the server uses for voice
+    pub fn set_voice_format(&mut self, voice_format: VoiceFormat) {
+        self.voice_format = voice_format;
+    }
+
+    /// The socket is shared, so incoming packets can be awaited while the transport is borrowed elsewhere
+    pub fn socket(&self) -> Arc<UdpSocket> {
+        self.socket.clone()
+    }
+
+    pub fn decode(&mut self, data: &[u8]) -> AnyError<UdpPacket> {
+        let plain = self.crypt_state.decrypt(data)?;
+        let header = *plain.first().ok_or("Received an empty UDP packet")?;
+
+        // the headers of both formats overlap, e.g. 0x01 is a protobuf ping but also legacy
+        // CELT-alpha voice to target 1, so only the negotiated format tells them apart
+        let timestamp = match self.voice_format {
+            VoiceFormat::Protobuf if header == UDP_PING_MESSAGE => {
+                udp_proto::Ping::decode(&plain[1..])?.timestamp
+            }
+            VoiceFormat::Legacy if (header >> 5) == UDP_PING_TYPE => {
+                let timestamp = varint::Builder::new()
+                    .slice(&plain[1..])
+                    .build()?
+                    .parsed_value;
+                u64::try_from(timestamp)?
+            }
+            _ => return Ok(UdpPacket::Voice(plain)),
+        };
+        self.missed_pings = 0;
+        self.active = true;
+
+        Ok(UdpPacket::Ping(timestamp))
+    }
+
+    pub async fn send_voice(&mut self, data: &[u8]) -> AnyError<()> {
+        let packet = self.crypt_state.encrypt(data)?;
+        self.socket.send(&packet).await?;
+
+        Ok(())
+    }
+
+    pub async fn send_ping(&mut self) -> AnyError<()> {
+        if self.missed_pings >= MAX_PING_FAILURES {
+            self.active = false;
+        } else {
+            self.missed_pings += 1;
+        }
+
+        let timestamp = current_timestamp()?;
+        let ping = match self.voice_format {
+            VoiceFormat::Legacy => {
+                let mut ping = vec![UDP_PING_TYPE << 5];
+                ping.extend(
+                    varint::Builder::new()
+                        .number(&timestamp)
+                        .build()?
+                        .parsed_vec(),
+                );
+                ping
+            }
+            VoiceFormat::Protobuf => {
+                let mut ping = vec![UDP_PING_MESSAGE];
+                ping.extend(
+                    udp_proto::Ping {
+                        timestamp,
+                        ..Default::default()
+                    }
+                    .encode_to_vec(),
+                );
+                ping
+            }
+        };
+
+        self.send_voice(&ping).await
+    }
+
+    /// Checks if we should ask the server for a new nonce, because decryption keeps failing
+    pub fn needs_resync(&mut self) -> bool {
+        self.needs_resync_at(Instant::now())
+    }
+
+    pub fn needs_resync_at(&mut self, now: Instant) -> bool {
+        let failing = now.duration_since(self.crypt_state.last_good()) > RESYNC_TIMEOUT;
+        let requested_recently = self
+            .last_resync_request
+            .is_some_and(|last| now.duration_since(last) < RESYNC_TIMEOUT);
+
+        if failing && !requested_recently {
+            self.last_resync_request = Some(now);
+            self.crypt_state.count_resync();
+            return true;
+        }
+
+        false
+    }
+}
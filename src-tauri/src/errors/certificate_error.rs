@@ -1,17 +1,35 @@
 use std::error::Error;
 use std::fmt;
 
+use crate::utils::pinned_certificate_store::CertificateMismatch;
+
 #[derive(Debug)]
 pub struct CertificateError {
     details: String,
+    mismatch: Option<CertificateMismatch>,
 }
 
 impl CertificateError {
     pub fn new(msg: &str) -> Self {
         Self {
             details: msg.to_string(),
+            mismatch: None,
         }
     }
+
+    pub fn mismatch(mismatch: CertificateMismatch) -> Self {
+        Self {
+            details: format!(
+                "The certificate of {}:{} has changed, new fingerprint: {}",
+                mismatch.host, mismatch.port, mismatch.presented
+            ),
+            mismatch: Some(mismatch),
+        }
+    }
+
+    pub const fn certificate_mismatch(&self) -> Option<&CertificateMismatch> {
+        self.mismatch.as_ref()
+    }
 }
 
 impl fmt::Display for CertificateError {
@@ -0,0 +1,27 @@
+use std::error::Error;
+use std::fmt::{self, Display};
+
+#[derive(Debug)]
+pub struct CryptError {
+    details: String,
+}
+
+impl CryptError {
+    pub fn new<T: Display>(msg: T) -> Self {
+        Self {
+            details: msg.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for CryptError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.details)
+    }
+}
+
+impl Error for CryptError {
+    fn description(&self) -> &str {
+        &self.details
+    }
+}
@@ -4,6 +4,8 @@ use self::application_error::ApplicationError;
 
 pub mod application_error;
 pub mod certificate_error;
+pub mod crypt_error;
+pub mod permission_denied_error;
 pub mod string_convertion;
 pub mod voice_error;
 
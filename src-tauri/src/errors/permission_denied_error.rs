@@ -0,0 +1,82 @@
+use std::error::Error;
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::mumble::{self, proto::permission_denied::DenyType};
+
+/// Why the server denied an action, serialized as a code the frontend can translate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DenialReason {
+    Text,
+    Permission,
+    SuperUser,
+    ChannelName,
+    TextTooLong,
+    H9k,
+    TemporaryChannel,
+    MissingCertificate,
+    UserName,
+    ChannelFull,
+    NestingLimit,
+    ChannelCountLimit,
+    ChannelListenerLimit,
+    UserListenerLimit,
+}
+
+impl From<DenyType> for DenialReason {
+    fn from(deny_type: DenyType) -> Self {
+        match deny_type {
+            DenyType::Text => Self::Text,
+            DenyType::Permission => Self::Permission,
+            DenyType::SuperUser => Self::SuperUser,
+            DenyType::ChannelName => Self::ChannelName,
+            DenyType::TextTooLong => Self::TextTooLong,
+            DenyType::H9k => Self::H9k,
+            DenyType::TemporaryChannel => Self::TemporaryChannel,
+            DenyType::MissingCertificate => Self::MissingCertificate,
+            DenyType::UserName => Self::UserName,
+            DenyType::ChannelFull => Self::ChannelFull,
+            DenyType::NestingLimit => Self::NestingLimit,
+            DenyType::ChannelCountLimit => Self::ChannelCountLimit,
+            DenyType::ChannelListenerLimit => Self::ChannelListenerLimit,
+            DenyType::UserListenerLimit => Self::UserListenerLimit,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PermissionDeniedError {
+    pub reason: DenialReason,
+    pub permission: Option<u32>,
+    pub channel_id: Option<u32>,
+    pub session: Option<u32>,
+    pub name: Option<String>,
+    /// Free text of the server, only set for `DenialReason::Text`
+    pub details: Option<String>,
+}
+
+impl From<&mumble::proto::PermissionDenied> for PermissionDeniedError {
+    fn from(permission_denied: &mumble::proto::PermissionDenied) -> Self {
+        Self {
+            reason: permission_denied.r#type().into(),
+            permission: permission_denied.permission,
+            channel_id: permission_denied.channel_id,
+            session: permission_denied.session,
+            name: permission_denied.name.clone(),
+            details: permission_denied.reason.clone(),
+        }
+    }
+}
+
+impl fmt::Display for PermissionDeniedError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.details {
+            Some(details) => write!(f, "Permission denied ({:?}): {details}", self.reason),
+            None => write!(f, "Permission denied ({:?})", self.reason),
+        }
+    }
+}
+
+impl Error for PermissionDeniedError {}
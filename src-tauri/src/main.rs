@@ -27,10 +27,18 @@ use tracing_subscriber::{
 };
 
 use crate::commands::{
-    change_user_state, close_app, connect_to_server, crop_and_store_image, disable_audio_info,
-    enable_audio_info, get_audio_devices, like_message, logout, send_message,
-    set_audio_input_setting, set_audio_output_setting, set_audio_user_state, set_user_image,
-    settings_cmd::{get_identity_certs, get_server_list, save_server},
+    ban_user, change_user_state, close_app, connect_to_server, create_channel,
+    crop_and_store_image, deregister_user, disable_audio_info, enable_audio_info,
+    get_audio_devices, get_audio_output_devices,
+    history_cmd::{get_chat_history, search_chat_history},
+    kick_user, like_message, listen_to_channels, logout, moderate_user, query_permissions,
+    register_self, remove_channel, remove_voice_target, rename_registered_user, request_acl,
+    request_ban_list, request_registered_users, request_user_stats, send_message,
+    set_audio_input_setting, set_audio_output_setting, set_audio_user_state, set_input_device,
+    set_listener_pose, set_listener_volume, set_output_device, set_user_image, set_voice_target,
+    settings_cmd::{accept_server_certificate, get_identity_certs, get_server_list, save_server},
+    stop_listening_to_channels, stop_user_stats, submit_acl, submit_ban_list,
+    trigger_context_action, update_access_tokens, update_channel, validate_shortcut,
     web_cmd::{
         convert_url_to_base64, get_open_graph_data_from_website, get_tenor_search_results,
         get_tenor_trending_results, open_browser,
@@ -89,12 +97,16 @@ async fn main() {
             crop_and_store_image,
             change_user_state,
             get_audio_devices,
+            get_audio_output_devices,
+            set_input_device,
+            set_output_device,
             zip_data_to_utf8,
             unzip_data_from_utf8,
             convert_to_base64,
             open_browser,
             get_open_graph_data_from_website,
             get_identity_certs,
+            accept_server_certificate,
             set_audio_input_setting,
             set_audio_output_setting,
             enable_audio_info,
@@ -103,6 +115,34 @@ async fn main() {
             get_tenor_trending_results,
             convert_url_to_base64,
             set_audio_user_state,
+            set_listener_pose,
+            update_access_tokens,
+            request_acl,
+            submit_acl,
+            query_permissions,
+            create_channel,
+            update_channel,
+            remove_channel,
+            kick_user,
+            ban_user,
+            moderate_user,
+            request_ban_list,
+            submit_ban_list,
+            request_registered_users,
+            rename_registered_user,
+            deregister_user,
+            register_self,
+            request_user_stats,
+            stop_user_stats,
+            trigger_context_action,
+            listen_to_channels,
+            stop_listening_to_channels,
+            set_listener_volume,
+            set_voice_target,
+            remove_voice_target,
+            validate_shortcut,
+            get_chat_history,
+            search_chat_history,
             close_app
         ])
         .run(tauri::generate_context!())
@@ -0,0 +1,251 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::Sender;
+use tracing::{debug, trace};
+
+use crate::{
+    errors::AnyError,
+    manager::session::Shared,
+    mumble,
+    protocol::serialize::message_container::FrontendMessage,
+    utils::{frontend::send_to_frontend, messages::message_builder},
+};
+
+/// Permission bits as used by the Mumble server in `PermissionQuery` and ACL entries
+#[allow(dead_code)] // we want the complete set, even if not every bit is checked by us
+pub mod permission {
+    pub const NONE: u32 = 0x0;
+    pub const WRITE: u32 = 0x1;
+    pub const TRAVERSE: u32 = 0x2;
+    pub const ENTER: u32 = 0x4;
+    pub const SPEAK: u32 = 0x8;
+    pub const MUTE_DEAFEN: u32 = 0x10;
+    pub const MOVE: u32 = 0x20;
+    pub const MAKE_CHANNEL: u32 = 0x40;
+    pub const LINK_CHANNEL: u32 = 0x80;
+    pub const WHISPER: u32 = 0x100;
+    pub const TEXT_MESSAGE: u32 = 0x200;
+    pub const MAKE_TEMP_CHANNEL: u32 = 0x400;
+    pub const LISTEN: u32 = 0x800;
+    pub const KICK: u32 = 0x1_0000;
+    pub const BAN: u32 = 0x2_0000;
+    pub const REGISTER: u32 = 0x4_0000;
+    pub const SELF_REGISTER: u32 = 0x8_0000;
+    pub const RESET_USER_CONTENT: u32 = 0x10_0000;
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ChannelGroup {
+    pub name: String,
+    pub inherited: bool,
+    pub inherit: bool,
+    pub inheritable: bool,
+    pub add: Vec<u32>,
+    pub remove: Vec<u32>,
+    pub inherited_members: Vec<u32>,
+}
+
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct AclEntry {
+    pub apply_here: bool,
+    pub apply_subs: bool,
+    pub inherited: bool,
+    pub user_id: Option<u32>,
+    pub group: Option<String>,
+    pub grant: u32,
+    pub deny: u32,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ChannelAcl {
+    pub channel_id: u32,
+    pub inherit_acls: bool,
+    pub groups: Vec<ChannelGroup>,
+    pub acls: Vec<AclEntry>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ChannelPermissions {
+    channel_id: u32,
+    permissions: u32,
+}
+
+impl From<mumble::proto::acl::ChanGroup> for ChannelGroup {
+    fn from(group: mumble::proto::acl::ChanGroup) -> Self {
+        Self {
+            inherited: group.inherited(),
+            inherit: group.inherit(),
+            inheritable: group.inheritable(),
+            name: group.name,
+            add: group.add,
+            remove: group.remove,
+            inherited_members: group.inherited_members,
+        }
+    }
+}
+
+impl From<mumble::proto::acl::ChanAcl> for AclEntry {
+    fn from(entry: mumble::proto::acl::ChanAcl) -> Self {
+        Self {
+            apply_here: entry.apply_here(),
+            apply_subs: entry.apply_subs(),
+            inherited: entry.inherited(),
+            user_id: entry.user_id,
+            grant: entry.grant(),
+            deny: entry.deny(),
+            group: entry.group,
+        }
+    }
+}
+
+impl From<mumble::proto::Acl> for ChannelAcl {
+    fn from(acl: mumble::proto::Acl) -> Self {
+        Self {
+            channel_id: acl.channel_id,
+            inherit_acls: acl.inherit_acls(),
+            groups: acl.groups.into_iter().map(ChannelGroup::from).collect(),
+            acls: acl.acls.into_iter().map(AclEntry::from).collect(),
+        }
+    }
+}
+
+impl From<ChannelAcl> for mumble::proto::Acl {
+    fn from(acl: ChannelAcl) -> Self {
+        // inherited entries are defined on a parent channel, the server would add them a second time
+        let groups = acl
+            .groups
+            .into_iter()
+            .filter(|group| {
+                !group.inherited
+                    || !group.inherit
+                    || !group.inheritable
+                    || !group.add.is_empty()
+                    || !group.remove.is_empty()
+            })
+            .map(|group| mumble::proto::acl::ChanGroup {
+                name: group.name,
+                inherited: Some(group.inherited),
+                inherit: Some(group.inherit),
+                inheritable: Some(group.inheritable),
+                add: group.add,
+                remove: group.remove,
+                inherited_members: Vec::new(),
+            })
+            .collect();
+        let acls = acl
+            .acls
+            .into_iter()
+            .filter(|entry| !entry.inherited)
+            .map(|entry| mumble::proto::acl::ChanAcl {
+                apply_here: Some(entry.apply_here),
+                apply_subs: Some(entry.apply_subs),
+                inherited: Some(false),
+                user_id: entry.user_id,
+                group: entry.group,
+                grant: Some(entry.grant),
+                deny: Some(entry.deny),
+            })
+            .collect();
+
+        Self {
+            channel_id: acl.channel_id,
+            inherit_acls: Some(acl.inherit_acls),
+            groups,
+            acls,
+            query: Some(false),
+        }
+    }
+}
+
+pub struct Manager {
+    frontend_channel: Sender<String>,
+    server_channel: Sender<Vec<u8>>,
+    permissions: Shared<HashMap<u32, u32>>,
+}
+
+impl Manager {
+    pub fn new(
+        send_to: Sender<String>,
+        server_channel: Sender<Vec<u8>>,
+        permissions: Shared<HashMap<u32, u32>>,
+    ) -> Self {
+        Self {
+            frontend_channel: send_to,
+            server_channel,
+            permissions,
+        }
+    }
+
+    pub fn notify_acl(&self, acl: mumble::proto::Acl) {
+        let acl = ChannelAcl::from(acl);
+        trace!("Received ACL for channel {}", acl.channel_id);
+
+        send_to_frontend(
+            &self.frontend_channel,
+            &FrontendMessage::new("channel_acl", &acl),
+        );
+    }
+
+    pub fn notify_permission_query(
+        &self,
+        permission_query: &mumble::proto::PermissionQuery,
+    ) -> AnyError<()> {
+        if permission_query.flush() {
+            debug!("Flushing cached permissions");
+            self.permissions.write(HashMap::clear);
+            send_to_frontend(
+                &self.frontend_channel,
+                &FrontendMessage::new("permissions_flushed", &()),
+            );
+        }
+
+        if let (Some(channel_id), Some(permissions)) =
+            (permission_query.channel_id, permission_query.permissions)
+        {
+            self.update_permissions(channel_id, permissions);
+        } else if let Some(channel_id) = permission_query.channel_id {
+            // the server only told us the cache is outdated, so ask again
+            self.query_permissions(channel_id)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn query_permissions(&self, channel_id: u32) -> AnyError<()> {
+        let permission_query = mumble::proto::PermissionQuery {
+            channel_id: Some(channel_id),
+            ..Default::default()
+        };
+        self.server_channel
+            .send(message_builder(&permission_query)?)?;
+
+        Ok(())
+    }
+
+    /// Forgets all permissions, e.g. because they belong to the session we lost
+    pub fn clear(&self) {
+        self.permissions.write(HashMap::clear);
+        send_to_frontend(
+            &self.frontend_channel,
+            &FrontendMessage::new("permissions_flushed", &()),
+        );
+    }
+
+    pub fn update_permissions(&self, channel_id: u32, permissions: u32) {
+        self.permissions
+            .write(|cache| cache.insert(channel_id, permissions));
+
+        send_to_frontend(
+            &self.frontend_channel,
+            &FrontendMessage::new(
+                "channel_permissions",
+                ChannelPermissions {
+                    channel_id,
+                    permissions,
+                },
+            ),
+        );
+    }
+}
@@ -0,0 +1,153 @@
+use std::net::{IpAddr, Ipv6Addr};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::Sender;
+use tracing::{trace, warn};
+
+use crate::{
+    errors::{application_error::ApplicationError, AnyError},
+    mumble,
+    protocol::serialize::message_container::FrontendMessage,
+    utils::frontend::send_to_frontend,
+};
+
+use super::session::Shared;
+
+// the server stores all addresses as IPv6, IPv4 addresses are mapped into the last 32 bits
+const IPV4_MAPPED_MASK_OFFSET: u32 = 96;
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct BanEntry {
+    pub address: String,
+    /// Prefix length for the address family of `address`
+    pub mask: u32,
+    pub name: Option<String>,
+    pub hash: Option<String>,
+    pub reason: Option<String>,
+    pub start: Option<String>,
+    /// Duration in seconds, 0 bans forever
+    pub duration: u32,
+}
+
+impl TryFrom<mumble::proto::ban_list::BanEntry> for BanEntry {
+    type Error = Box<dyn std::error::Error>;
+
+    fn try_from(entry: mumble::proto::ban_list::BanEntry) -> AnyError<Self> {
+        let octets: [u8; 16] = entry
+            .address
+            .as_slice()
+            .try_into()
+            .map_err(|_| ApplicationError::new("Ban entry without a valid address"))?;
+        let address = Ipv6Addr::from(octets);
+
+        let (address, mask) = match address.to_ipv4_mapped() {
+            Some(ipv4) => (
+                IpAddr::V4(ipv4),
+                entry.mask.saturating_sub(IPV4_MAPPED_MASK_OFFSET),
+            ),
+            None => (IpAddr::V6(address), entry.mask),
+        };
+
+        Ok(Self {
+            address: address.to_string(),
+            mask,
+            name: entry.name,
+            hash: entry.hash,
+            reason: entry.reason,
+            start: entry.start,
+            duration: entry.duration.unwrap_or_default(),
+        })
+    }
+}
+
+impl TryFrom<BanEntry> for mumble::proto::ban_list::BanEntry {
+    type Error = Box<dyn std::error::Error>;
+
+    fn try_from(entry: BanEntry) -> AnyError<Self> {
+        let address = entry.address.parse::<IpAddr>()?;
+        let (address, mask) = match address {
+            IpAddr::V4(ipv4) if entry.mask <= 32 => {
+                (ipv4.to_ipv6_mapped(), entry.mask + IPV4_MAPPED_MASK_OFFSET)
+            }
+            IpAddr::V6(ipv6) if entry.mask <= 128 => (ipv6, entry.mask),
+            _ => {
+                return Err(Box::new(ApplicationError::new(
+                    format!("Invalid mask for ban of {}", entry.address).as_str(),
+                )))
+            }
+        };
+
+        Ok(Self {
+            address: address.octets().to_vec(),
+            mask,
+            name: entry.name,
+            hash: entry.hash,
+            reason: entry.reason,
+            start: entry.start,
+            duration: Some(entry.duration),
+        })
+    }
+}
+
+/// Builds the ban list which replaces the one of the server.
+///
+/// The entries we couldn't read are sent back unchanged, otherwise submitting would delete them.
+pub fn ban_list_submission(
+    bans: Vec<BanEntry>,
+    unreadable_bans: &[mumble::proto::ban_list::BanEntry],
+) -> AnyError<mumble::proto::BanList> {
+    let mut bans = bans
+        .into_iter()
+        .map(mumble::proto::ban_list::BanEntry::try_from)
+        .collect::<AnyError<Vec<_>>>()?;
+    bans.extend_from_slice(unreadable_bans);
+
+    Ok(mumble::proto::BanList {
+        bans,
+        query: Some(false),
+    })
+}
+
+pub struct Manager {
+    frontend_channel: Sender<String>,
+    unreadable_bans: Shared<Vec<mumble::proto::ban_list::BanEntry>>,
+}
+
+impl Manager {
+    pub fn new(
+        send_to: Sender<String>,
+        unreadable_bans: Shared<Vec<mumble::proto::ban_list::BanEntry>>,
+    ) -> Self {
+        Self {
+            frontend_channel: send_to,
+            unreadable_bans,
+        }
+    }
+
+    pub fn notify_ban_list(&self, ban_list: mumble::proto::BanList) {
+        // one broken entry shouldn't hide all other bans
+        let mut bans = Vec::new();
+        let mut unreadable_bans = Vec::new();
+        for entry in ban_list.bans {
+            match BanEntry::try_from(entry.clone()) {
+                Ok(ban) => bans.push(ban),
+                Err(e) => {
+                    warn!("Keeping unreadable ban entry as it is: {e}");
+                    unreadable_bans.push(entry);
+                }
+            }
+        }
+        trace!(
+            "Received {} bans, {} of them unreadable",
+            bans.len() + unreadable_bans.len(),
+            unreadable_bans.len()
+        );
+        self.unreadable_bans
+            .write(|unreadable| *unreadable = unreadable_bans);
+
+        send_to_frontend(
+            &self.frontend_channel,
+            &FrontendMessage::new("ban_list", &bans),
+        );
+    }
+}
@@ -3,17 +3,17 @@ use std::{
     mem,
 };
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use tracing::{debug, info};
 
 use crate::{
-    errors::AnyError,
+    errors::{application_error::ApplicationError, AnyError},
     mumble,
     protocol::serialize::message_container::FrontendMessage,
     utils::{frontend::send_to_frontend, messages::message_builder},
 };
 
-use super::Update;
+use super::{acl::permission, pending_action::PendingAction, session::SessionState, Update};
 use tokio::sync::broadcast::Sender;
 
 #[derive(Debug, Default, Serialize)]
@@ -40,6 +40,110 @@ pub struct BlobData {
     pub data: String,
 }
 
+/// Changes to a channel requested by the frontend, a channel without id is created
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct UpdateableChannelState {
+    pub channel_id: Option<u32>,
+    pub parent: Option<u32>,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub position: Option<i32>,
+    pub max_users: Option<u32>,
+    pub temporary: Option<bool>,
+    #[serde(default)]
+    pub links_add: Vec<u32>,
+    #[serde(default)]
+    pub links_remove: Vec<u32>,
+}
+
+impl UpdateableChannelState {
+    /// Checks a channel to create against the known permissions, returns the action to wait for
+    pub fn creation(&self, session: &SessionState) -> AnyError<PendingAction> {
+        let (Some(parent), Some(name)) = (self.parent, self.name.clone()) else {
+            return Err(Box::new(ApplicationError::new(
+                "A new channel needs a parent and a name",
+            )));
+        };
+        if self.channel_id.is_some() {
+            return Err(Box::new(ApplicationError::new(
+                "A new channel can't have an id",
+            )));
+        }
+
+        if self.temporary == Some(true) {
+            session.check_permission(
+                parent,
+                permission::MAKE_TEMP_CHANNEL,
+                "Missing permission to create a temporary channel here",
+            )?;
+        } else {
+            session.check_permission(
+                parent,
+                permission::MAKE_CHANNEL,
+                "Missing permission to create a channel here",
+            )?;
+        }
+
+        Ok(PendingAction::CreateChannel { parent, name })
+    }
+
+    /// Checks changes to a channel against the known permissions, returns the action to wait for
+    pub fn update(&self, session: &SessionState) -> AnyError<PendingAction> {
+        let Some(channel_id) = self.channel_id else {
+            return Err(Box::new(ApplicationError::new(
+                "Missing the id of the channel to update",
+            )));
+        };
+
+        let changes_links = !self.links_add.is_empty() || !self.links_remove.is_empty();
+        let changes_channel = self.parent.is_some()
+            || self.name.is_some()
+            || self.description.is_some()
+            || self.position.is_some()
+            || self.max_users.is_some();
+        if changes_links {
+            session.check_permission(
+                channel_id,
+                permission::LINK_CHANNEL,
+                "Missing permission to link this channel",
+            )?;
+        }
+        if changes_channel {
+            session.check_permission(
+                channel_id,
+                permission::WRITE,
+                "Missing permission to edit this channel",
+            )?;
+        }
+        if let Some(parent) = self.parent {
+            session.check_permission(
+                parent,
+                permission::MAKE_CHANNEL,
+                "Missing permission to move the channel there",
+            )?;
+        }
+
+        Ok(PendingAction::UpdateChannel { channel_id })
+    }
+}
+
+impl From<UpdateableChannelState> for mumble::proto::ChannelState {
+    fn from(channel: UpdateableChannelState) -> Self {
+        Self {
+            channel_id: channel.channel_id,
+            parent: channel.parent,
+            name: channel.name,
+            description: channel.description,
+            position: channel.position,
+            max_users: channel.max_users,
+            temporary: channel.temporary,
+            links_add: channel.links_add,
+            links_remove: channel.links_remove,
+            ..Default::default()
+        }
+    }
+}
+
 impl Update<mumble::proto::ChannelState> for Data {
     fn update_from(&mut self, other: &mut mumble::proto::ChannelState) -> &Self {
         self.links = mem::take(&mut other.links);
@@ -183,4 +287,8 @@ impl Manager {
         self.channels.remove(&session);
         self.notify(session);
     }
+
+    pub fn clear(&mut self) {
+        self.channels.clear();
+    }
 }
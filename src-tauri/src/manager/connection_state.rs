@@ -1,28 +1,81 @@
 use crate::{
-    protocol::serialize::message_container::FrontendMessage, utils::frontend::send_to_frontend,
+    connection::ping_stats::PingReply,
+    errors::{permission_denied_error::PermissionDeniedError, AnyError},
+    mumble,
+    protocol::serialize::message_container::FrontendMessage,
+    utils::frontend::send_to_frontend,
 };
 
+use serde::Serialize;
 use tokio::sync::broadcast::Sender;
 
+use super::pending_action::PendingAction;
+
+#[derive(Debug, Clone, Serialize)]
+struct PermissionDenied<'a> {
+    #[serde(flatten)]
+    error: &'a PermissionDeniedError,
+    action: Option<PendingAction>,
+}
+
 pub struct Manager {
     frontend_channel: Sender<String>,
     _server_channel: Sender<Vec<u8>>,
+    crypt_setup_channel: Sender<mumble::proto::CryptSetup>,
+    ping_channel: Sender<PingReply>,
 }
 
 impl Manager {
-    pub fn new(send_to: Sender<String>, server_channel: Sender<Vec<u8>>) -> Self {
+    pub fn new(
+        send_to: Sender<String>,
+        server_channel: Sender<Vec<u8>>,
+        crypt_setup_channel: Sender<mumble::proto::CryptSetup>,
+        ping_channel: Sender<PingReply>,
+    ) -> Self {
         Self {
             frontend_channel: send_to,
             _server_channel: server_channel,
+            crypt_setup_channel,
+            ping_channel,
         }
     }
 
+    pub fn notify_ping(&self, ping: &mumble::proto::Ping) -> AnyError<()> {
+        self.ping_channel.send(PingReply::Tcp {
+            timestamp: ping.timestamp(),
+        })?;
+
+        Ok(())
+    }
+
+    pub fn notify_crypt_setup(&self, crypt_setup: mumble::proto::CryptSetup) -> AnyError<()> {
+        self.crypt_setup_channel.send(crypt_setup)?;
+
+        Ok(())
+    }
+
     pub fn notify_disconnected(&self, message: &Option<String>) {
         let msg = FrontendMessage::new("disconnected", message);
 
         send_to_frontend(&self.frontend_channel, &msg);
     }
 
+    pub fn notify_permission_denied(
+        &self,
+        error: &PermissionDeniedError,
+        action: Option<PendingAction>,
+    ) {
+        let msg = FrontendMessage::new("permission_denied", PermissionDenied { error, action });
+
+        send_to_frontend(&self.frontend_channel, &msg);
+    }
+
+    pub fn notify_reconnected(&self) {
+        let msg = FrontendMessage::new("reconnected", &());
+
+        send_to_frontend(&self.frontend_channel, &msg);
+    }
+
     pub fn notify_connected(&self) {
         let msg = FrontendMessage::new("connected", &());
 
@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+use tokio::sync::broadcast::Sender;
+use tracing::{debug, warn};
+
+use crate::{
+    errors::{application_error::ApplicationError, AnyError},
+    manager::session::Shared,
+    mumble::{self, proto::context_action_modify::Operation},
+    protocol::serialize::message_container::FrontendMessage,
+    utils::frontend::send_to_frontend,
+};
+
+const CONTEXT_SERVER: u32 = 0x01;
+const CONTEXT_CHANNEL: u32 = 0x02;
+const CONTEXT_USER: u32 = 0x04;
+
+/// Where the frontend should show a context action
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContextScope {
+    Server,
+    Channel,
+    User,
+}
+
+impl ContextScope {
+    fn from_context(context: u32) -> Vec<Self> {
+        [
+            (CONTEXT_SERVER, Self::Server),
+            (CONTEXT_CHANNEL, Self::Channel),
+            (CONTEXT_USER, Self::User),
+        ]
+        .into_iter()
+        .filter(|(flag, _)| context & flag == *flag)
+        .map(|(_, scope)| scope)
+        .collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ContextAction {
+    pub action: String,
+    pub text: String,
+    pub scopes: Vec<ContextScope>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct RemovedContextAction<'a> {
+    action: &'a str,
+}
+
+/// Context actions registered by the server, by their action name
+#[derive(Debug, Default)]
+pub struct ContextActionRegistry {
+    actions: HashMap<String, ContextAction>,
+}
+
+impl ContextActionRegistry {
+    /// Checks that the server registered the action for the context it is triggered in
+    pub fn check(
+        &self,
+        action: &str,
+        session: Option<u32>,
+        channel_id: Option<u32>,
+    ) -> AnyError<()> {
+        let scope = match (session, channel_id) {
+            (Some(_), _) => ContextScope::User,
+            (None, Some(_)) => ContextScope::Channel,
+            (None, None) => ContextScope::Server,
+        };
+        let registered = self
+            .actions
+            .get(action)
+            .ok_or_else(|| ApplicationError::new("Unknown context action"))?;
+
+        if !registered.scopes.contains(&scope) {
+            return Err(Box::new(ApplicationError::new(&format!(
+                "Context action {action} is not available for {scope:?}"
+            ))));
+        }
+
+        Ok(())
+    }
+
+    fn insert(&mut self, action: ContextAction) {
+        self.actions.insert(action.action.clone(), action);
+    }
+
+    fn remove(&mut self, action: &str) -> Option<ContextAction> {
+        self.actions.remove(action)
+    }
+
+    fn clear(&mut self) {
+        self.actions.clear();
+    }
+}
+
+/// Context menu entries registered by server side plugins and bots
+pub struct Manager {
+    actions: Shared<ContextActionRegistry>,
+    frontend_channel: Sender<String>,
+}
+
+impl Manager {
+    pub fn new(send_to: Sender<String>, actions: Shared<ContextActionRegistry>) -> Self {
+        Self {
+            actions,
+            frontend_channel: send_to,
+        }
+    }
+
+    pub fn notify_context_action_modify(&mut self, modify: mumble::proto::ContextActionModify) {
+        match modify.operation() {
+            Operation::Add => {
+                let action = ContextAction {
+                    scopes: ContextScope::from_context(modify.context()),
+                    text: modify.text.unwrap_or_else(|| modify.action.clone()),
+                    action: modify.action,
+                };
+                debug!("Adding context action {}", action.action);
+
+                send_to_frontend(
+                    &self.frontend_channel,
+                    &FrontendMessage::new("context_action_added", &action),
+                );
+                self.actions.write(|actions| actions.insert(action));
+            }
+            Operation::Remove => {
+                if self
+                    .actions
+                    .write(|actions| actions.remove(&modify.action))
+                    .is_none()
+                {
+                    warn!("Tried to remove unknown context action {}", modify.action);
+                    return;
+                }
+
+                send_to_frontend(
+                    &self.frontend_channel,
+                    &FrontendMessage::new(
+                        "context_action_removed",
+                        RemovedContextAction {
+                            action: &modify.action,
+                        },
+                    ),
+                );
+            }
+        }
+    }
+
+    /// Forgets all actions, the frontend has to drop them as well
+    pub fn clear(&mut self) {
+        self.actions.write(ContextActionRegistry::clear);
+        send_to_frontend(
+            &self.frontend_channel,
+            &FrontendMessage::new("context_actions_cleared", &()),
+        );
+    }
+}
@@ -1,10 +1,22 @@
 use std::mem;
 
+pub mod acl;
+pub mod ban_list;
 pub mod channel;
 pub mod connection_state;
+pub mod context_action;
+pub mod pending_action;
+pub mod server_config;
+pub mod session;
 pub mod text_message;
 pub mod user;
+pub mod user_list;
+pub mod user_stats;
 pub mod voice;
+pub mod voice_target;
+
+#[cfg(test)]
+mod tests;
 
 trait Update<New> {
     fn update_if_some<T: Default>(original: &mut T, other: &mut Option<T>) {
@@ -0,0 +1,155 @@
+use std::collections::VecDeque;
+
+use serde::Serialize;
+use tokio::sync::broadcast::Sender;
+use tracing::trace;
+
+use crate::{
+    connection::ping_stats::current_timestamp,
+    errors::{
+        permission_denied_error::{DenialReason, PermissionDeniedError},
+        AnyError,
+    },
+};
+
+use super::acl::permission;
+
+/// An action sent to the server, which the server might still deny
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum PendingAction {
+    TextMessage { message_id: String },
+    UpdateUserState,
+    SetUserImage,
+    SubmitAcl { channel_id: u32 },
+    CreateChannel { parent: u32, name: String },
+    UpdateChannel { channel_id: u32 },
+    RemoveChannel { channel_id: u32 },
+    KickUser { session: u32 },
+    BanUser { session: u32 },
+    ModerateUser { session: u32 },
+    SubmitBanList,
+    UpdateUserList { user_id: u32 },
+    RegisterSelf,
+    ListenToChannels { channel_ids: Vec<u32> },
+    StopListeningToChannels { channel_ids: Vec<u32> },
+    SetListenerVolume { channel_id: u32 },
+}
+
+impl PendingAction {
+    fn may_be_denied_with(&self, error: &PermissionDeniedError) -> bool {
+        match error.reason {
+            DenialReason::Text | DenialReason::SuperUser => true,
+            DenialReason::Permission => match self {
+                Self::TextMessage { .. } => error.permission == Some(permission::TEXT_MESSAGE),
+                Self::SubmitAcl { channel_id }
+                | Self::UpdateChannel { channel_id }
+                | Self::RemoveChannel { channel_id }
+                | Self::SetListenerVolume { channel_id }
+                | Self::CreateChannel {
+                    parent: channel_id, ..
+                } => error
+                    .channel_id
+                    .map_or(true, |denied| denied == *channel_id),
+                Self::KickUser { session }
+                | Self::BanUser { session }
+                | Self::ModerateUser { session } => {
+                    error.session.map_or(true, |denied| denied == *session)
+                }
+                Self::ListenToChannels { channel_ids }
+                | Self::StopListeningToChannels { channel_ids } => error
+                    .channel_id
+                    .map_or(true, |denied| channel_ids.contains(&denied)),
+                Self::UpdateUserState
+                | Self::SetUserImage
+                | Self::SubmitBanList
+                | Self::UpdateUserList { .. }
+                | Self::RegisterSelf => true,
+            },
+            DenialReason::TextTooLong => matches!(
+                self,
+                Self::TextMessage { .. }
+                    | Self::UpdateUserState
+                    | Self::SetUserImage
+                    | Self::CreateChannel { .. }
+                    | Self::UpdateChannel { .. }
+            ),
+            DenialReason::MissingCertificate => {
+                matches!(self, Self::UpdateUserState | Self::RegisterSelf)
+            }
+            DenialReason::UserName => {
+                matches!(self, Self::UpdateUserState | Self::UpdateUserList { .. })
+            }
+            DenialReason::ChannelListenerLimit | DenialReason::UserListenerLimit => {
+                matches!(self, Self::UpdateUserState | Self::ListenToChannels { .. })
+            }
+            DenialReason::ChannelFull => {
+                matches!(self, Self::UpdateUserState | Self::ModerateUser { .. })
+            }
+            DenialReason::ChannelName
+            | DenialReason::TemporaryChannel
+            | DenialReason::NestingLimit => {
+                matches!(
+                    self,
+                    Self::CreateChannel { .. } | Self::UpdateChannel { .. }
+                )
+            }
+            DenialReason::ChannelCountLimit => matches!(self, Self::CreateChannel { .. }),
+            DenialReason::H9k => false,
+        }
+    }
+}
+
+/// Actions which weren't processed by the server yet
+///
+/// The server handles messages in order, so everything sent before a ping it answered went through.
+/// Actions and pings are sent while holding the write lock of the session state, so the order of
+/// their timestamps is the order in which they are written to the server.
+#[derive(Debug, Default)]
+pub struct PendingActions {
+    actions: VecDeque<(u64, PendingAction)>,
+}
+
+impl PendingActions {
+    /// Sends the message of the action to the server and keeps the action until it is confirmed
+    pub fn send(
+        &mut self,
+        action: PendingAction,
+        server_channel: &Sender<Vec<u8>>,
+        message: Vec<u8>,
+    ) -> AnyError<()> {
+        let timestamp = current_timestamp()?;
+        server_channel.send(message)?;
+        self.actions.push_back((timestamp, action));
+
+        Ok(())
+    }
+
+    /// Removes and returns all actions sent before the ping with the given timestamp
+    pub fn confirm(&mut self, ping_timestamp: u64) -> Vec<PendingAction> {
+        let (confirmed, pending): (VecDeque<_>, VecDeque<_>) = self
+            .actions
+            .drain(..)
+            .partition(|(timestamp, _)| *timestamp < ping_timestamp);
+        self.actions = pending;
+
+        confirmed.into_iter().map(|(_, action)| action).collect()
+    }
+
+    /// Returns the oldest pending action which could have caused the denial
+    pub fn resolve(&mut self, error: &PermissionDeniedError) -> Option<PendingAction> {
+        let index = self
+            .actions
+            .iter()
+            .position(|(_, action)| action.may_be_denied_with(error))?;
+        let (_, action) = self.actions.remove(index)?;
+        trace!("Permission denied for {:?}", action);
+
+        Some(action)
+    }
+
+    /// Removes all actions, e.g. because the connection they were sent with is lost
+    pub fn drain(&mut self) -> Vec<PendingAction> {
+        self.actions.drain(..).map(|(_, action)| action).collect()
+    }
+}
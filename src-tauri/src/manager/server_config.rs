@@ -0,0 +1,213 @@
+use serde::Serialize;
+use tokio::sync::broadcast::Sender;
+use tracing::debug;
+
+use crate::{
+    errors::{application_error::ApplicationError, AnyError},
+    manager::session::Shared,
+    mumble,
+    protocol::{
+        format_version, from_legacy_version, serialize::message_container::FrontendMessage,
+        VoiceFormat,
+    },
+    utils::frontend::send_to_frontend,
+};
+
+/// Limits and features of the server, `None` if the server didn't tell us
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct ServerConfig {
+    pub max_bandwidth: Option<u32>,
+    pub welcome_text: Option<String>,
+    pub allow_html: Option<bool>,
+    pub message_length: Option<u32>,
+    pub image_message_length: Option<u32>,
+    pub max_users: Option<u32>,
+    pub recording_allowed: Option<bool>,
+    #[serde(skip)]
+    pub version: Option<u64>,
+}
+
+impl ServerConfig {
+    pub fn voice_format(&self) -> VoiceFormat {
+        self.version
+            .map_or(VoiceFormat::Legacy, VoiceFormat::for_server_version)
+    }
+
+    fn update_from(&mut self, config: mumble::proto::ServerConfig) {
+        // the server only sends what changed
+        self.max_bandwidth = config.max_bandwidth.or(self.max_bandwidth);
+        if config.welcome_text.is_some() {
+            self.welcome_text = config.welcome_text;
+        }
+        self.allow_html = config.allow_html.or(self.allow_html);
+        self.message_length = config.message_length.or(self.message_length);
+        self.image_message_length = config.image_message_length.or(self.image_message_length);
+        self.max_users = config.max_users.or(self.max_users);
+        self.recording_allowed = config.recording_allowed.or(self.recording_allowed);
+    }
+
+    /// Checks a text message or comment against the length limits, 0 means unlimited
+    pub fn check_message_length(&self, message: &str) -> AnyError<()> {
+        let is_image = message.contains("<img");
+        let limit = if is_image {
+            self.image_message_length
+        } else {
+            self.message_length
+        };
+        // the server counts characters, not bytes
+        let length = message.chars().count();
+
+        match limit {
+            Some(limit) if limit > 0 && length > limit as usize => {
+                Err(Box::new(ApplicationError::new(
+                    format!(
+                        "{} is too long for this server ({length} > {limit})",
+                        if is_image { "Image message" } else { "Message" },
+                    )
+                    .as_str(),
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the message as it may be sent to the server, HTML is reduced to plain text
+    /// if the server doesn't allow it
+    pub fn prepare_message(&self, message: &str) -> AnyError<String> {
+        let message = if self.allow_html == Some(false) {
+            if message.contains("<img") {
+                return Err(Box::new(ApplicationError::new(
+                    "This server doesn't allow images in messages",
+                )));
+            }
+            strip_html(message)
+        } else {
+            message.to_string()
+        };
+        self.check_message_length(&message)?;
+
+        Ok(message)
+    }
+}
+
+/// Removes all tags from the message and unescapes the common entities, line breaks are kept
+fn strip_html(message: &str) -> String {
+    let mut text = String::with_capacity(message.len());
+    let mut rest = message;
+
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('>') else {
+            // not a tag, just a lonely '<'
+            rest = &rest[start..];
+            break;
+        };
+
+        let tag = rest[start + 1..start + end]
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        if matches!(tag.as_str(), "br" | "p" | "div" | "li")
+            && !text.is_empty()
+            && !text.ends_with('\n')
+        {
+            text.push('\n');
+        }
+        rest = &rest[start + end + 1..];
+    }
+    text.push_str(rest);
+
+    text.trim_end()
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+struct SuggestedConfig {
+    version: Option<String>,
+    positional: Option<bool>,
+    push_to_talk: Option<bool>,
+}
+
+impl From<mumble::proto::SuggestConfig> for SuggestedConfig {
+    fn from(config: mumble::proto::SuggestConfig) -> Self {
+        Self {
+            version: config
+                .version_v2
+                .or_else(|| config.version_v1.map(from_legacy_version))
+                .map(format_version),
+            positional: config.positional,
+            push_to_talk: config.push_to_talk,
+        }
+    }
+}
+
+pub struct Manager {
+    frontend_channel: Sender<String>,
+    config: Shared<ServerConfig>,
+}
+
+impl Manager {
+    pub fn new(send_to: Sender<String>, config: Shared<ServerConfig>) -> Self {
+        Self {
+            frontend_channel: send_to,
+            config,
+        }
+    }
+
+    fn notify(&self, config: &ServerConfig) {
+        send_to_frontend(
+            &self.frontend_channel,
+            &FrontendMessage::new("server_config", config),
+        );
+    }
+
+    /// Returns the maximum bandwidth the server allows for our voice data
+    pub fn notify_server_config(&self, server_config: mumble::proto::ServerConfig) -> Option<u32> {
+        debug!("Received server config: {:?}", server_config);
+        let config = self.config.write(|config| {
+            config.update_from(server_config);
+            config.clone()
+        });
+        self.notify(&config);
+
+        config.max_bandwidth
+    }
+
+    pub fn notify_max_bandwidth(&self, max_bandwidth: u32) {
+        let config = self.config.write(|config| {
+            config.max_bandwidth = Some(max_bandwidth);
+            config.clone()
+        });
+        self.notify(&config);
+    }
+
+    /// Returns the voice format to use with the server
+    pub fn notify_version(&self, version: &mumble::proto::Version) -> VoiceFormat {
+        let server_version = version
+            .version_v2
+            .or_else(|| version.version_v1.map(from_legacy_version));
+        debug!(
+            "Received server version: {:?}",
+            server_version.map(format_version)
+        );
+
+        self.config.write(|config| {
+            config.version = server_version;
+            config.voice_format()
+        })
+    }
+
+    pub fn notify_suggest_config(&self, suggest_config: mumble::proto::SuggestConfig) {
+        send_to_frontend(
+            &self.frontend_channel,
+            &FrontendMessage::new("suggest_config", SuggestedConfig::from(suggest_config)),
+        );
+    }
+}
@@ -0,0 +1,89 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, PoisonError, RwLock},
+};
+
+use crate::{
+    errors::{application_error::ApplicationError, AnyError},
+    mumble,
+};
+
+use super::{
+    context_action::ContextActionRegistry, pending_action::PendingActions,
+    server_config::ServerConfig, voice_target::VoiceTargetRegistry,
+};
+
+/// A value behind a lock, which is cheap to clone and hand to another thread.
+///
+/// The values are only updated with complete operations, so a panicking writer doesn't leave
+/// them half changed and a poisoned lock is used as it is.
+#[derive(Debug, Default)]
+pub struct Shared<T> {
+    value: Arc<RwLock<T>>,
+}
+
+impl<T> Clone for Shared<T> {
+    fn clone(&self) -> Self {
+        Self {
+            value: self.value.clone(),
+        }
+    }
+}
+
+impl<T> Shared<T> {
+    pub fn read<R>(&self, read: impl FnOnce(&T) -> R) -> R {
+        read(&self.value.read().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    pub fn write<R>(&self, write: impl FnOnce(&mut T) -> R) -> R {
+        write(&mut self.value.write().unwrap_or_else(PoisonError::into_inner))
+    }
+}
+
+/// What we know about the current session.
+///
+/// The message router keeps it up to date with what the server tells us, the connection reads it
+/// to check commands before they are sent.
+#[derive(Debug, Default, Clone)]
+pub struct SessionState {
+    /// Permissions of the current user by channel id
+    pub permissions: Shared<HashMap<u32, u32>>,
+    /// Channel id of every user by session
+    pub user_channels: Shared<HashMap<u32, u32>>,
+    pub pending_actions: Shared<PendingActions>,
+    pub server_config: Shared<ServerConfig>,
+    pub context_actions: Shared<ContextActionRegistry>,
+    pub voice_targets: Shared<VoiceTargetRegistry>,
+    /// Entries of the last ban list which we couldn't show, they are kept for the next submit
+    pub unreadable_bans: Shared<Vec<mumble::proto::ban_list::BanEntry>>,
+}
+
+impl SessionState {
+    /// Returns `None` if the server didn't tell us the permissions for this channel yet
+    pub fn has_permission(&self, channel_id: u32, permission: u32) -> Option<bool> {
+        self.permissions.read(|permissions| {
+            permissions
+                .get(&channel_id)
+                .map(|granted| granted & permission == permission)
+        })
+    }
+
+    /// Fails early if the server told us we are not allowed to do this, otherwise the server decides
+    pub fn check_permission(
+        &self,
+        channel_id: u32,
+        permission: u32,
+        message: &str,
+    ) -> AnyError<()> {
+        if self.has_permission(channel_id, permission) == Some(false) {
+            return Err(Box::new(ApplicationError::new(message)));
+        }
+
+        Ok(())
+    }
+
+    pub fn user_channel(&self, session: u32) -> Option<u32> {
+        self.user_channels
+            .read(|channels| channels.get(&session).copied())
+    }
+}
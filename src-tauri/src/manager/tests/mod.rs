@@ -0,0 +1,11 @@
+mod test_acl;
+mod test_ban_list;
+mod test_channel;
+mod test_context_action;
+mod test_pending_action;
+mod test_server_config;
+mod test_text_message;
+mod test_user;
+mod test_user_list;
+mod test_user_stats;
+mod test_voice_target;
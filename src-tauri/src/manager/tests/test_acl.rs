@@ -0,0 +1,141 @@
+mod tests {
+    use tokio::sync::broadcast;
+
+    use crate::{
+        manager::{
+            acl::{permission, AclEntry, ChannelAcl, ChannelGroup, Manager},
+            session::SessionState,
+        },
+        mumble,
+    };
+
+    fn group(name: &str, inherited: bool) -> ChannelGroup {
+        ChannelGroup {
+            name: name.to_string(),
+            inherited,
+            inherit: true,
+            inheritable: true,
+            inherited_members: vec![1, 2],
+            ..Default::default()
+        }
+    }
+
+    fn entry(group: &str, inherited: bool) -> AclEntry {
+        AclEntry {
+            apply_here: true,
+            apply_subs: true,
+            inherited,
+            user_id: None,
+            group: Some(group.to_string()),
+            grant: permission::ENTER,
+            deny: permission::SPEAK,
+        }
+    }
+
+    #[test]
+    fn test_from_proto() {
+        let acl = mumble::proto::Acl {
+            channel_id: 3,
+            inherit_acls: Some(false),
+            groups: vec![mumble::proto::acl::ChanGroup {
+                name: "admin".to_string(),
+                inherited: Some(true),
+                inherit: Some(false),
+                inheritable: Some(true),
+                add: vec![5],
+                remove: vec![6],
+                inherited_members: vec![7],
+            }],
+            acls: vec![mumble::proto::acl::ChanAcl {
+                apply_here: Some(true),
+                apply_subs: Some(false),
+                inherited: Some(true),
+                user_id: Some(4),
+                group: None,
+                grant: Some(permission::KICK),
+                deny: Some(permission::BAN),
+            }],
+            query: None,
+        };
+
+        let acl = ChannelAcl::from(acl);
+        assert_eq!(acl.channel_id, 3);
+        assert!(!acl.inherit_acls);
+
+        let group = &acl.groups[0];
+        assert_eq!(group.name, "admin");
+        assert!(group.inherited && !group.inherit && group.inheritable);
+        assert_eq!(group.add, vec![5]);
+        assert_eq!(group.remove, vec![6]);
+        assert_eq!(group.inherited_members, vec![7]);
+
+        let entry = &acl.acls[0];
+        assert!(entry.apply_here && !entry.apply_subs && entry.inherited);
+        assert_eq!(entry.user_id, Some(4));
+        assert_eq!(entry.grant, permission::KICK);
+        assert_eq!(entry.deny, permission::BAN);
+    }
+
+    #[test]
+    fn test_submit_skips_inherited_entries() {
+        let mut modified_group = group("modified", true);
+        modified_group.add = vec![9];
+        let acl = ChannelAcl {
+            channel_id: 3,
+            inherit_acls: true,
+            groups: vec![
+                group("local", false),
+                group("inherited", true),
+                modified_group,
+            ],
+            acls: vec![entry("local", false), entry("inherited", true)],
+        };
+
+        let acl = mumble::proto::Acl::from(acl);
+        assert_eq!(acl.channel_id, 3);
+        assert_eq!(acl.inherit_acls, Some(true));
+        assert_eq!(acl.query, Some(false));
+
+        // inherited groups are only sent if they were changed on this channel
+        let groups = acl
+            .groups
+            .iter()
+            .map(|group| group.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(groups, vec!["local", "modified"]);
+        assert!(acl
+            .groups
+            .iter()
+            .all(|group| group.inherited_members.is_empty()));
+
+        assert_eq!(acl.acls.len(), 1);
+        assert_eq!(acl.acls[0].group.as_deref(), Some("local"));
+        assert_eq!(acl.acls[0].inherited, Some(false));
+        assert_eq!(acl.acls[0].grant, Some(permission::ENTER));
+        assert_eq!(acl.acls[0].deny, Some(permission::SPEAK));
+    }
+
+    #[test]
+    fn test_permission_cache() {
+        let (frontend_channel, _) = broadcast::channel(16);
+        let (server_channel, _) = broadcast::channel(16);
+        let session = SessionState::default();
+        let manager = Manager::new(
+            frontend_channel,
+            server_channel,
+            session.permissions.clone(),
+        );
+
+        assert_eq!(session.has_permission(0, permission::ENTER), None);
+
+        manager.update_permissions(0, permission::ENTER | permission::SPEAK);
+        assert_eq!(session.has_permission(0, permission::ENTER), Some(true));
+        assert_eq!(
+            session.has_permission(0, permission::ENTER | permission::KICK),
+            Some(false)
+        );
+
+        manager.clear();
+        assert_eq!(session.has_permission(0, permission::ENTER), None);
+    }
+}
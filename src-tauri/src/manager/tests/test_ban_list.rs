@@ -0,0 +1,127 @@
+mod tests {
+    use serde_json::Value;
+    use tokio::sync::broadcast;
+
+    use crate::{
+        manager::{
+            ban_list::{ban_list_submission, BanEntry, Manager},
+            session::SessionState,
+        },
+        mumble,
+    };
+
+    fn ban(address: &str, mask: u32) -> BanEntry {
+        BanEntry {
+            address: address.to_string(),
+            mask,
+            reason: Some("spam".to_string()),
+            duration: 60,
+            ..Default::default()
+        }
+    }
+
+    fn round_trip(entry: BanEntry) -> (mumble::proto::ban_list::BanEntry, BanEntry) {
+        let proto =
+            mumble::proto::ban_list::BanEntry::try_from(entry).expect("Failed to convert ban");
+        let entry = BanEntry::try_from(proto.clone()).expect("Failed to convert ban back");
+
+        (proto, entry)
+    }
+
+    #[test]
+    fn test_ipv4_round_trip() {
+        let (proto, entry) = round_trip(ban("192.168.1.0", 24));
+
+        // ::ffff:192.168.1.0 with the 96 bit prefix of the mapping
+        let mut expected = vec![0; 10];
+        expected.extend([0xff, 0xff, 192, 168, 1, 0]);
+        assert_eq!(proto.address, expected);
+        assert_eq!(proto.mask, 120);
+        assert_eq!(proto.duration, Some(60));
+
+        assert_eq!(entry.address, "192.168.1.0");
+        assert_eq!(entry.mask, 24);
+        assert_eq!(entry.reason.as_deref(), Some("spam"));
+    }
+
+    #[test]
+    fn test_ipv6_round_trip() {
+        let (proto, entry) = round_trip(ban("2001:db8::", 32));
+
+        assert_eq!(proto.address.len(), 16);
+        assert_eq!(proto.mask, 32);
+        assert_eq!(entry.address, "2001:db8::");
+        assert_eq!(entry.mask, 32);
+    }
+
+    #[test]
+    fn test_invalid_mask() {
+        assert!(mumble::proto::ban_list::BanEntry::try_from(ban("10.0.0.1", 33)).is_err());
+        assert!(mumble::proto::ban_list::BanEntry::try_from(ban("::1", 129)).is_err());
+        assert!(mumble::proto::ban_list::BanEntry::try_from(ban("no address", 8)).is_err());
+    }
+
+    fn unreadable_ban() -> mumble::proto::ban_list::BanEntry {
+        mumble::proto::ban_list::BanEntry {
+            address: vec![10, 0, 0, 1],
+            mask: 32,
+            name: Some("old client".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_keep_unreadable_entries() {
+        let (frontend_channel, mut receiver) = broadcast::channel(16);
+        let session = SessionState::default();
+        let manager = Manager::new(frontend_channel, session.unreadable_bans.clone());
+        let valid = mumble::proto::ban_list::BanEntry::try_from(ban("10.0.0.1", 32))
+            .expect("Failed to convert ban");
+
+        manager.notify_ban_list(mumble::proto::BanList {
+            bans: vec![unreadable_ban(), valid],
+            query: None,
+        });
+
+        let message: Value = serde_json::from_str(&receiver.try_recv().expect("No ban list sent"))
+            .expect("Invalid json");
+        assert_eq!(message["message_type"], "ban_list");
+        let bans = message["data"].as_array().expect("Bans are no list");
+        assert_eq!(bans.len(), 1);
+        assert_eq!(bans[0]["address"], "10.0.0.1");
+        assert_eq!(bans[0]["mask"], 32);
+        assert_eq!(
+            session.unreadable_bans.read(Clone::clone),
+            vec![unreadable_ban()]
+        );
+    }
+
+    #[test]
+    fn test_submission_sends_unreadable_entries_back() {
+        let ban_list = ban_list_submission(vec![ban("10.0.0.1", 32)], &[unreadable_ban()])
+            .expect("Failed to build ban list");
+
+        assert_eq!(ban_list.query, Some(false));
+        assert_eq!(ban_list.bans.len(), 2);
+        assert_eq!(ban_list.bans[0].address.len(), 16);
+        assert_eq!(ban_list.bans[1], unreadable_ban());
+    }
+
+    #[test]
+    fn test_newer_ban_list_replaces_unreadable_entries() {
+        let (frontend_channel, _receiver) = broadcast::channel(16);
+        let session = SessionState::default();
+        let manager = Manager::new(frontend_channel, session.unreadable_bans.clone());
+
+        manager.notify_ban_list(mumble::proto::BanList {
+            bans: vec![unreadable_ban()],
+            query: None,
+        });
+        manager.notify_ban_list(mumble::proto::BanList {
+            bans: Vec::new(),
+            query: None,
+        });
+
+        assert!(session.unreadable_bans.read(Vec::is_empty));
+    }
+}
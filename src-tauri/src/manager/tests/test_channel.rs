@@ -0,0 +1,211 @@
+mod tests {
+    use crate::{
+        manager::{
+            acl::permission,
+            channel::{Data, UpdateableChannelState},
+            pending_action::PendingAction,
+            session::SessionState,
+            Update,
+        },
+        mumble,
+    };
+
+    const PARENT: u32 = 1;
+    const CHANNEL: u32 = 5;
+
+    fn session_with(permissions: &[(u32, u32)]) -> SessionState {
+        let session = SessionState::default();
+        session.permissions.write(|known| {
+            for (channel_id, granted) in permissions {
+                known.insert(*channel_id, *granted);
+            }
+        });
+
+        session
+    }
+
+    fn new_channel(temporary: Option<bool>) -> UpdateableChannelState {
+        UpdateableChannelState {
+            parent: Some(PARENT),
+            name: Some("Lobby".to_string()),
+            temporary,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_create_needs_parent_and_name() {
+        let session = SessionState::default();
+        let without_parent = UpdateableChannelState {
+            name: Some("Lobby".to_string()),
+            ..Default::default()
+        };
+        let without_name = UpdateableChannelState {
+            parent: Some(PARENT),
+            ..Default::default()
+        };
+
+        assert!(without_parent.creation(&session).is_err());
+        assert!(without_name.creation(&session).is_err());
+    }
+
+    #[test]
+    fn test_create_rejects_id() {
+        let session = SessionState::default();
+        let channel = UpdateableChannelState {
+            channel_id: Some(CHANNEL),
+            ..new_channel(None)
+        };
+
+        assert!(channel.creation(&session).is_err());
+    }
+
+    #[test]
+    fn test_create_with_unknown_permissions() {
+        let session = SessionState::default();
+
+        let action = new_channel(None)
+            .creation(&session)
+            .expect("Failed to create");
+
+        assert_eq!(
+            action,
+            PendingAction::CreateChannel {
+                parent: PARENT,
+                name: "Lobby".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_create_temporary_channel_permission() {
+        let temp_only = session_with(&[(PARENT, permission::MAKE_TEMP_CHANNEL)]);
+        let channel_only = session_with(&[(PARENT, permission::MAKE_CHANNEL)]);
+
+        assert!(new_channel(Some(true)).creation(&temp_only).is_ok());
+        assert!(new_channel(Some(true)).creation(&channel_only).is_err());
+    }
+
+    #[test]
+    fn test_create_normal_channel_permission() {
+        let temp_only = session_with(&[(PARENT, permission::MAKE_TEMP_CHANNEL)]);
+        let channel_only = session_with(&[(PARENT, permission::MAKE_CHANNEL)]);
+
+        assert!(new_channel(None).creation(&channel_only).is_ok());
+        assert!(new_channel(Some(false)).creation(&channel_only).is_ok());
+        assert!(new_channel(None).creation(&temp_only).is_err());
+        assert!(new_channel(Some(false)).creation(&temp_only).is_err());
+    }
+
+    #[test]
+    fn test_update_needs_id() {
+        let session = SessionState::default();
+        let channel = UpdateableChannelState {
+            name: Some("Lobby".to_string()),
+            ..Default::default()
+        };
+
+        assert!(channel.update(&session).is_err());
+    }
+
+    #[test]
+    fn test_update_links_permission() {
+        let session = session_with(&[(CHANNEL, permission::WRITE)]);
+        let links = UpdateableChannelState {
+            channel_id: Some(CHANNEL),
+            links_add: vec![2],
+            ..Default::default()
+        };
+        let name = UpdateableChannelState {
+            channel_id: Some(CHANNEL),
+            name: Some("Lobby".to_string()),
+            ..Default::default()
+        };
+
+        assert!(links.update(&session).is_err());
+        assert_eq!(
+            name.update(&session).expect("Failed to update"),
+            PendingAction::UpdateChannel {
+                channel_id: CHANNEL
+            }
+        );
+    }
+
+    #[test]
+    fn test_update_move_needs_permission_on_new_parent() {
+        let session = session_with(&[(CHANNEL, permission::WRITE), (PARENT, permission::NONE)]);
+        let channel = UpdateableChannelState {
+            channel_id: Some(CHANNEL),
+            parent: Some(PARENT),
+            ..Default::default()
+        };
+
+        assert!(channel.update(&session).is_err());
+    }
+
+    #[test]
+    fn test_into_channel_state() {
+        let channel = UpdateableChannelState {
+            channel_id: Some(CHANNEL),
+            parent: Some(PARENT),
+            name: Some("Lobby".to_string()),
+            description: Some("Welcome".to_string()),
+            position: Some(-2),
+            max_users: Some(10),
+            temporary: Some(false),
+            links_add: vec![2, 3],
+            links_remove: vec![4],
+        };
+
+        let channel_state: mumble::proto::ChannelState = channel.into();
+
+        assert_eq!(channel_state.channel_id, Some(CHANNEL));
+        assert_eq!(channel_state.parent, Some(PARENT));
+        assert_eq!(channel_state.name.as_deref(), Some("Lobby"));
+        assert_eq!(channel_state.description.as_deref(), Some("Welcome"));
+        assert_eq!(channel_state.position, Some(-2));
+        assert_eq!(channel_state.max_users, Some(10));
+        assert_eq!(channel_state.temporary, Some(false));
+        assert_eq!(channel_state.links_add, vec![2, 3]);
+        assert_eq!(channel_state.links_remove, vec![4]);
+        assert!(channel_state.links.is_empty());
+    }
+
+    #[test]
+    fn test_into_channel_state_leaves_unchanged_fields_out() {
+        let channel = UpdateableChannelState {
+            channel_id: Some(CHANNEL),
+            links_remove: vec![4],
+            ..Default::default()
+        };
+
+        let channel_state: mumble::proto::ChannelState = channel.into();
+
+        assert_eq!(channel_state.parent, None);
+        assert_eq!(channel_state.name, None);
+        assert_eq!(channel_state.description, None);
+        assert!(channel_state.links_add.is_empty());
+        assert_eq!(channel_state.links_remove, vec![4]);
+    }
+
+    #[test]
+    fn test_update_from_takes_links() {
+        let mut data = Data {
+            links: vec![7],
+            ..Default::default()
+        };
+        let mut channel_state = mumble::proto::ChannelState {
+            channel_id: Some(CHANNEL),
+            name: Some("Lobby".to_string()),
+            links: vec![2, 3],
+            ..Default::default()
+        };
+
+        data.update_from(&mut channel_state);
+
+        assert_eq!(data.channel_id, CHANNEL);
+        assert_eq!(data.name, "Lobby");
+        assert_eq!(data.links, vec![2, 3]);
+        assert!(channel_state.links.is_empty());
+    }
+}
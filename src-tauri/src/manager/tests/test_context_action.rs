@@ -0,0 +1,70 @@
+mod tests {
+    use tokio::sync::broadcast;
+
+    use crate::{
+        manager::{
+            context_action::{ContextActionRegistry, Manager},
+            session::Shared,
+        },
+        mumble::{self, proto::context_action_modify::Operation},
+    };
+
+    // channel and user context, like the actions of most bots
+    const CONTEXT_CHANNEL_AND_USER: u32 = 0x06;
+
+    fn modify(action: &str, operation: Operation) -> mumble::proto::ContextActionModify {
+        mumble::proto::ContextActionModify {
+            action: action.to_string(),
+            text: Some("Do something".to_string()),
+            context: Some(CONTEXT_CHANNEL_AND_USER),
+            operation: Some(operation as i32),
+        }
+    }
+
+    fn allowed(
+        registry: &Shared<ContextActionRegistry>,
+        action: &str,
+        session: Option<u32>,
+        channel_id: Option<u32>,
+    ) -> bool {
+        registry.read(|actions| actions.check(action, session, channel_id).is_ok())
+    }
+
+    #[test]
+    fn test_check_registered_actions() {
+        let (frontend_channel, _frontend_receiver) = broadcast::channel(16);
+        let registry = Shared::<ContextActionRegistry>::default();
+        let mut manager = Manager::new(frontend_channel, registry.clone());
+
+        assert!(!allowed(&registry, "bot", Some(1), None));
+
+        manager.notify_context_action_modify(modify("bot", Operation::Add));
+        assert!(allowed(&registry, "bot", Some(1), None));
+        assert!(allowed(&registry, "bot", None, Some(2)));
+        assert!(!allowed(&registry, "bot", None, None));
+        assert!(!allowed(&registry, "other", Some(1), None));
+
+        manager.notify_context_action_modify(modify("bot", Operation::Remove));
+        assert!(!allowed(&registry, "bot", Some(1), None));
+    }
+
+    #[test]
+    fn test_clear_notifies_frontend() {
+        let (frontend_channel, mut frontend_receiver) = broadcast::channel(16);
+        let registry = Shared::<ContextActionRegistry>::default();
+        let mut manager = Manager::new(frontend_channel, registry.clone());
+
+        manager.notify_context_action_modify(modify("bot", Operation::Add));
+        assert!(frontend_receiver
+            .try_recv()
+            .expect("No action added")
+            .contains("context_action_added"));
+
+        manager.clear();
+        assert!(frontend_receiver
+            .try_recv()
+            .expect("No clear sent")
+            .contains("context_actions_cleared"));
+        assert!(!allowed(&registry, "bot", Some(1), None));
+    }
+}
@@ -0,0 +1,332 @@
+mod tests {
+    use std::{thread, time::Duration};
+
+    use tokio::sync::broadcast;
+
+    use crate::{
+        connection::ping_stats::current_timestamp,
+        errors::permission_denied_error::{DenialReason, PermissionDeniedError},
+        manager::{
+            acl::permission,
+            pending_action::{PendingAction, PendingActions},
+        },
+    };
+
+    fn denial(reason: DenialReason) -> PermissionDeniedError {
+        PermissionDeniedError {
+            reason,
+            permission: None,
+            channel_id: None,
+            session: None,
+            name: None,
+            details: None,
+        }
+    }
+
+    fn text_message(message_id: &str) -> PendingAction {
+        PendingAction::TextMessage {
+            message_id: message_id.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_send_keeps_action_until_confirmed() {
+        let (server_channel, mut server_receiver) = broadcast::channel(16);
+        let mut pending_actions = PendingActions::default();
+
+        pending_actions
+            .send(text_message("first"), &server_channel, vec![1])
+            .expect("Failed to send");
+        assert_eq!(server_receiver.try_recv().expect("Nothing sent"), vec![1]);
+
+        thread::sleep(Duration::from_millis(2));
+        let ping_timestamp = current_timestamp().expect("No time");
+        thread::sleep(Duration::from_millis(2));
+
+        pending_actions
+            .send(text_message("second"), &server_channel, vec![2])
+            .expect("Failed to send");
+
+        assert_eq!(
+            pending_actions.confirm(ping_timestamp),
+            vec![text_message("first")]
+        );
+        // the confirmed message went through, so a denial can only belong to the second one
+        assert_eq!(
+            pending_actions.resolve(&denial(DenialReason::TextTooLong)),
+            Some(text_message("second"))
+        );
+        assert!(pending_actions.drain().is_empty());
+    }
+
+    #[test]
+    fn test_failed_send_is_not_pending() {
+        let (server_channel, server_receiver) = broadcast::channel::<Vec<u8>>(16);
+        drop(server_receiver);
+        let mut pending_actions = PendingActions::default();
+
+        assert!(pending_actions
+            .send(text_message("lost"), &server_channel, vec![1])
+            .is_err());
+        assert!(pending_actions.drain().is_empty());
+    }
+
+    fn all_actions() -> Vec<PendingAction> {
+        vec![
+            text_message("abc"),
+            PendingAction::UpdateUserState,
+            PendingAction::SetUserImage,
+            PendingAction::SubmitAcl { channel_id: 1 },
+            PendingAction::CreateChannel {
+                parent: 1,
+                name: "new".to_string(),
+            },
+            PendingAction::UpdateChannel { channel_id: 1 },
+            PendingAction::RemoveChannel { channel_id: 1 },
+            PendingAction::KickUser { session: 2 },
+            PendingAction::BanUser { session: 2 },
+            PendingAction::ModerateUser { session: 2 },
+            PendingAction::SubmitBanList,
+            PendingAction::UpdateUserList { user_id: 3 },
+            PendingAction::RegisterSelf,
+            PendingAction::ListenToChannels {
+                channel_ids: vec![1, 4],
+            },
+            PendingAction::StopListeningToChannels {
+                channel_ids: vec![4],
+            },
+            PendingAction::SetListenerVolume { channel_id: 4 },
+        ]
+    }
+
+    /// Returns the actions which could be the cause of the denial
+    fn candidates(error: &PermissionDeniedError) -> Vec<PendingAction> {
+        all_actions()
+            .into_iter()
+            .filter(|action| {
+                let (server_channel, _server_receiver) = broadcast::channel(16);
+                let mut pending_actions = PendingActions::default();
+                pending_actions
+                    .send(action.clone(), &server_channel, Vec::new())
+                    .expect("Failed to send");
+                pending_actions.resolve(error).as_ref() == Some(action)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_denial_reasons() {
+        let every_action = all_actions();
+        let table = vec![
+            (DenialReason::Text, every_action.clone()),
+            (DenialReason::SuperUser, every_action),
+            (
+                DenialReason::TextTooLong,
+                vec![
+                    text_message("abc"),
+                    PendingAction::UpdateUserState,
+                    PendingAction::SetUserImage,
+                    PendingAction::CreateChannel {
+                        parent: 1,
+                        name: "new".to_string(),
+                    },
+                    PendingAction::UpdateChannel { channel_id: 1 },
+                ],
+            ),
+            (
+                DenialReason::MissingCertificate,
+                vec![PendingAction::UpdateUserState, PendingAction::RegisterSelf],
+            ),
+            (
+                DenialReason::UserName,
+                vec![
+                    PendingAction::UpdateUserState,
+                    PendingAction::UpdateUserList { user_id: 3 },
+                ],
+            ),
+            (
+                DenialReason::ChannelListenerLimit,
+                vec![
+                    PendingAction::UpdateUserState,
+                    PendingAction::ListenToChannels {
+                        channel_ids: vec![1, 4],
+                    },
+                ],
+            ),
+            (
+                DenialReason::UserListenerLimit,
+                vec![
+                    PendingAction::UpdateUserState,
+                    PendingAction::ListenToChannels {
+                        channel_ids: vec![1, 4],
+                    },
+                ],
+            ),
+            (
+                DenialReason::ChannelFull,
+                vec![
+                    PendingAction::UpdateUserState,
+                    PendingAction::ModerateUser { session: 2 },
+                ],
+            ),
+            (
+                DenialReason::ChannelName,
+                vec![
+                    PendingAction::CreateChannel {
+                        parent: 1,
+                        name: "new".to_string(),
+                    },
+                    PendingAction::UpdateChannel { channel_id: 1 },
+                ],
+            ),
+            (
+                DenialReason::TemporaryChannel,
+                vec![
+                    PendingAction::CreateChannel {
+                        parent: 1,
+                        name: "new".to_string(),
+                    },
+                    PendingAction::UpdateChannel { channel_id: 1 },
+                ],
+            ),
+            (
+                DenialReason::NestingLimit,
+                vec![
+                    PendingAction::CreateChannel {
+                        parent: 1,
+                        name: "new".to_string(),
+                    },
+                    PendingAction::UpdateChannel { channel_id: 1 },
+                ],
+            ),
+            (
+                DenialReason::ChannelCountLimit,
+                vec![PendingAction::CreateChannel {
+                    parent: 1,
+                    name: "new".to_string(),
+                }],
+            ),
+            (DenialReason::H9k, Vec::new()),
+        ];
+
+        for (reason, expected) in table {
+            assert_eq!(candidates(&denial(reason)), expected, "{reason:?}");
+        }
+    }
+
+    #[test]
+    fn test_permission_denial_targets() {
+        let unrelated = |action: &PendingAction| {
+            matches!(
+                action,
+                PendingAction::UpdateUserState
+                    | PendingAction::SetUserImage
+                    | PendingAction::SubmitBanList
+                    | PendingAction::UpdateUserList { .. }
+                    | PendingAction::RegisterSelf
+            )
+        };
+        let table: Vec<(Option<u32>, Option<u32>, Option<u32>, Vec<PendingAction>)> = vec![
+            // without details everything but a text message could have been denied
+            (
+                None,
+                None,
+                None,
+                all_actions().into_iter().skip(1).collect(),
+            ),
+            (Some(permission::TEXT_MESSAGE), None, None, all_actions()),
+            // neither the channel nor the session matches
+            (
+                Some(permission::WRITE),
+                Some(9),
+                Some(9),
+                all_actions().into_iter().filter(unrelated).collect(),
+            ),
+            (
+                Some(permission::LISTEN),
+                Some(4),
+                None,
+                all_actions()
+                    .into_iter()
+                    .filter(|action| {
+                        unrelated(action)
+                            || matches!(
+                                action,
+                                PendingAction::KickUser { .. }
+                                    | PendingAction::BanUser { .. }
+                                    | PendingAction::ModerateUser { .. }
+                                    | PendingAction::ListenToChannels { .. }
+                                    | PendingAction::StopListeningToChannels { .. }
+                                    | PendingAction::SetListenerVolume { .. }
+                            )
+                    })
+                    .collect(),
+            ),
+            (
+                Some(permission::WRITE),
+                Some(1),
+                Some(9),
+                all_actions()
+                    .into_iter()
+                    .filter(|action| {
+                        unrelated(action)
+                            || matches!(
+                                action,
+                                PendingAction::SubmitAcl { .. }
+                                    | PendingAction::CreateChannel { .. }
+                                    | PendingAction::UpdateChannel { .. }
+                                    | PendingAction::RemoveChannel { .. }
+                                    | PendingAction::ListenToChannels { .. }
+                            )
+                    })
+                    .collect(),
+            ),
+        ];
+
+        for (permission, channel_id, session, expected) in table {
+            let error = PermissionDeniedError {
+                permission,
+                channel_id,
+                session,
+                ..denial(DenialReason::Permission)
+            };
+            assert_eq!(candidates(&error), expected, "{error:?}");
+        }
+    }
+
+    #[test]
+    fn test_resolve_oldest_candidate() {
+        let (server_channel, _server_receiver) = broadcast::channel(16);
+        let mut pending_actions = PendingActions::default();
+        for action in [
+            PendingAction::KickUser { session: 2 },
+            PendingAction::UpdateChannel { channel_id: 1 },
+            PendingAction::CreateChannel {
+                parent: 1,
+                name: "new".to_string(),
+            },
+        ] {
+            pending_actions
+                .send(action, &server_channel, Vec::new())
+                .expect("Failed to send");
+        }
+
+        let error = denial(DenialReason::ChannelName);
+        assert_eq!(
+            pending_actions.resolve(&error),
+            Some(PendingAction::UpdateChannel { channel_id: 1 })
+        );
+        assert_eq!(
+            pending_actions.resolve(&error),
+            Some(PendingAction::CreateChannel {
+                parent: 1,
+                name: "new".to_string(),
+            })
+        );
+        assert_eq!(pending_actions.resolve(&error), None);
+        assert_eq!(
+            pending_actions.drain(),
+            vec![PendingAction::KickUser { session: 2 }]
+        );
+    }
+}
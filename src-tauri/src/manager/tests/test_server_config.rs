@@ -0,0 +1,72 @@
+mod tests {
+    use crate::manager::server_config::ServerConfig;
+
+    fn config(allow_html: Option<bool>, message_length: Option<u32>) -> ServerConfig {
+        ServerConfig {
+            allow_html,
+            message_length,
+            image_message_length: Some(0),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_message_length_counts_characters() {
+        let config = config(None, Some(5));
+
+        assert!(config.check_message_length("äöüß€").is_ok());
+        assert!(config.check_message_length("äöüß€!").is_err());
+    }
+
+    #[test]
+    fn test_unlimited_message_length() {
+        let config = config(None, Some(0));
+
+        assert!(config.check_message_length(&"a".repeat(10_000)).is_ok());
+    }
+
+    #[test]
+    fn test_html_is_kept_if_allowed() {
+        for allow_html in [None, Some(true)] {
+            let message = config(allow_html, None)
+                .prepare_message("<b>Hello</b>")
+                .expect("Message rejected");
+
+            assert_eq!(message, "<b>Hello</b>");
+        }
+    }
+
+    #[test]
+    fn test_html_is_stripped_if_not_allowed() {
+        let message = config(Some(false), None)
+            .prepare_message("<p><b>Hello</b> &amp; welcome<br/>to 1 &lt; 2</p>")
+            .expect("Message rejected");
+
+        assert_eq!(message, "Hello & welcome\nto 1 < 2");
+    }
+
+    #[test]
+    fn test_lonely_bracket_is_kept() {
+        let message = config(Some(false), None)
+            .prepare_message("a <b>b</b> < c")
+            .expect("Message rejected");
+
+        assert_eq!(message, "a b < c");
+    }
+
+    #[test]
+    fn test_images_are_rejected_if_html_is_not_allowed() {
+        let result =
+            config(Some(false), None).prepare_message("<img src='data:image/png;base64,AA' />");
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_length_is_checked_after_stripping() {
+        let config = config(Some(false), Some(5));
+
+        assert!(config.prepare_message("<b>Hello</b>").is_ok());
+        assert!(config.prepare_message("<b>Hello!</b>").is_err());
+    }
+}
@@ -0,0 +1,107 @@
+mod tests {
+    use serde_json::Value;
+    use tempfile::tempdir;
+    use tokio::sync::broadcast::{self, Receiver};
+
+    use crate::{
+        manager::{
+            text_message::{Manager, SentMessage},
+            user::User,
+        },
+        utils::chat_history::{ChatHistory, NewMessage},
+    };
+
+    fn receive_json(receiver: &mut Receiver<String>) -> Value {
+        let message = receiver.try_recv().expect("No message for the frontend");
+        serde_json::from_str(&message).expect("Invalid json")
+    }
+
+    #[test]
+    fn test_load_history_as_one_event() {
+        let tmp_dir = tempdir().expect("Failed to create temp dir");
+        let path = tmp_dir.path().join("history.sqlite");
+        let history = ChatHistory::open_at(&path, "example.com", 64738).expect("Failed to open");
+        for channel_id in [Some(1), None] {
+            history
+                .insert(&NewMessage {
+                    channel_id,
+                    message_id: Some("abc"),
+                    sender_id: 1,
+                    sender_name: "alice",
+                    message: "hello",
+                    timestamp: 42,
+                })
+                .expect("Failed to insert message");
+        }
+
+        let (frontend_channel, mut receiver) = broadcast::channel(16);
+        let mut manager = Manager::new(frontend_channel, Some(history));
+        manager.load_history();
+
+        let message = receive_json(&mut receiver);
+        assert_eq!(message["message_type"], "chat_history");
+        let messages = message["data"].as_array().expect("History is no list");
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0]["channel_id"], 1);
+        assert_eq!(messages[1]["channel_id"], Value::Null);
+        assert_eq!(messages[0]["sender"]["user_name"], "alice");
+        assert_eq!(messages[0]["state"], "confirmed");
+
+        // the history is only loaded for the first connection
+        manager.load_history();
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_sent_message_event() {
+        let (frontend_channel, mut receiver) = broadcast::channel(16);
+        let mut manager = Manager::new(frontend_channel, None);
+        let user = User {
+            id: 3,
+            name: "bob".to_string(),
+            ..Default::default()
+        };
+
+        manager.add_sent_message(
+            SentMessage {
+                message: "hi".to_string(),
+                channel_id: None,
+                message_id: "abc".to_string(),
+                timestamp: 42,
+            },
+            &user,
+        );
+
+        let message = receive_json(&mut receiver);
+        assert_eq!(message["message_type"], "text_message");
+        assert_eq!(message["data"]["id"], "abc");
+        assert_eq!(message["data"]["channel_id"], Value::Null);
+        assert_eq!(message["data"]["state"], "pending");
+    }
+
+    #[test]
+    fn test_denial_after_confirmation() {
+        let (frontend_channel, mut receiver) = broadcast::channel(16);
+        let mut manager = Manager::new(frontend_channel, None);
+
+        manager.add_sent_message(
+            SentMessage {
+                message: "hi".to_string(),
+                channel_id: Some(1),
+                message_id: "abc".to_string(),
+                timestamp: 42,
+            },
+            &User::default(),
+        );
+        receive_json(&mut receiver);
+
+        manager.confirm_sent_message("abc");
+        let message = receive_json(&mut receiver);
+        assert_eq!(message["message_type"], "text_message_state");
+        assert_eq!(message["data"]["state"], "confirmed");
+
+        // a confirmed message stays confirmed
+        manager.fail_sent_message("abc");
+        assert!(receiver.try_recv().is_err());
+    }
+}
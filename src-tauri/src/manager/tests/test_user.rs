@@ -0,0 +1,209 @@
+mod tests {
+    use prost::Message;
+    use tokio::sync::broadcast::{self, Receiver};
+
+    use crate::{
+        manager::{session::SessionState, user::Manager},
+        mumble,
+    };
+
+    const SESSION: u32 = 7;
+
+    fn create_manager() -> (Manager, Receiver<Vec<u8>>) {
+        let (frontend_channel, _) = broadcast::channel(16);
+        let (server_channel, server_receiver) = broadcast::channel(16);
+
+        (
+            Manager::new(
+                frontend_channel,
+                server_channel,
+                SessionState::default().user_channels,
+            ),
+            server_receiver,
+        )
+    }
+
+    fn add_current_user(manager: &mut Manager, mut state: mumble::proto::UserState) {
+        state.session = Some(SESSION);
+        manager
+            .update_user(&mut state)
+            .expect("Failed to update user");
+        manager.notify_current_user(&mumble::proto::ServerSync {
+            session: Some(SESSION),
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    fn test_snapshot_without_current_user() {
+        let (manager, _) = create_manager();
+
+        assert!(manager.session_snapshot().is_none());
+    }
+
+    #[test]
+    fn test_snapshot_and_restore() {
+        let (mut manager, mut server_receiver) = create_manager();
+        add_current_user(
+            &mut manager,
+            mumble::proto::UserState {
+                name: Some("user".to_string()),
+                channel_id: Some(3),
+                self_mute: Some(true),
+                listening_channel_add: vec![4],
+                listening_volume_adjustment: vec![mumble::proto::user_state::VolumeAdjustment {
+                    listening_channel: Some(4),
+                    volume_adjustment: Some(0.5),
+                }],
+                ..Default::default()
+            },
+        );
+
+        let snapshot = manager
+            .session_snapshot()
+            .expect("Expected a session snapshot");
+        assert_eq!(snapshot.session, None);
+        assert_eq!(snapshot.channel_id, Some(3));
+        assert_eq!(snapshot.self_mute, Some(true));
+        assert_eq!(snapshot.self_deaf, Some(false));
+        assert_eq!(snapshot.listening_channel_add, vec![4]);
+        assert_eq!(
+            snapshot.listening_volume_adjustment[0].volume_adjustment,
+            Some(0.5)
+        );
+
+        // the new session starts empty, the snapshot is sent as our own state
+        manager.clear();
+        assert!(manager.current_user().is_none());
+        manager
+            .restore_session(&snapshot)
+            .expect("Failed to restore session");
+
+        let sent = server_receiver.try_recv().expect("Expected a message");
+        let restored =
+            mumble::proto::UserState::decode(&sent[6..]).expect("Failed to decode user state");
+        assert_eq!(restored, snapshot);
+    }
+
+    #[test]
+    fn test_user_channel_cache() {
+        let (frontend_channel, _) = broadcast::channel(16);
+        let (server_channel, _) = broadcast::channel(16);
+        let session = SessionState::default();
+        let mut manager = Manager::new(
+            frontend_channel,
+            server_channel,
+            session.user_channels.clone(),
+        );
+
+        for channel_id in [3, 5] {
+            manager
+                .update_user(&mut mumble::proto::UserState {
+                    session: Some(SESSION),
+                    channel_id: Some(channel_id),
+                    ..Default::default()
+                })
+                .expect("Failed to update user");
+            assert_eq!(session.user_channel(SESSION), Some(channel_id));
+        }
+
+        manager.remove_user(&mumble::proto::UserRemove {
+            session: SESSION,
+            ..Default::default()
+        });
+        assert_eq!(session.user_channel(SESSION), None);
+    }
+
+    fn add_user(manager: &mut Manager, session: u32, channel_id: u32) {
+        manager
+            .update_user(&mut mumble::proto::UserState {
+                session: Some(session),
+                channel_id: Some(channel_id),
+                ..Default::default()
+            })
+            .expect("Failed to update user");
+    }
+
+    fn volume_adjustment(
+        channel_id: u32,
+        volume: f32,
+    ) -> mumble::proto::user_state::VolumeAdjustment {
+        mumble::proto::user_state::VolumeAdjustment {
+            listening_channel: Some(channel_id),
+            volume_adjustment: Some(volume),
+        }
+    }
+
+    #[test]
+    fn test_listener_volume_adjustment() {
+        let (mut manager, _) = create_manager();
+        add_current_user(
+            &mut manager,
+            mumble::proto::UserState {
+                channel_id: Some(3),
+                listening_channel_add: vec![3, 4, 5],
+                listening_volume_adjustment: vec![
+                    volume_adjustment(3, 0.25),
+                    volume_adjustment(4, 0.5),
+                ],
+                ..Default::default()
+            },
+        );
+        add_user(&mut manager, 8, 3);
+        add_user(&mut manager, 9, 4);
+        add_user(&mut manager, 10, 5);
+        add_user(&mut manager, 11, 6);
+
+        assert!((manager.listener_volume_adjustment(8) - 0.25).abs() < f32::EPSILON);
+        assert!((manager.listener_volume_adjustment(9) - 0.5).abs() < f32::EPSILON);
+        // listened without an adjustment
+        assert!((manager.listener_volume_adjustment(10) - 1.0).abs() < f32::EPSILON);
+        // not listened at all
+        assert!((manager.listener_volume_adjustment(11) - 1.0).abs() < f32::EPSILON);
+        assert!((manager.listener_volume_adjustment(12) - 1.0).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn test_stop_listening_removes_volume_adjustment() {
+        let (mut manager, _) = create_manager();
+        add_current_user(
+            &mut manager,
+            mumble::proto::UserState {
+                channel_id: Some(3),
+                listening_channel_add: vec![4, 5],
+                listening_volume_adjustment: vec![
+                    volume_adjustment(4, 0.5),
+                    volume_adjustment(5, 0.75),
+                ],
+                ..Default::default()
+            },
+        );
+        add_user(&mut manager, 9, 4);
+
+        add_current_user(
+            &mut manager,
+            mumble::proto::UserState {
+                listening_channel_remove: vec![4],
+                ..Default::default()
+            },
+        );
+        let snapshot = manager
+            .session_snapshot()
+            .expect("Expected a session snapshot");
+        assert_eq!(snapshot.listening_channel_add, vec![5]);
+        assert_eq!(
+            snapshot.listening_volume_adjustment,
+            vec![volume_adjustment(5, 0.75)]
+        );
+
+        // listening again starts with the default volume
+        add_current_user(
+            &mut manager,
+            mumble::proto::UserState {
+                listening_channel_add: vec![4],
+                ..Default::default()
+            },
+        );
+        assert!((manager.listener_volume_adjustment(9) - 1.0).abs() < f32::EPSILON);
+    }
+}
@@ -0,0 +1,77 @@
+mod tests {
+    use serde_json::Value;
+    use tokio::sync::broadcast;
+
+    use crate::{
+        manager::user_list::{Manager, RegisteredUser},
+        mumble,
+    };
+
+    fn registered(user_id: u32, name: &str) -> mumble::proto::user_list::User {
+        mumble::proto::user_list::User {
+            user_id,
+            name: Some(name.to_string()),
+            last_seen: Some("2023-08-01 12:00:00".to_string()),
+            last_channel: Some(3),
+        }
+    }
+
+    #[test]
+    fn test_from_proto() {
+        let user = RegisteredUser::from(registered(7, "Alice"));
+
+        assert_eq!(user.user_id, 7);
+        assert_eq!(user.name.as_deref(), Some("Alice"));
+        assert_eq!(user.last_seen.as_deref(), Some("2023-08-01 12:00:00"));
+        assert_eq!(user.last_channel, Some(3));
+    }
+
+    #[test]
+    fn test_rename_sends_id_and_name() {
+        let user = RegisteredUser {
+            user_id: 7,
+            name: Some("Bob".to_string()),
+            last_seen: Some("2023-08-01 12:00:00".to_string()),
+            last_channel: Some(3),
+        };
+
+        let proto: mumble::proto::user_list::User = user.into();
+
+        assert_eq!(proto.user_id, 7);
+        assert_eq!(proto.name.as_deref(), Some("Bob"));
+        assert_eq!(proto.last_seen, None);
+        assert_eq!(proto.last_channel, None);
+    }
+
+    #[test]
+    fn test_deregister_sends_no_name() {
+        let user = RegisteredUser {
+            user_id: 7,
+            ..Default::default()
+        };
+
+        let proto: mumble::proto::user_list::User = user.into();
+
+        assert_eq!(proto.user_id, 7);
+        assert_eq!(proto.name, None);
+    }
+
+    #[test]
+    fn test_notify_user_list() {
+        let (frontend_channel, mut receiver) = broadcast::channel(16);
+        let manager = Manager::new(frontend_channel);
+
+        manager.notify_user_list(mumble::proto::UserList {
+            users: vec![registered(7, "Alice"), registered(9, "Bob")],
+        });
+
+        let message: Value = serde_json::from_str(&receiver.try_recv().expect("No users sent"))
+            .expect("Invalid json");
+        assert_eq!(message["message_type"], "registered_users");
+        let users = message["data"].as_array().expect("Users are no list");
+        assert_eq!(users.len(), 2);
+        assert_eq!(users[0]["user_id"], 7);
+        assert_eq!(users[0]["name"], "Alice");
+        assert_eq!(users[1]["last_channel"], 3);
+    }
+}
@@ -0,0 +1,68 @@
+mod tests {
+    use serde_json::Value;
+    use tokio::sync::broadcast::{self, Receiver};
+
+    use crate::{manager::user_stats::Manager, mumble};
+
+    const SESSION: u32 = 7;
+
+    fn receive_stats(receiver: &mut Receiver<String>) -> Value {
+        let message: Value =
+            serde_json::from_str(&receiver.try_recv().expect("No statistics sent"))
+                .expect("Invalid json");
+        assert_eq!(message["message_type"], "user_stats");
+
+        message["data"].clone()
+    }
+
+    fn refresh(tcp_packets: u32) -> mumble::proto::UserStats {
+        mumble::proto::UserStats {
+            session: Some(SESSION),
+            stats_only: Some(true),
+            tcp_packets: Some(tcp_packets),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_refresh_keeps_details() {
+        let (frontend_channel, mut receiver) = broadcast::channel(16);
+        let mut manager = Manager::new(frontend_channel);
+        let mut address = vec![0; 10];
+        address.extend([0xff, 0xff, 10, 0, 0, 1]);
+
+        manager.notify_user_stats(mumble::proto::UserStats {
+            session: Some(SESSION),
+            version: Some(mumble::proto::Version {
+                os: Some("Linux".to_string()),
+                ..Default::default()
+            }),
+            address: Some(address),
+            strong_certificate: Some(true),
+            tcp_packets: Some(1),
+            ..Default::default()
+        });
+        let full = receive_stats(&mut receiver);
+        assert_eq!(full["os"], "Linux");
+        assert_eq!(full["address"], "10.0.0.1");
+
+        manager.notify_user_stats(refresh(2));
+        let refreshed = receive_stats(&mut receiver);
+        assert_eq!(refreshed["tcp_packets"], 2);
+        assert_eq!(refreshed["os"], "Linux");
+        assert_eq!(refreshed["address"], "10.0.0.1");
+        assert_eq!(refreshed["strong_certificate"], true);
+    }
+
+    #[test]
+    fn test_refresh_without_details() {
+        let (frontend_channel, mut receiver) = broadcast::channel(16);
+        let mut manager = Manager::new(frontend_channel);
+
+        manager.notify_user_stats(refresh(2));
+        let refreshed = receive_stats(&mut receiver);
+        assert_eq!(refreshed["tcp_packets"], 2);
+        assert_eq!(refreshed["address"], Value::Null);
+        assert_eq!(refreshed["strong_certificate"], Value::Null);
+    }
+}
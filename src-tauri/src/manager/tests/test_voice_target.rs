@@ -0,0 +1,126 @@
+mod tests {
+    use prost::Message;
+    use tokio::sync::broadcast;
+
+    use crate::{
+        manager::{
+            session::Shared,
+            voice_target::{Manager, VoiceTarget, VoiceTargetRegistry, WhisperTarget},
+        },
+        mumble,
+    };
+
+    fn voice_target(id: u32, targets: Vec<WhisperTarget>) -> mumble::proto::VoiceTarget {
+        VoiceTarget {
+            id,
+            targets,
+            shortcut: None,
+        }
+        .into()
+    }
+
+    fn whisper_to(channel_id: u32) -> WhisperTarget {
+        WhisperTarget {
+            channel_id: Some(channel_id),
+            ..Default::default()
+        }
+    }
+
+    fn whisper_to_users(sessions: Vec<u32>) -> WhisperTarget {
+        WhisperTarget {
+            sessions,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_register_and_remove() {
+        let mut registry = VoiceTargetRegistry::default();
+        registry.register(&voice_target(2, vec![whisper_to(5)]));
+        registry.register(&voice_target(1, vec![whisper_to(3)]));
+
+        let ids: Vec<u32> = registry.all().iter().map(|target| target.id()).collect();
+        assert_eq!(ids, vec![1, 2]);
+
+        // removing is done by registering the target without receivers
+        registry.register(&voice_target(1, Vec::new()));
+        let ids: Vec<u32> = registry.all().iter().map(|target| target.id()).collect();
+        assert_eq!(ids, vec![2]);
+    }
+
+    #[test]
+    fn test_register_replaces_target() {
+        let mut registry = VoiceTargetRegistry::default();
+        registry.register(&voice_target(1, vec![whisper_to(3)]));
+        registry.register(&voice_target(1, vec![whisper_to(4)]));
+
+        let targets = registry.all();
+        assert_eq!(targets.len(), 1);
+        assert_eq!(targets[0].targets[0].channel_id, Some(4));
+    }
+
+    #[test]
+    fn test_register_all_sends_every_target() {
+        let (server_channel, mut receiver) = broadcast::channel(16);
+        let registry = Shared::<VoiceTargetRegistry>::default();
+        registry.write(|targets| {
+            targets.register(&voice_target(1, vec![whisper_to(3)]));
+            targets.register(&voice_target(2, vec![whisper_to(5)]));
+        });
+
+        Manager::new(server_channel, registry)
+            .register_all()
+            .expect("Failed to register voice targets");
+
+        for id in [1, 2] {
+            let message = receiver.try_recv().expect("No voice target sent");
+            let sent =
+                mumble::proto::VoiceTarget::decode(&message[6..]).expect("Invalid voice target");
+            assert_eq!(sent.id(), id);
+        }
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_forget_sessions_keeps_channels() {
+        let mut registry = VoiceTargetRegistry::default();
+        registry.register(&voice_target(
+            1,
+            vec![whisper_to_users(vec![7, 8]), whisper_to(3)],
+        ));
+        registry.register(&voice_target(2, vec![whisper_to_users(vec![9])]));
+        let mut group = whisper_to(4);
+        group.group = Some("admin".to_string());
+        registry.register(&voice_target(3, vec![group]));
+
+        registry.forget_sessions();
+
+        let targets = registry.all();
+        let ids: Vec<u32> = targets.iter().map(|target| target.id()).collect();
+        assert_eq!(ids, vec![1, 3]);
+        assert_eq!(targets[0].targets.len(), 1);
+        assert_eq!(targets[0].targets[0].channel_id, Some(3));
+        assert!(targets[0].targets[0].session.is_empty());
+        assert_eq!(targets[1].targets[0].group.as_deref(), Some("admin"));
+    }
+
+    #[test]
+    fn test_register_all_after_reconnect_drops_users() {
+        let (server_channel, mut receiver) = broadcast::channel(16);
+        let registry = Shared::<VoiceTargetRegistry>::default();
+        registry.write(|targets| {
+            targets.register(&voice_target(1, vec![whisper_to_users(vec![7])]));
+            targets.register(&voice_target(2, vec![whisper_to(5)]));
+        });
+
+        Manager::new(server_channel, registry.clone())
+            .register_all()
+            .expect("Failed to register voice targets");
+
+        let message = receiver.try_recv().expect("No voice target sent");
+        let sent = mumble::proto::VoiceTarget::decode(&message[6..]).expect("Invalid voice target");
+        assert_eq!(sent.id(), 2);
+        assert!(receiver.try_recv().is_err());
+        assert_eq!(registry.read(VoiceTargetRegistry::all).len(), 1);
+    }
+}
@@ -2,67 +2,167 @@ use std::time::{SystemTime, UNIX_EPOCH};
 
 use serde::Serialize;
 use tokio::sync::broadcast::Sender;
-use tracing::error;
+use tracing::{error, warn};
 
-use crate::{mumble, protocol::serialize::message_container::FrontendMessage};
+use crate::{
+    mumble,
+    protocol::serialize::message_container::FrontendMessage,
+    utils::{
+        chat_history::{ChatHistory, HistoryScope, NewMessage, StoredMessage},
+        frontend::send_to_frontend,
+    },
+};
 
 use super::user::User;
 
+const HISTORY_SIZE: u32 = 100;
+
 #[derive(Debug, Clone, Serialize)]
 struct SenderInfo {
     user_id: u32,
     user_name: String,
 }
 
+/// Own messages stay pending until the server has processed them, which we know once it
+/// answered a ping sent after the message was written to the socket
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum MessageState {
+    Pending,
+    Confirmed,
+    Failed,
+}
+
 #[derive(Debug, Clone, Serialize)]
 struct TextMessage {
     sender: SenderInfo,
     message: String,
     timestamp: u128,
     id: Option<String>,
+    channel_id: Option<u32>,
+    state: MessageState,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct MessageStateUpdate<'a> {
+    id: &'a str,
+    state: MessageState,
+}
+
+#[derive(Debug, Clone)]
+pub struct SentMessage {
+    pub message: String,
+    pub channel_id: Option<u32>,
+    pub message_id: String,
+    pub timestamp: u64,
+}
+
+impl From<StoredMessage> for TextMessage {
+    fn from(message: StoredMessage) -> Self {
+        Self {
+            sender: SenderInfo {
+                user_id: message.sender_id,
+                user_name: message.sender_name,
+            },
+            message: message.message,
+            timestamp: u128::from(message.timestamp),
+            id: message.message_id,
+            channel_id: message.channel_id,
+            state: MessageState::Confirmed,
+        }
+    }
 }
 
 pub struct Manager {
     message_log: Vec<TextMessage>,
     frontend_channel: Sender<String>,
+    chat_history: Option<ChatHistory>,
 }
 
 impl Manager {
-    pub fn new(send_to: Sender<String>) -> Self {
+    pub fn new(send_to: Sender<String>, chat_history: Option<ChatHistory>) -> Self {
         Self {
             message_log: Vec::new(),
             frontend_channel: send_to,
+            chat_history,
         }
     }
 
-    fn notify(&self, element: Option<usize>) {
-        let result = element.map_or_else(
-            || {
-                let msg = FrontendMessage::new("text_message", &self.message_log);
-                serde_json::to_string(&msg)
-            },
-            |inner_element| {
-                let text = &self.message_log[inner_element];
-                let msg = FrontendMessage::new("text_message", text);
-                serde_json::to_string(&msg)
-            },
+    fn notify_last(&self) {
+        if let Some(message) = self.message_log.last() {
+            send_to_frontend(
+                &self.frontend_channel,
+                &FrontendMessage::new("text_message", message),
+            );
+        }
+    }
+
+    fn store(&self, message: &TextMessage) {
+        let Some(chat_history) = self.chat_history.as_ref() else {
+            return;
+        };
+
+        let new_message = NewMessage {
+            channel_id: message.channel_id,
+            message_id: message.id.as_deref(),
+            sender_id: message.sender.user_id,
+            sender_name: &message.sender.user_name,
+            message: &message.message,
+            timestamp: u64::try_from(message.timestamp).unwrap_or_default(),
+        };
+
+        if let Err(e) = chat_history.insert(&new_message) {
+            error!("Failed to store message in chat history: {}", e);
+        }
+    }
+
+    fn notify_state(&self, element: usize) {
+        let message = &self.message_log[element];
+        let Some(id) = message.id.as_deref() else {
+            return;
+        };
+
+        send_to_frontend(
+            &self.frontend_channel,
+            &FrontendMessage::new(
+                "text_message_state",
+                MessageStateUpdate {
+                    id,
+                    state: message.state,
+                },
+            ),
         );
+    }
 
-        match result {
-            Ok(json) => {
-                if let Err(e) = self.frontend_channel.send(json) {
-                    error!("Failed to send user list to frontend: {}", e);
-                }
-            }
-            Err(e) => {
-                error!("Failed to serialize user list: {}", e);
-            }
+    fn push(&mut self, message: TextMessage) {
+        if message.state == MessageState::Confirmed {
+            self.store(&message);
         }
+        self.message_log.push(message);
+        self.notify_last();
     }
 
-    fn notify_last(&self) {
-        let last = self.message_log.len() - 1;
-        self.notify(Some(last));
+    /// Fills the message log with the most recent messages of this server
+    pub fn load_history(&mut self) {
+        // after a reconnect the log is already filled and stored in the history
+        if !self.message_log.is_empty() {
+            return;
+        }
+        let Some(chat_history) = self.chat_history.as_ref() else {
+            return;
+        };
+
+        match chat_history.page(HistoryScope::All, None, HISTORY_SIZE) {
+            Ok(history) if !history.is_empty() => {
+                self.message_log = history.into_iter().map(TextMessage::from).collect();
+                send_to_frontend(
+                    &self.frontend_channel,
+                    &FrontendMessage::new("chat_history", &self.message_log),
+                );
+            }
+            Ok(_) => {}
+            Err(e) => error!("Failed to load chat history: {}", e),
+        }
     }
 
     pub fn add_text_message(&mut self, text_message: mumble::proto::TextMessage, user: &User) {
@@ -84,8 +184,59 @@ impl Manager {
             message: text_message.message,
             timestamp,
             id: text_message.message_id,
+            channel_id: text_message.channel_id.first().copied(),
+            state: MessageState::Confirmed,
         };
-        self.message_log.push(message);
-        self.notify_last();
+        self.push(message);
+    }
+
+    pub fn add_sent_message(&mut self, sent_message: SentMessage, user: &User) {
+        let message = TextMessage {
+            sender: SenderInfo {
+                user_id: user.id,
+                user_name: user.name.clone(),
+            },
+            message: sent_message.message,
+            timestamp: u128::from(sent_message.timestamp),
+            id: Some(sent_message.message_id),
+            channel_id: sent_message.channel_id,
+            state: MessageState::Pending,
+        };
+        self.push(message);
+    }
+
+    /// The server processed the message, so it can't be denied anymore
+    pub fn confirm_sent_message(&mut self, message_id: &str) {
+        let Some(index) = self.pending_message(message_id) else {
+            warn!(
+                "Text message {} was confirmed, but it isn't pending",
+                message_id
+            );
+            return;
+        };
+
+        self.message_log[index].state = MessageState::Confirmed;
+        self.store(&self.message_log[index]);
+        self.notify_state(index);
+    }
+
+    fn pending_message(&self, message_id: &str) -> Option<usize> {
+        self.message_log.iter().position(|message| {
+            message.state == MessageState::Pending && message.id.as_deref() == Some(message_id)
+        })
+    }
+
+    pub fn fail_sent_message(&mut self, message_id: &str) {
+        // only pending messages can fail, a confirmed one went through
+        let Some(index) = self.pending_message(message_id) else {
+            warn!(
+                "Text message {} was denied, but it isn't pending",
+                message_id
+            );
+            return;
+        };
+
+        self.message_log[index].state = MessageState::Failed;
+        self.notify_state(index);
     }
 }
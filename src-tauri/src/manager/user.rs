@@ -1,11 +1,15 @@
 use base64::{engine::general_purpose, Engine as _};
-use std::collections::{hash_map::Entry, HashMap};
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    mem,
+};
 
 use serde::{Deserialize, Serialize};
 use tracing::{debug, info, trace};
 
 use crate::{
     errors::AnyError,
+    manager::session::Shared,
     mumble,
     protocol::serialize::message_container::FrontendMessage,
     utils::{
@@ -18,6 +22,8 @@ use crate::{
 use super::Update;
 use tokio::sync::broadcast::Sender;
 
+const DEFAULT_VOLUME_ADJUSTMENT: f32 = 1.0;
+
 #[derive(Debug, Clone, Copy, Serialize, PartialEq)]
 enum HashUserFields {
     ProfilePicture,
@@ -38,6 +44,9 @@ pub struct User {
     self_deaf: bool,
     priority_speaker: bool,
     recording: bool,
+    listening_channels: Vec<u32>,
+    /// Linear volume factor per listened channel, the server only sends these for ourselves
+    listening_volume_adjustments: HashMap<u32, f32>,
     profile_picture_hash: Vec<u8>,
     #[serde(skip_serializing)] // We don't want to send such a big blob to the frontend
     profile_picture: Vec<u8>,
@@ -65,6 +74,31 @@ pub struct UpdateableUserState {
     pub comment: Option<String>,
 }
 
+/// Changes a moderator applies to another user
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct UserModeration {
+    pub session: u32,
+    pub mute: Option<bool>,
+    pub deaf: Option<bool>,
+    pub suppress: Option<bool>,
+    pub priority_speaker: Option<bool>,
+    pub move_to: Option<u32>,
+}
+
+impl From<UserModeration> for mumble::proto::UserState {
+    fn from(moderation: UserModeration) -> Self {
+        Self {
+            session: Some(moderation.session),
+            channel_id: moderation.move_to,
+            mute: moderation.mute,
+            deaf: moderation.deaf,
+            suppress: moderation.suppress,
+            priority_speaker: moderation.priority_speaker,
+            ..Default::default()
+        }
+    }
+}
+
 #[derive(Debug, Default, Serialize, Deserialize, Clone)]
 pub struct SyncInfo {
     pub session: Option<u32>,
@@ -95,22 +129,50 @@ impl Update<mumble::proto::UserState> for User {
         Self::update_if_some(&mut self.profile_picture, &mut other.texture);
         Self::update_if_some(&mut self.comment, &mut other.comment);
 
+        self.listening_channels
+            .retain(|channel| !other.listening_channel_remove.contains(channel));
+        for channel in mem::take(&mut other.listening_channel_add) {
+            if !self.listening_channels.contains(&channel) {
+                self.listening_channels.push(channel);
+            }
+        }
+        for adjustment in mem::take(&mut other.listening_volume_adjustment) {
+            if let Some(channel) = adjustment.listening_channel {
+                self.listening_volume_adjustments.insert(
+                    channel,
+                    adjustment
+                        .volume_adjustment
+                        .unwrap_or(DEFAULT_VOLUME_ADJUSTMENT),
+                );
+            }
+        }
+        self.listening_volume_adjustments
+            .retain(|channel, _| self.listening_channels.contains(channel));
+
         self
     }
 }
 
 pub struct Manager {
     users: HashMap<u32, User>,
+    current_session: Option<u32>,
     frontend_channel: Sender<String>,
     server_channel: Sender<Vec<u8>>,
+    user_channels: Shared<HashMap<u32, u32>>,
 }
 
 impl Manager {
-    pub fn new(send_to: Sender<String>, server_channel: Sender<Vec<u8>>) -> Self {
+    pub fn new(
+        send_to: Sender<String>,
+        server_channel: Sender<Vec<u8>>,
+        user_channels: Shared<HashMap<u32, u32>>,
+    ) -> Self {
         Self {
             users: HashMap::new(),
+            current_session: None,
             frontend_channel: send_to,
             server_channel,
+            user_channels,
         }
     }
 
@@ -305,12 +367,19 @@ impl Manager {
                 v.insert(user);
             }
         };
+
+        if let Some(user) = self.users.get(&session) {
+            self.user_channels
+                .write(|channels| channels.insert(session, user.channel_id));
+        }
     }
 
     pub fn remove_user(&mut self, user_info: &mumble::proto::UserRemove) {
         let session = user_info.session;
 
         self.users.remove(&session);
+        self.user_channels
+            .write(|channels| channels.remove(&session));
         self.notify_remove(session);
     }
 
@@ -318,7 +387,27 @@ impl Manager {
         self.users.get(&id)
     }
 
+    pub fn current_user(&self) -> Option<&User> {
+        self.users.get(&self.current_session?)
+    }
+
+    /// Volume factor for audio of `session` which the server sent us because we listen to the
+    /// channel of the speaker
+    pub fn listener_volume_adjustment(&self, session: u32) -> f32 {
+        let (Some(current_user), Some(speaker)) = (self.current_user(), self.users.get(&session))
+        else {
+            return DEFAULT_VOLUME_ADJUSTMENT;
+        };
+
+        current_user
+            .listening_volume_adjustments
+            .get(&speaker.channel_id)
+            .copied()
+            .unwrap_or(DEFAULT_VOLUME_ADJUSTMENT)
+    }
+
     pub fn notify_current_user(&mut self, sync_info: &mumble::proto::ServerSync) {
+        self.current_session = sync_info.session;
         let sync_info = SyncInfo {
             session: sync_info.session,
             max_bandwidth: sync_info.max_bandwidth,
@@ -328,6 +417,42 @@ impl Manager {
         let message = FrontendMessage::new("sync_info", sync_info);
         send_to_frontend(&self.frontend_channel, &message);
     }
+
+    /// Captures the state of the current user, so it can be re-applied after a reconnect
+    pub fn session_snapshot(&self) -> Option<mumble::proto::UserState> {
+        let user = self.users.get(&self.current_session?)?;
+
+        Some(mumble::proto::UserState {
+            channel_id: Some(user.channel_id),
+            self_mute: Some(user.self_mute),
+            self_deaf: Some(user.self_deaf),
+            listening_channel_add: user.listening_channels.clone(),
+            listening_volume_adjustment: user
+                .listening_volume_adjustments
+                .iter()
+                .map(
+                    |(channel, volume)| mumble::proto::user_state::VolumeAdjustment {
+                        listening_channel: Some(*channel),
+                        volume_adjustment: Some(*volume),
+                    },
+                )
+                .collect(),
+            ..Default::default()
+        })
+    }
+
+    pub fn restore_session(&self, session: &mumble::proto::UserState) -> AnyError<()> {
+        info!("Restoring previous session: {:?}", session);
+        self.server_channel.send(message_builder(session)?)?;
+
+        Ok(())
+    }
+
+    pub fn clear(&mut self) {
+        self.users.clear();
+        self.user_channels.write(HashMap::clear);
+        self.current_session = None;
+    }
 }
 
 fn update_user_comment_and_pfp_from_cache(
@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::Sender;
+use tracing::trace;
+
+use crate::{
+    mumble, protocol::serialize::message_container::FrontendMessage,
+    utils::frontend::send_to_frontend,
+};
+
+/// A user registered on the server, it doesn't have to be online
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct RegisteredUser {
+    pub user_id: u32,
+    pub name: Option<String>,
+    pub last_seen: Option<String>,
+    pub last_channel: Option<u32>,
+}
+
+impl From<mumble::proto::user_list::User> for RegisteredUser {
+    fn from(user: mumble::proto::user_list::User) -> Self {
+        Self {
+            user_id: user.user_id,
+            name: user.name,
+            last_seen: user.last_seen,
+            last_channel: user.last_channel,
+        }
+    }
+}
+
+/// Only the id and the name are sent back, a user without name gets deregistered
+impl From<RegisteredUser> for mumble::proto::user_list::User {
+    fn from(user: RegisteredUser) -> Self {
+        Self {
+            user_id: user.user_id,
+            name: user.name,
+            ..Default::default()
+        }
+    }
+}
+
+pub struct Manager {
+    frontend_channel: Sender<String>,
+}
+
+impl Manager {
+    pub fn new(send_to: Sender<String>) -> Self {
+        Self {
+            frontend_channel: send_to,
+        }
+    }
+
+    pub fn notify_user_list(&self, user_list: mumble::proto::UserList) {
+        let users = user_list
+            .users
+            .into_iter()
+            .map(RegisteredUser::from)
+            .collect::<Vec<_>>();
+        trace!("Received {} registered users", users.len());
+
+        send_to_frontend(
+            &self.frontend_channel,
+            &FrontendMessage::new("registered_users", &users),
+        );
+    }
+}
@@ -0,0 +1,220 @@
+use std::{
+    collections::HashMap,
+    net::{IpAddr, Ipv6Addr},
+};
+
+use openssl::x509::{X509NameRef, X509};
+use serde::Serialize;
+use tokio::sync::broadcast::Sender;
+use tracing::warn;
+
+use crate::{
+    mumble,
+    protocol::{
+        format_version, from_legacy_version, serialize::message_container::FrontendMessage,
+    },
+    utils::{frontend::send_to_frontend, pinned_certificate_store::fingerprint},
+};
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct PacketStatistics {
+    pub good: u32,
+    pub late: u32,
+    pub lost: u32,
+    pub resync: u32,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct CertificateInfo {
+    pub subject: String,
+    pub issuer: String,
+    pub not_before: String,
+    pub not_after: String,
+    pub fingerprint: String,
+}
+
+/// Statistics of a user as reported by the server.
+///
+/// Refreshes only contain the statistics, the version, certificates and address are taken from
+/// the last full result of the user.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct UserStatistics {
+    pub session: Option<u32>,
+    pub client_version: Option<String>,
+    pub release: Option<String>,
+    pub os: Option<String>,
+    pub os_version: Option<String>,
+    pub certificates: Vec<CertificateInfo>,
+    pub strong_certificate: Option<bool>,
+    pub address: Option<String>,
+    pub from_client: Option<PacketStatistics>,
+    pub from_server: Option<PacketStatistics>,
+    pub udp_packets: Option<u32>,
+    pub tcp_packets: Option<u32>,
+    pub udp_ping_avg: Option<f32>,
+    pub udp_ping_var: Option<f32>,
+    pub tcp_ping_avg: Option<f32>,
+    pub tcp_ping_var: Option<f32>,
+    pub bandwidth: Option<u32>,
+    pub onlinesecs: Option<u32>,
+    pub idlesecs: Option<u32>,
+    pub opus: Option<bool>,
+    pub celt_versions: Vec<i32>,
+}
+
+impl UserStatistics {
+    /// Fills the details a refresh doesn't contain from an earlier result
+    fn merge_details(&mut self, previous: &Self) {
+        fn keep<T: Clone>(current: &mut Option<T>, previous: Option<&T>) {
+            if current.is_none() {
+                *current = previous.cloned();
+            }
+        }
+
+        keep(&mut self.client_version, previous.client_version.as_ref());
+        keep(&mut self.release, previous.release.as_ref());
+        keep(&mut self.os, previous.os.as_ref());
+        keep(&mut self.os_version, previous.os_version.as_ref());
+        keep(
+            &mut self.strong_certificate,
+            previous.strong_certificate.as_ref(),
+        );
+        keep(&mut self.address, previous.address.as_ref());
+        keep(&mut self.opus, previous.opus.as_ref());
+        if self.certificates.is_empty() {
+            self.certificates.clone_from(&previous.certificates);
+        }
+        if self.celt_versions.is_empty() {
+            self.celt_versions.clone_from(&previous.celt_versions);
+        }
+    }
+}
+
+impl From<mumble::proto::user_stats::Stats> for PacketStatistics {
+    fn from(stats: mumble::proto::user_stats::Stats) -> Self {
+        Self {
+            good: stats.good(),
+            late: stats.late(),
+            lost: stats.lost(),
+            resync: stats.resync(),
+        }
+    }
+}
+
+impl From<mumble::proto::UserStats> for UserStatistics {
+    fn from(stats: mumble::proto::UserStats) -> Self {
+        let certificates = stats
+            .certificates
+            .iter()
+            .filter_map(|certificate| describe_certificate(certificate.as_slice()))
+            .collect();
+        let version = stats.version.as_ref();
+
+        Self {
+            session: stats.session,
+            client_version: version.and_then(format_client_version),
+            release: version.and_then(|version| version.release.clone()),
+            os: version.and_then(|version| version.os.clone()),
+            os_version: version.and_then(|version| version.os_version.clone()),
+            certificates,
+            strong_certificate: stats.strong_certificate,
+            address: stats.address.as_deref().and_then(format_address),
+            from_client: stats.from_client.map(PacketStatistics::from),
+            from_server: stats.from_server.map(PacketStatistics::from),
+            udp_packets: stats.udp_packets,
+            tcp_packets: stats.tcp_packets,
+            udp_ping_avg: stats.udp_ping_avg,
+            udp_ping_var: stats.udp_ping_var,
+            tcp_ping_avg: stats.tcp_ping_avg,
+            tcp_ping_var: stats.tcp_ping_var,
+            bandwidth: stats.bandwidth,
+            onlinesecs: stats.onlinesecs,
+            idlesecs: stats.idlesecs,
+            opus: stats.opus,
+            celt_versions: stats.celt_versions,
+        }
+    }
+}
+
+fn format_client_version(version: &mumble::proto::Version) -> Option<String> {
+    version
+        .version_v2
+        .or_else(|| version.version_v1.map(from_legacy_version))
+        .map(format_version)
+}
+
+fn format_address(address: &[u8]) -> Option<String> {
+    let octets: [u8; 16] = address.try_into().ok()?;
+    let address = Ipv6Addr::from(octets);
+
+    Some(
+        address
+            .to_ipv4_mapped()
+            .map_or(IpAddr::V6(address), IpAddr::V4)
+            .to_string(),
+    )
+}
+
+fn format_name(name: &X509NameRef) -> String {
+    name.entries()
+        .filter_map(|entry| {
+            let key = entry.object().nid().short_name().ok()?;
+            let value = entry.data().as_utf8().ok()?;
+            Some(format!("{key}={value}"))
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn describe_certificate(der: &[u8]) -> Option<CertificateInfo> {
+    match X509::from_der(der) {
+        Ok(certificate) => Some(CertificateInfo {
+            subject: format_name(certificate.subject_name()),
+            issuer: format_name(certificate.issuer_name()),
+            not_before: certificate.not_before().to_string(),
+            not_after: certificate.not_after().to_string(),
+            fingerprint: fingerprint(der),
+        }),
+        Err(e) => {
+            warn!("Failed to parse certificate of user: {}", e);
+            None
+        }
+    }
+}
+
+pub struct Manager {
+    frontend_channel: Sender<String>,
+    statistics: HashMap<u32, UserStatistics>,
+}
+
+impl Manager {
+    pub fn new(send_to: Sender<String>) -> Self {
+        Self {
+            frontend_channel: send_to,
+            statistics: HashMap::new(),
+        }
+    }
+
+    pub fn notify_user_stats(&mut self, user_stats: mumble::proto::UserStats) {
+        let mut statistics = UserStatistics::from(user_stats);
+        if let Some(session) = statistics.session {
+            if let Some(previous) = self.statistics.get(&session) {
+                statistics.merge_details(previous);
+            }
+            self.statistics.insert(session, statistics.clone());
+        }
+
+        send_to_frontend(
+            &self.frontend_channel,
+            &FrontendMessage::new("user_stats", statistics),
+        );
+    }
+
+    pub fn remove_user(&mut self, session: u32) {
+        self.statistics.remove(&session);
+    }
+
+    pub fn clear(&mut self) {
+        self.statistics.clear();
+    }
+}
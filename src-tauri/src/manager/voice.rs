@@ -3,7 +3,9 @@ use crate::errors::application_error::ApplicationError;
 use crate::errors::AnyError;
 use crate::mumble;
 use crate::protocol::serialize::message_container::FrontendMessage;
+use crate::protocol::VoiceFormat;
 use crate::utils::audio;
+use crate::utils::audio::decoder::AudioContext;
 use crate::utils::audio::player::Player;
 use crate::utils::audio::recorder::Recorder;
 use crate::utils::frontend::send_to_frontend;
@@ -14,9 +16,6 @@ use serde::Serialize;
 use std::collections::{hash_map::Entry, HashMap};
 use tokio::sync::broadcast::{Receiver, Sender};
 
-const SAMPLE_RATE: u32 = 48000;
-const CHANNELS: opus::Channels = opus::Channels::Mono;
-
 #[derive(Debug, Serialize, Clone)]
 struct AudioInfo {
     talking: bool,
@@ -36,18 +35,18 @@ impl Manager {
     pub fn new(
         send_to: Sender<String>,
         server_channel: Sender<Vec<u8>>,
+        voice_channel: Sender<Vec<u8>>,
         settings_channel: Receiver<GlobalSettings>,
     ) -> AnyError<Self> {
-        let mut player = Player::new(settings_channel.resubscribe());
+        let mut player = Player::new(settings_channel.resubscribe(), send_to.clone());
         if let Err(error) = player.start() {
             return Err(Box::new(ApplicationError::new(&format!(
                 "Failed to start audio player: {error}"
             ))));
         }
 
-        let server_channel_clone = server_channel.clone();
-
-        let mut recoder = audio::recorder::Recorder::new(server_channel_clone, settings_channel);
+        let mut recoder =
+            audio::recorder::Recorder::new(voice_channel, settings_channel, send_to.clone());
         //if enable_recorder {
         if let Err(error) = recoder.start() {
             return Err(Box::new(ApplicationError::new(&format!(
@@ -62,12 +61,21 @@ impl Manager {
             user_audio_info: HashMap::new(),
             audio_player: player,
             recoder,
-            decoder: Box::new(audio::decoder::UDPDecoder::new(SAMPLE_RATE, CHANNELS)),
+            decoder: audio::decoder::create_decoder(VoiceFormat::default()),
         })
     }
 
-    pub fn notify_audio(&mut self, audio_data: &[u8]) -> AnyError<()> {
-        let audio_data = self.decoder.decode_audio(audio_data)?;
+    /// Plays received audio, `listener_volume` returns the listener adjustment for a session.
+    /// It only applies to audio the server sent us because we listen to the channel.
+    pub fn notify_audio(
+        &mut self,
+        audio_data: &[u8],
+        listener_volume: impl Fn(u32) -> f32,
+    ) -> AnyError<()> {
+        let mut audio_data = self.decoder.decode_audio(audio_data)?;
+        if audio_data.context == AudioContext::Listen {
+            audio_data.listener_volume = listener_volume(audio_data.user_id);
+        }
         self.send_taking_information(audio_data.user_id, audio_data.talking);
         if let Err(error) = self.audio_player.add_to_queue(audio_data) {
             return Err(VoiceError::new(format!("Failed to add audio to queue: {error}")).into());
@@ -76,6 +84,16 @@ impl Manager {
         Ok(())
     }
 
+    /// Drops the playback state of a user who left the server
+    pub fn remove_user(&mut self, user_id: u32) -> AnyError<()> {
+        self.user_audio_info.remove(&user_id);
+        if let Err(error) = self.audio_player.remove_user(user_id) {
+            return Err(VoiceError::new(format!("Failed to remove user audio: {error}")).into());
+        }
+
+        Ok(())
+    }
+
     fn send_taking_information(&mut self, user_id: u32, talking: bool) {
         match self.user_audio_info.entry(user_id) {
             Entry::Occupied(o) => {
@@ -118,6 +136,15 @@ impl Manager {
         Ok(())
     }
 
+    pub(crate) fn set_max_bandwidth(&self, max_bandwidth: u32) {
+        self.recoder.set_max_bandwidth(max_bandwidth);
+    }
+
+    pub(crate) fn set_voice_format(&mut self, voice_format: VoiceFormat) {
+        self.decoder = audio::decoder::create_decoder(voice_format);
+        self.recoder.set_voice_format(voice_format);
+    }
+
     pub(crate) fn set_codec(&self, codec_version: &mumble::proto::CodecVersion) {
         send_to_frontend(
             &self.frontend_channel,
@@ -0,0 +1,128 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::Sender;
+use tracing::debug;
+
+use crate::{errors::AnyError, manager::session::Shared, mumble, utils::messages::message_builder};
+
+/// Target id used for normal talking to the current channel
+pub const CURRENT_CHANNEL_TARGET: u8 = 0;
+/// Ids 1 to 30 can be registered, 31 is reserved for the server loopback
+pub const VOICE_TARGET_IDS: std::ops::RangeInclusive<u32> = 1..=30;
+
+/// Users or a channel which hear us while a voice target is active
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct WhisperTarget {
+    #[serde(default)]
+    pub sessions: Vec<u32>,
+    pub channel_id: Option<u32>,
+    /// Only members of this group in the channel hear us
+    pub group: Option<String>,
+    /// Shout to the linked channels as well
+    #[serde(default)]
+    pub links: bool,
+    /// Shout to the sub channels as well
+    #[serde(default)]
+    pub children: bool,
+}
+
+/// The frontend persists voice targets, they have to be registered again after connecting
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct VoiceTarget {
+    pub id: u32,
+    pub targets: Vec<WhisperTarget>,
+    /// Key combination like `Ctrl+Shift+W`, the target is used while it is held
+    pub shortcut: Option<String>,
+}
+
+impl From<WhisperTarget> for mumble::proto::voice_target::Target {
+    fn from(target: WhisperTarget) -> Self {
+        Self {
+            session: target.sessions,
+            channel_id: target.channel_id,
+            group: target.group,
+            links: Some(target.links),
+            children: Some(target.children),
+        }
+    }
+}
+
+impl From<VoiceTarget> for mumble::proto::VoiceTarget {
+    fn from(voice_target: VoiceTarget) -> Self {
+        Self {
+            id: Some(voice_target.id),
+            targets: voice_target
+                .targets
+                .into_iter()
+                .map(mumble::proto::voice_target::Target::from)
+                .collect(),
+        }
+    }
+}
+
+/// Voice targets registered with the server by their id
+///
+/// The server forgets them with the session, so they are registered again after every server sync.
+#[derive(Debug, Default)]
+pub struct VoiceTargetRegistry {
+    targets: BTreeMap<u32, mumble::proto::VoiceTarget>,
+}
+
+impl VoiceTargetRegistry {
+    /// Keeps the voice target, a target without any receivers is removed
+    pub fn register(&mut self, voice_target: &mumble::proto::VoiceTarget) {
+        if voice_target.targets.is_empty() {
+            self.targets.remove(&voice_target.id());
+        } else {
+            self.targets.insert(voice_target.id(), voice_target.clone());
+        }
+    }
+
+    /// Session ids are reused by the server, after a reconnect they may belong to anybody.
+    /// Only the channel receivers are kept, targets without any receiver left are removed.
+    pub fn forget_sessions(&mut self) {
+        for voice_target in self.targets.values_mut() {
+            voice_target.targets.retain_mut(|target| {
+                target.session.clear();
+                target.channel_id.is_some()
+            });
+        }
+        self.targets
+            .retain(|_, voice_target| !voice_target.targets.is_empty());
+    }
+
+    pub fn all(&self) -> Vec<mumble::proto::VoiceTarget> {
+        self.targets.values().cloned().collect()
+    }
+}
+
+pub struct Manager {
+    server_channel: Sender<Vec<u8>>,
+    targets: Shared<VoiceTargetRegistry>,
+}
+
+impl Manager {
+    pub fn new(server_channel: Sender<Vec<u8>>, targets: Shared<VoiceTargetRegistry>) -> Self {
+        Self {
+            server_channel,
+            targets,
+        }
+    }
+
+    /// Registers the known voice targets with the new session of the server.
+    ///
+    /// Whispered users are dropped, their old session ids could now belong to somebody else.
+    pub fn register_all(&self) -> AnyError<()> {
+        let voice_targets = self.targets.write(|targets| {
+            targets.forget_sessions();
+            targets.all()
+        });
+        for voice_target in voice_targets {
+            debug!("Registering voice target {}", voice_target.id());
+            self.server_channel.send(message_builder(&voice_target)?)?;
+        }
+
+        Ok(())
+    }
+}
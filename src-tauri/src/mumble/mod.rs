@@ -1,2 +1,4 @@
 #[allow(clippy::all)]
 pub mod proto;
+#[allow(clippy::all)]
+pub mod udp_proto;
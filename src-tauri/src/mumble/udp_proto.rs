@@ -0,0 +1,4 @@
+#![allow(clippy::pedantic)]
+#![allow(clippy::all)]
+#![allow(clippy::nursery)]
+include!(concat!(env!("OUT_DIR"), "/mumble_udp.rs"));
@@ -8,15 +8,24 @@ use tracing::{error, info, trace, warn};
 use crate::{
     commands::utils::settings::GlobalSettings,
     connection::{traits::Shutdown, MessageChannels},
-    errors::{application_error::ApplicationError, AnyError},
+    errors::{
+        application_error::ApplicationError, permission_denied_error::PermissionDeniedError,
+        AnyError,
+    },
     manager::{
+        acl, ban_list,
         channel::{self},
-        connection_state, text_message,
-        user::{self},
+        connection_state, context_action,
+        pending_action::{PendingAction, PendingActions},
+        server_config,
+        session::{SessionState, Shared},
+        text_message::{self, SentMessage},
+        user, user_list, user_stats,
         voice::{self},
+        voice_target,
     },
     mumble,
-    utils::messages::MessageInfo,
+    utils::{chat_history::ChatHistory, messages::MessageInfo},
 };
 
 #[allow(clippy::struct_field_names)]
@@ -26,6 +35,16 @@ pub struct MessageRouter {
     text_manager: text_message::Manager,
     connection_manager: connection_state::Manager,
     voice_manager: voice::Manager,
+    acl_manager: acl::Manager,
+    ban_list_manager: ban_list::Manager,
+    user_list_manager: user_list::Manager,
+    user_stats_manager: user_stats::Manager,
+    context_action_manager: context_action::Manager,
+    server_config_manager: server_config::Manager,
+    voice_target_manager: voice_target::Manager,
+    pending_actions: Shared<PendingActions>,
+    session_to_restore: Option<mumble::proto::UserState>,
+    reconnecting: bool,
 }
 
 impl MessageRouter {
@@ -33,26 +52,58 @@ impl MessageRouter {
         sender: MessageChannels,
         server_channel: Sender<Vec<u8>>,
         settings_channel: Receiver<GlobalSettings>,
+        session: SessionState,
+        chat_history: Option<ChatHistory>,
     ) -> AnyError<Self> {
         Ok(Self {
             user_manager: user::Manager::new(
                 sender.message_channel.clone(),
                 server_channel.clone(),
+                session.user_channels,
             ),
             channel_manager: channel::Manager::new(
                 sender.message_channel.clone(),
                 server_channel.clone(),
             ),
-            text_manager: text_message::Manager::new(sender.message_channel.clone()),
+            text_manager: text_message::Manager::new(sender.message_channel.clone(), chat_history),
+            acl_manager: acl::Manager::new(
+                sender.message_channel.clone(),
+                server_channel.clone(),
+                session.permissions,
+            ),
+            ban_list_manager: ban_list::Manager::new(
+                sender.message_channel.clone(),
+                session.unreadable_bans,
+            ),
+            user_list_manager: user_list::Manager::new(sender.message_channel.clone()),
+            user_stats_manager: user_stats::Manager::new(sender.message_channel.clone()),
+            context_action_manager: context_action::Manager::new(
+                sender.message_channel.clone(),
+                session.context_actions,
+            ),
+            server_config_manager: server_config::Manager::new(
+                sender.message_channel.clone(),
+                session.server_config,
+            ),
+            voice_target_manager: voice_target::Manager::new(
+                server_channel.clone(),
+                session.voice_targets,
+            ),
             connection_manager: connection_state::Manager::new(
                 sender.message_channel.clone(),
                 server_channel.clone(),
+                sender.crypt_setup_channel,
+                sender.ping_channel,
             ),
             voice_manager: voice::Manager::new(
                 sender.message_channel,
                 server_channel,
+                sender.voice_channel,
                 settings_channel,
             )?,
+            pending_actions: session.pending_actions,
+            session_to_restore: None,
+            reconnecting: false,
         })
     }
 
@@ -82,19 +133,90 @@ impl MessageRouter {
         Ok(())
     }
 
+    pub fn add_sent_message(&mut self, message: SentMessage) {
+        match self.user_manager.current_user() {
+            Some(user) => self.text_manager.add_sent_message(message, user),
+            None => warn!("Sent a text message before the server sync"),
+        }
+    }
+
+    fn handle_permission_denied(&mut self, permission_denied: &mumble::proto::PermissionDenied) {
+        let error = PermissionDeniedError::from(permission_denied);
+        warn!("{}", error);
+
+        let action = self
+            .pending_actions
+            .write(|actions| actions.resolve(&error));
+        if let Some(PendingAction::TextMessage { message_id }) = &action {
+            self.text_manager.fail_sent_message(message_id);
+        }
+        self.connection_manager
+            .notify_permission_denied(&error, action);
+    }
+
+    /// Actions the server never got, or never answered, are lost with the connection
+    fn fail_pending_actions(&mut self) {
+        for action in self.pending_actions.write(PendingActions::drain) {
+            if let PendingAction::TextMessage { message_id } = action {
+                self.text_manager.fail_sent_message(&message_id);
+            }
+        }
+    }
+
+    pub fn prepare_reconnect(&mut self) {
+        self.session_to_restore = self.user_manager.session_snapshot();
+        self.reconnecting = true;
+        self.fail_pending_actions();
+        self.user_manager.clear();
+        self.channel_manager.clear();
+        // permissions may have changed while we were gone, the server sends them again
+        self.acl_manager.clear();
+        // sessions are assigned again, the details may belong to someone else now
+        self.user_stats_manager.clear();
+        // the server registers them again after the reconnect
+        self.context_action_manager.clear();
+    }
+
+    fn restore_session(&mut self) -> AnyError<()> {
+        if let Some(session) = self.session_to_restore.take() {
+            self.user_manager.restore_session(&session)?;
+            self.connection_manager.notify_reconnected();
+        }
+
+        Ok(())
+    }
+
     pub fn recv_message(&mut self, message: MessageInfo) -> AnyError<()> {
         if message.message_type != crate::utils::messages::MessageTypes::UdpTunnel {
             trace!("Received message: {:<100?}", message);
         }
 
         match message.message_type {
-            crate::utils::messages::MessageTypes::Version => {}
+            crate::utils::messages::MessageTypes::Version => {
+                let version = Self::handle_downcast::<mumble::proto::Version>(message)?;
+                let voice_format = self.server_config_manager.notify_version(&version);
+                self.voice_manager.set_voice_format(voice_format);
+            }
             crate::utils::messages::MessageTypes::UdpTunnel => {
                 let audio_data = Self::handle_downcast::<Vec<u8>>(message)?;
-                self.voice_manager.notify_audio(&audio_data)?;
+                let user_manager = &self.user_manager;
+                self.voice_manager.notify_audio(&audio_data, |session| {
+                    user_manager.listener_volume_adjustment(session)
+                })?;
             }
             crate::utils::messages::MessageTypes::Authenticate => {}
-            crate::utils::messages::MessageTypes::Ping => {}
+            crate::utils::messages::MessageTypes::Ping => {
+                let ping = Self::handle_downcast::<mumble::proto::Ping>(message)?;
+                self.connection_manager.notify_ping(&ping)?;
+                let confirmed = self
+                    .pending_actions
+                    .write(|actions| actions.confirm(ping.timestamp()));
+                for action in confirmed {
+                    if let PendingAction::TextMessage { message_id } = action {
+                        self.text_manager.confirm_sent_message(&message_id);
+                    }
+                }
+            }
             crate::utils::messages::MessageTypes::Reject => {
                 let reject = Self::handle_downcast::<mumble::proto::Reject>(message)?;
                 self.connection_manager.notify_disconnected(&reject.reason);
@@ -105,8 +227,29 @@ impl MessageRouter {
             crate::utils::messages::MessageTypes::ServerSync => {
                 let server_sync = Self::handle_downcast::<mumble::proto::ServerSync>(message)?;
                 self.user_manager.notify_current_user(&server_sync);
+                if let Some(max_bandwidth) = server_sync.max_bandwidth {
+                    self.server_config_manager
+                        .notify_max_bandwidth(max_bandwidth);
+                    self.voice_manager.set_max_bandwidth(max_bandwidth);
+                }
+                if let Some(permissions) = server_sync.permissions {
+                    // the permissions in the sync message are the ones of the root channel
+                    match u32::try_from(permissions) {
+                        Ok(permissions) => self.acl_manager.update_permissions(0, permissions),
+                        Err(e) => warn!("Invalid root permissions {permissions}: {e}"),
+                    }
+                }
+                if self.reconnecting {
+                    // everything sent while we were offline was dropped
+                    self.fail_pending_actions();
+                    self.reconnecting = false;
+                }
                 self.connection_manager.notify_connected();
+                // the server only accepts them once we are synchronized
+                self.voice_target_manager.register_all()?;
                 self.voice_manager.deafen()?;
+                self.restore_session()?;
+                self.text_manager.load_history();
             }
             crate::utils::messages::MessageTypes::ChannelRemove => {
                 let removed_channel =
@@ -121,45 +264,76 @@ impl MessageRouter {
             crate::utils::messages::MessageTypes::UserRemove => {
                 let removed_user = Self::handle_downcast::<mumble::proto::UserRemove>(message)?;
                 self.user_manager.remove_user(&removed_user);
+                self.user_stats_manager.remove_user(removed_user.session);
+                self.voice_manager.remove_user(removed_user.session)?;
             }
             crate::utils::messages::MessageTypes::UserState => {
                 let mut changed_user = Self::handle_downcast::<mumble::proto::UserState>(message)?;
                 self.user_manager.update_user(&mut changed_user)?;
             }
-            crate::utils::messages::MessageTypes::BanList => {}
+            crate::utils::messages::MessageTypes::BanList => {
+                let ban_list = Self::handle_downcast::<mumble::proto::BanList>(message)?;
+                self.ban_list_manager.notify_ban_list(ban_list);
+            }
             crate::utils::messages::MessageTypes::TextMessage => {
                 self.handle_text_message(message)?;
             }
             crate::utils::messages::MessageTypes::PermissionDenied => {
                 let permission_denied =
                     Self::handle_downcast::<mumble::proto::PermissionDenied>(message)?;
-                warn!("Permission denied: {:?}", permission_denied);
+                self.handle_permission_denied(&permission_denied);
+            }
+            crate::utils::messages::MessageTypes::Acl => {
+                let acl = Self::handle_downcast::<mumble::proto::Acl>(message)?;
+                self.acl_manager.notify_acl(acl);
             }
-            crate::utils::messages::MessageTypes::Acl => {}
             crate::utils::messages::MessageTypes::QueryUsers => {}
-            crate::utils::messages::MessageTypes::CryptSetup => {}
-            crate::utils::messages::MessageTypes::ContextActionModify => {}
+            crate::utils::messages::MessageTypes::CryptSetup => {
+                let crypt_setup = Self::handle_downcast::<mumble::proto::CryptSetup>(message)?;
+                self.connection_manager.notify_crypt_setup(crypt_setup)?;
+            }
+            crate::utils::messages::MessageTypes::ContextActionModify => {
+                let modify = Self::handle_downcast::<mumble::proto::ContextActionModify>(message)?;
+                self.context_action_manager
+                    .notify_context_action_modify(modify);
+            }
             crate::utils::messages::MessageTypes::ContextAction => {}
-            crate::utils::messages::MessageTypes::UserList => {}
+            crate::utils::messages::MessageTypes::UserList => {
+                let user_list = Self::handle_downcast::<mumble::proto::UserList>(message)?;
+                self.user_list_manager.notify_user_list(user_list);
+            }
             crate::utils::messages::MessageTypes::VoiceTarget => {}
             crate::utils::messages::MessageTypes::PermissionQuery => {
                 let permission_query =
                     Self::handle_downcast::<mumble::proto::PermissionQuery>(message)?;
-                warn!("Permission query: {:?}", permission_query);
-
-                if permission_query.flush() {
-                    //TODO: We currently don't support ACLs, so we just disconnect the user
-                    //self.shutdown().await?;
-                }
+                trace!("Permission query: {:?}", permission_query);
+                self.acl_manager
+                    .notify_permission_query(&permission_query)?;
             }
             crate::utils::messages::MessageTypes::CodecVersion => {
                 let codec_version = Self::handle_downcast::<mumble::proto::CodecVersion>(message)?;
                 self.voice_manager.set_codec(&codec_version);
             }
-            crate::utils::messages::MessageTypes::UserStats => {}
+            crate::utils::messages::MessageTypes::UserStats => {
+                let user_stats = Self::handle_downcast::<mumble::proto::UserStats>(message)?;
+                self.user_stats_manager.notify_user_stats(user_stats);
+            }
             crate::utils::messages::MessageTypes::RequestBlob => {}
-            crate::utils::messages::MessageTypes::ServerConfig => {}
-            crate::utils::messages::MessageTypes::SuggestConfig => {}
+            crate::utils::messages::MessageTypes::ServerConfig => {
+                let server_config = Self::handle_downcast::<mumble::proto::ServerConfig>(message)?;
+                if let Some(max_bandwidth) = self
+                    .server_config_manager
+                    .notify_server_config(server_config)
+                {
+                    self.voice_manager.set_max_bandwidth(max_bandwidth);
+                }
+            }
+            crate::utils::messages::MessageTypes::SuggestConfig => {
+                let suggest_config =
+                    Self::handle_downcast::<mumble::proto::SuggestConfig>(message)?;
+                self.server_config_manager
+                    .notify_suggest_config(suggest_config);
+            }
             crate::utils::messages::MessageTypes::PluginDataTransmission => {
                 info!("Received plugin data transmission");
             }
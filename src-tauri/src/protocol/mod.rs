@@ -5,7 +5,7 @@ pub mod stream_reader;
 
 use std::cmp;
 
-use crate::{mumble, utils::messages::message_builder};
+use crate::{errors::AnyError, mumble, utils::messages::message_builder};
 use tauri::PackageInfo;
 use tokio::sync::broadcast::Sender;
 
@@ -29,10 +29,52 @@ const fn get_patch(version: u64) -> u64 {
     (version & FIELD_PATCH) >> OFFSET_PATCH
 }
 
-const fn from_components(major: u64, minor: u64, patch: u64) -> u64 {
+pub const fn from_components(major: u64, minor: u64, patch: u64) -> u64 {
     (major << OFFSET_MAJOR) | (minor << OFFSET_MINOR) | (patch << OFFSET_PATCH)
 }
 
+// first version exchanging voice and UDP pings as `MumbleUDP` protobuf messages
+const PROTOBUF_VOICE_VERSION: u64 = from_components(1, 5, 0);
+// first byte of a protobuf UDP packet, followed by the encoded message
+pub const UDP_AUDIO_MESSAGE: u8 = 0;
+pub const UDP_PING_MESSAGE: u8 = 1;
+
+/// Encoding of voice packets and UDP pings, tunneled voice uses the same format
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum VoiceFormat {
+    #[default]
+    Legacy,
+    Protobuf,
+}
+
+impl VoiceFormat {
+    /// We announce the protobuf version, so the server decides which format is used
+    pub const fn for_server_version(version: u64) -> Self {
+        if version >= PROTOBUF_VOICE_VERSION {
+            Self::Protobuf
+        } else {
+            Self::Legacy
+        }
+    }
+}
+
+pub fn from_legacy_version(version: u32) -> u64 {
+    from_components(
+        u64::from(version >> 16),
+        u64::from((version >> 8) & 0xFF),
+        u64::from(version & 0xFF),
+    )
+}
+
+pub fn format_version(version: u64) -> String {
+    format!(
+        "{}.{}.{}",
+        get_major(version),
+        get_minor(version),
+        get_patch(version)
+    )
+}
+
 // The size of the version field in the legacy protocol is 32 bits, so we need to truncate
 #[allow(clippy::cast_possible_truncation)]
 fn to_legacy_version(version: u64) -> u32 {
@@ -45,14 +87,20 @@ fn to_legacy_version(version: u64) -> u32 {
     major | minor | patch
 }
 
-pub fn init_connection(username: &str, channel: &Sender<Vec<u8>>, package_info: &PackageInfo) {
+pub fn init_connection(
+    username: &str,
+    password: Option<String>,
+    access_tokens: Vec<String>,
+    channel: &Sender<Vec<u8>>,
+    package_info: &PackageInfo,
+) {
     let fancy_version = from_components(
         package_info.version.major,
         package_info.version.minor,
         package_info.version.patch,
     );
 
-    let mumble_version = from_components(1, 4, 0);
+    let mumble_version = PROTOBUF_VOICE_VERSION;
 
     let info = os_info::get();
 
@@ -77,8 +125,8 @@ pub fn init_connection(username: &str, channel: &Sender<Vec<u8>>, package_info:
     let auth = mumble::proto::Authenticate {
         opus: Some(true),
         celt_versions: vec![0, -2_147_483_632, -2_147_483_637],
-        password: None,
-        tokens: vec![],
+        password,
+        tokens: access_tokens,
         username: Some(username.to_string()),
         client_type: Some(0), // 1 = BOT, 0 = User
     };
@@ -86,3 +134,14 @@ pub fn init_connection(username: &str, channel: &Sender<Vec<u8>>, package_info:
     let buffer = message_builder(&auth).unwrap_or_default();
     _ = channel.send(buffer);
 }
+
+pub fn update_access_tokens(access_tokens: Vec<String>, channel: &Sender<Vec<u8>>) -> AnyError<()> {
+    // the server only looks at the tokens, if we are already authenticated
+    let auth = mumble::proto::Authenticate {
+        tokens: access_tokens,
+        ..Default::default()
+    };
+    channel.send(message_builder(&auth)?)?;
+
+    Ok(())
+}
@@ -4,34 +4,28 @@ use tracing::error;
 
 use crate::{
     errors::AnyError,
+    manager::text_message::SentMessage,
     utils::messages::{get_message, MessageInfo, MessageTypes},
 };
 
 use super::message_router::MessageRouter;
 
-pub struct StreamReader {
+/// Splits the TCP stream into messages, the data of a frame may arrive in several reads
+#[derive(Default)]
+pub struct FrameBuffer {
     stream_buffer: Vec<u8>, //TODO: replace with vecdeque
-    message_handler: MessageRouter,
 }
 
-impl StreamReader {
-    pub const fn new(message_handler: MessageRouter) -> Self {
-        Self {
-            stream_buffer: Vec::<u8>::new(),
-            message_handler,
-        }
+impl FrameBuffer {
+    pub fn push(&mut self, data: &mut Vec<u8>) {
+        self.stream_buffer.append(data);
     }
 
-    pub fn read_next(&mut self, data: &mut Vec<u8>) {
-        self.stream_buffer.append(data);
-        while let Some(result) = self.try_read() {
-            if let Err(e) = self.message_handler.recv_message(result) {
-                error!("Error handling message: {}", e);
-            }
-        }
+    pub fn clear(&mut self) {
+        self.stream_buffer.clear();
     }
 
-    fn try_read(&mut self) -> Option<MessageInfo> {
+    pub fn try_read(&mut self) -> Option<MessageInfo> {
         if self.stream_buffer.len() < 6 {
             return None;
         }
@@ -70,9 +64,55 @@ impl StreamReader {
     fn get_n_from(&self, n: usize, start: usize) -> &[u8] {
         &self.stream_buffer[start..(n + start)]
     }
+}
+
+pub struct StreamReader {
+    frames: FrameBuffer,
+    message_handler: MessageRouter,
+}
+
+impl StreamReader {
+    pub fn new(message_handler: MessageRouter) -> Self {
+        Self {
+            frames: FrameBuffer::default(),
+            message_handler,
+        }
+    }
+
+    pub fn read_next(&mut self, data: &mut Vec<u8>) {
+        self.frames.push(data);
+        while let Some(result) = self.frames.try_read() {
+            if let Err(e) = self.message_handler.recv_message(result) {
+                error!("Error handling message: {}", e);
+            }
+        }
+    }
+
+    /// Handles voice packets which were received over UDP instead of the TCP tunnel
+    pub fn read_voice(&mut self, data: Vec<u8>) {
+        let message = MessageInfo {
+            message_type: MessageTypes::UdpTunnel,
+            message_data: Box::new(data),
+        };
+
+        if let Err(e) = self.message_handler.recv_message(message) {
+            error!("Error handling voice packet: {}", e);
+        }
+    }
+
+    /// Records a message the current user sent, the server doesn't echo it back
+    pub fn add_sent_message(&mut self, message: SentMessage) {
+        self.message_handler.add_sent_message(message);
+    }
+
+    /// Drops partially received data of the lost connection and remembers the current session
+    pub fn prepare_reconnect(&mut self) {
+        self.frames.clear();
+        self.message_handler.prepare_reconnect();
+    }
 
     pub async fn shutdown(&mut self) -> AnyError<()> {
-        self.stream_buffer.clear();
+        self.frames.clear();
         self.message_handler.shutdown().await?;
 
         Ok(())
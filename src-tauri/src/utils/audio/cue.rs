@@ -0,0 +1,68 @@
+use std::time::Duration;
+
+use rodio::{source::SineWave, OutputStream, OutputStreamHandle, Source};
+use tracing::warn;
+
+use super::device_manager::open_output_stream;
+
+const CUE_DURATION: Duration = Duration::from_millis(80);
+const CUE_VOLUME: f32 = 0.2;
+const START_FREQUENCY: f32 = 880.0;
+const STOP_FREQUENCY: f32 = 440.0;
+
+/// Short beeps, which signal when push to talk starts or stops transmitting
+pub struct AudioCue {
+    // the stream stops playing once it is dropped
+    output: Option<(OutputStream, OutputStreamHandle)>,
+    // `None` plays them on the default device
+    device: Option<String>,
+}
+
+impl AudioCue {
+    pub const fn new() -> Self {
+        Self {
+            output: None,
+            device: None,
+        }
+    }
+
+    /// Plays the following cues on the given output device, like the voices of the other users
+    pub fn set_device(&mut self, device: Option<&str>) {
+        self.device = device.map(ToString::to_string);
+        // opened again with the next cue
+        self.output = None;
+    }
+
+    pub fn play(&mut self, transmitting: bool) {
+        if self.output.is_none() {
+            match open_output_stream(self.device.as_deref()) {
+                Ok(output) => self.output = Some(output),
+                Err(e) => {
+                    warn!("Failed to open audio output for cues: {}", e);
+                    return;
+                }
+            }
+        }
+
+        let frequency = if transmitting {
+            START_FREQUENCY
+        } else {
+            STOP_FREQUENCY
+        };
+        let cue = SineWave::new(frequency)
+            .take_duration(CUE_DURATION)
+            .amplify(CUE_VOLUME);
+
+        if let Some((_, handle)) = self.output.as_ref() {
+            if let Err(e) = handle.play_raw(cue) {
+                warn!("Failed to play audio cue: {}", e);
+            }
+        }
+    }
+}
+
+impl Default for AudioCue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
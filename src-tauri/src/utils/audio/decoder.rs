@@ -1,38 +1,63 @@
-use std::collections::{btree_map::Entry, BTreeMap};
+use prost::Message;
 
 use crate::{
     errors::AnyError,
+    mumble::udp_proto,
+    protocol::{VoiceFormat, UDP_AUDIO_MESSAGE},
     utils::varint::{self},
 };
 
+use super::positional::Position;
+
+/// Why the server sent us the audio, stored in the target bits of the header by legacy servers
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum AudioContext {
+    #[default]
+    Normal,
+    Shout,
+    Whisper,
+    /// We only hear it because we listen to the channel of the speaker
+    Listen,
+}
+
+impl From<u32> for AudioContext {
+    fn from(context: u32) -> Self {
+        match context {
+            1 => Self::Shout,
+            2 => Self::Whisper,
+            3 => Self::Listen,
+            _ => Self::Normal,
+        }
+    }
+}
+
+/// A received audio packet, the opus payload is decoded by the player once it is due
 pub struct DecodedMessage {
     pub user_id: u32,
     pub talking: bool,
-    pub data: Vec<i16>,
+    pub context: AudioContext,
+    pub sequence_number: u64,
+    pub payload: Vec<u8>,
+    /// Linear factor for audio we only hear through a channel listener
+    pub listener_volume: f32,
+    /// Position of the speaker, if the client sends positional audio
+    pub position: Option<Position>,
 }
 
 pub trait Decoder: Send {
     fn decode_audio(&mut self, audio_data: &[u8]) -> AnyError<DecodedMessage>;
 }
 
-#[allow(clippy::struct_field_names)]
-#[allow(clippy::module_name_repetitions)]
-pub struct UDPDecoder {
-    decoder_map: DecoderMap,
-    sample_rate: u32,
-    channels: opus::Channels,
-}
-
-impl UDPDecoder {
-    pub fn new(sample_rate: u32, channels: opus::Channels) -> Self {
-        Self {
-            decoder_map: DecoderMap::new(sample_rate, channels),
-            sample_rate,
-            channels,
-        }
+pub fn create_decoder(format: VoiceFormat) -> Box<dyn Decoder> {
+    match format {
+        VoiceFormat::Legacy => Box::new(UDPDecoder),
+        VoiceFormat::Protobuf => Box::new(ProtobufDecoder),
     }
 }
 
+#[allow(clippy::module_name_repetitions)]
+pub struct UDPDecoder;
+
 impl Decoder for UDPDecoder {
     // we want a downcast, because we are reading from a stream
     #[allow(clippy::cast_possible_truncation)]
@@ -42,7 +67,7 @@ impl Decoder for UDPDecoder {
         let audio_header = audio_data[0];
 
         let audio_type = (audio_header & 0xE0) >> 5;
-        //let audio_target = audio_header & 0x1F;
+        let context = AudioContext::from(u32::from(audio_header & 0x1F));
         if audio_type != 4 {
             return Err(format!("Received audio data with unknown type: {audio_type:?}").into());
         }
@@ -69,77 +94,55 @@ impl Decoder for UDPDecoder {
         let talking = (opus_header.0 & 0x2000) <= 0;
         let user_id = session_id.0 as u32;
 
-        // = SampleRate * 60ms = 48000Hz * 0.06s = 2880, ~12KB
-        let mut audio_buffer_size = self.sample_rate * 60 / 1000;
-        if self.channels == opus::Channels::Stereo {
-            audio_buffer_size *= 2;
-        }
-        let mut decoded_data = vec![0; audio_buffer_size as usize];
-
         let payload_size = opus_header.0 & 0x1FFF;
-        let payload = &audio_data[position..position + payload_size as usize];
-        // each user needs their own decoder, because the opus decoder seems to have a bug, which keeps prev. entries. This causes audio glitches
-        let decoder = self.decoder_map.get_decoder(user_id)?;
-        let num_decoded_samples = decoder.decode(payload, &mut decoded_data, false)?;
-        decoded_data.truncate(num_decoded_samples);
+        let payload = audio_data
+            .get(position..position + payload_size as usize)
+            .ok_or("Received truncated audio data")?;
+        position += payload_size as usize;
 
         Ok(DecodedMessage {
             user_id,
             talking,
-            data: decoded_data,
+            context,
+            sequence_number: sequence_number.0 as u64,
+            payload: payload.to_vec(),
+            listener_volume: 1.0,
+            position: Position::from_le_bytes(&audio_data[position..]),
         })
     }
 }
 
-struct DecoderMap {
-    sample_rate: u32,
-    channels: opus::Channels,
-    sink_map: BTreeMap<u32, opus::Decoder>,
-}
+/// Reads `MumbleUDP.Audio` messages, used by servers since 1.5
+#[allow(clippy::module_name_repetitions)]
+pub struct ProtobufDecoder;
 
-impl DecoderMap {
-    fn new(sample_rate: u32, channels: opus::Channels) -> Self {
-        Self {
-            sample_rate,
-            channels,
-            sink_map: BTreeMap::new(),
+impl Decoder for ProtobufDecoder {
+    fn decode_audio(&mut self, audio_data: &[u8]) -> AnyError<DecodedMessage> {
+        let (&message_type, message) = audio_data
+            .split_first()
+            .ok_or("Received empty audio data")?;
+        if message_type != UDP_AUDIO_MESSAGE {
+            return Err(format!("Received UDP message with unknown type: {message_type:?}").into());
         }
-    }
 
-    fn get_decoder(&mut self, user_id: u32) -> AnyError<&mut opus::Decoder> {
-        let result = match self.sink_map.entry(user_id) {
-            Entry::Occupied(entry) => entry.into_mut(),
-            Entry::Vacant(entry) => {
-                entry.insert(Self::create_sink(self.sample_rate, self.channels)?)
-            }
+        let audio = udp_proto::Audio::decode(message)?;
+        let context = match audio.header {
+            Some(udp_proto::audio::Header::Context(context)) => AudioContext::from(context),
+            _ => AudioContext::Normal,
+        };
+        let position = match audio.positional_data[..] {
+            [x, y, z, ..] => Some(Position { x, y, z }),
+            _ => None,
         };
 
-        Ok(result)
-    }
-
-    fn create_sink(sample_rate: u32, channels: opus::Channels) -> AnyError<opus::Decoder> {
-        Ok(opus::Decoder::new(sample_rate, channels)?)
+        Ok(DecodedMessage {
+            user_id: audio.sender_session,
+            talking: !audio.is_terminator,
+            context,
+            sequence_number: audio.frame_number,
+            payload: audio.opus_data,
+            listener_volume: 1.0,
+            position,
+        })
     }
 }
-
-// pub struct ProtobufDecoder {
-//     decoder_map: DecoderMap,
-//     sample_rate: u32,
-//     channels: opus::Channels,
-// }
-
-// impl ProtobufDecoder {
-//     pub fn new(sample_rate: u32, channels: opus::Channels) -> Self {
-//         Self {
-//             decoder_map: DecoderMap::new(sample_rate, channels),
-//             sample_rate,
-//             channels,
-//         }
-//     }
-// }
-
-// impl Decoder for ProtobufDecoder {
-//     fn decode_audio(&mut self, audio_data: &[u8]) -> AnyError<DecodedMessage> {
-//         Err("Not implemented".into())
-//     }
-// }
@@ -1,11 +1,16 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, thread, time::Duration};
 
 use rodio::{
     cpal::{self, traits::HostTrait},
-    Device, DeviceTrait,
+    Device, DeviceTrait, OutputStream, OutputStreamHandle,
 };
+use tokio::sync::broadcast::Sender;
+use tracing::{info, trace, warn};
 
-use crate::errors::AnyError;
+use crate::{commands::utils::settings::GlobalSettings, errors::AnyError};
+
+// devices can't be watched, so the available devices are looked up periodically
+const DEVICE_CHECK_INTERVAL: Duration = Duration::from_secs(5);
 
 #[allow(clippy::module_name_repetitions)]
 pub struct AudioDeviceManager {
@@ -44,3 +49,146 @@ impl AudioDeviceManager {
             .collect::<Result<_, _>>()
     }
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceKind {
+    Input,
+    Output,
+}
+
+/// Names of the devices of one kind, which were available during the last lookup
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AvailableDevices {
+    pub kind: DeviceKind,
+    pub names: Vec<String>,
+}
+
+fn devices(kind: DeviceKind) -> AnyError<Vec<Device>> {
+    let host = cpal::default_host();
+    let devices = match kind {
+        DeviceKind::Input => host.input_devices()?.collect(),
+        DeviceKind::Output => host.output_devices()?.collect(),
+    };
+
+    Ok(devices)
+}
+
+/// Names of the available devices, cpal has no other stable identifier
+pub fn device_names(kind: DeviceKind) -> AnyError<Vec<String>> {
+    Ok(devices(kind)?
+        .iter()
+        .filter_map(|device| device.name().ok())
+        .collect())
+}
+
+/// Returns the device with the given name, or the default device if there is none
+pub fn find_device(kind: DeviceKind, name: Option<&str>) -> AnyError<Device> {
+    if let Some(name) = name {
+        let device = devices(kind)?
+            .into_iter()
+            .find(|device| device.name().is_ok_and(|device_name| device_name == name));
+
+        match device {
+            Some(device) => return Ok(device),
+            None => warn!("Audio device {name} not found, using the default device"),
+        }
+    }
+
+    let host = cpal::default_host();
+    let device = match kind {
+        DeviceKind::Input => host.default_input_device(),
+        DeviceKind::Output => host.default_output_device(),
+    };
+
+    device.ok_or_else(|| format!("Failed to get default {kind:?} device").into())
+}
+
+pub fn open_output_stream(device: Option<&str>) -> AnyError<(OutputStream, OutputStreamHandle)> {
+    let device = find_device(DeviceKind::Output, device)?;
+
+    Ok(OutputStream::try_from_device(&device)?)
+}
+
+/// Looks up the available devices in the background and sends them over the settings channel
+///
+/// Enumerating devices can block for a while, which the audio threads can't afford. The thread
+/// ends once nobody listens to the settings channel anymore.
+pub fn watch_devices(settings_channel: Sender<GlobalSettings>) {
+    thread::spawn(move || loop {
+        for kind in [DeviceKind::Input, DeviceKind::Output] {
+            let names = match device_names(kind) {
+                Ok(names) => names,
+                Err(e) => {
+                    warn!("Failed to get {kind:?} devices: {e}");
+                    continue;
+                }
+            };
+
+            let devices = AvailableDevices { kind, names };
+            if settings_channel
+                .send(GlobalSettings::AvailableDevices(devices))
+                .is_err()
+            {
+                trace!("Settings channel closed, no longer watching audio devices");
+                return;
+            }
+        }
+
+        thread::sleep(DEVICE_CHECK_INTERVAL);
+    });
+}
+
+/// The device chosen by the user, the default device is used while it is unavailable
+pub struct DeviceSelection {
+    kind: DeviceKind,
+    preferred: Option<String>,
+    active: Option<String>,
+    // unknown until the first lookup arrived
+    available: Option<Vec<String>>,
+}
+
+impl DeviceSelection {
+    pub const fn new(kind: DeviceKind) -> Self {
+        Self {
+            kind,
+            preferred: None,
+            active: None,
+            available: None,
+        }
+    }
+
+    pub fn select(&mut self, device: Option<String>) {
+        self.preferred = device;
+    }
+
+    /// Keeps the result of a lookup, devices of the other kind are ignored
+    pub fn update_available(&mut self, devices: AvailableDevices) {
+        if devices.kind == self.kind {
+            self.available = Some(devices.names);
+        }
+    }
+
+    pub fn active(&self) -> Option<&str> {
+        self.active.as_deref()
+    }
+
+    /// Checks whether the stream has to switch to another device, `active` returns the new one
+    pub fn poll(&mut self) -> bool {
+        let available = self.preferred.as_ref().filter(|preferred| {
+            self.available
+                .as_ref()
+                .is_some_and(|names| names.contains(preferred))
+        });
+        if available == self.active.as_ref() {
+            return false;
+        }
+
+        info!(
+            "Switching {:?} device to {}",
+            self.kind,
+            available.map_or("the default device", String::as_str)
+        );
+        self.active = available.cloned();
+        true
+    }
+}
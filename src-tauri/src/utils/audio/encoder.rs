@@ -1,67 +1,135 @@
 use opus::Channels;
+use prost::Message;
+use tracing::{error, info, trace};
 
-use crate::utils::varint;
+use crate::{
+    manager::voice_target::CURRENT_CHANNEL_TARGET,
+    mumble::udp_proto,
+    protocol::{VoiceFormat, UDP_AUDIO_MESSAGE},
+    utils::varint,
+};
 
 use super::microphone::DeviceConfig;
 
 const MAXIMUM_SAMPLES_PER_TALK: u64 = 600;
 const QUALITY: opus::Application = opus::Application::Audio;
+const MIN_BITRATE: i32 = 8000;
+// the target is stored in the lower 5 bits of the header
+const TARGET_MASK: u8 = 0b0001_1111;
+// set in the opus size header of the last packet of a transmission
+const TERMINATOR_BIT: i128 = 0x2000;
+// IP, UDP, crypt and Mumble audio header of every packet
+const PACKET_OVERHEAD_BYTES: u32 = 20 + 8 + 4 + 1 + 2 + 2;
 
 pub trait Encoder {
     fn encode_audio(&mut self, data: &[f32], sequence_number: &mut u64) -> Option<Vec<u8>>;
+
+    /// Limits the bitrate, so the voice data including the packet overhead fits into `max_bandwidth`
+    fn set_max_bandwidth(&mut self, max_bandwidth: u32);
+
+    /// Selects the registered voice target the following packets are sent to, returns the packet
+    /// ending the transmission to the previous target if we were talking to it
+    fn set_target(&mut self, target: u8, sequence_number: &mut u64) -> Option<Vec<u8>>;
 }
 
-#[allow(clippy::struct_field_names)] // yes
-#[allow(clippy::module_name_repetitions)] // yes
-pub struct UDPEncoder {
+pub fn create_encoder(format: VoiceFormat, config: DeviceConfig) -> Box<dyn Encoder> {
+    match format {
+        VoiceFormat::Legacy => Box::new(UDPEncoder::new(config)),
+        VoiceFormat::Protobuf => Box::new(ProtobufEncoder::new(config)),
+    }
+}
+
+fn is_zero(buf: &[f32]) -> bool {
+    let (prefix, aligned, suffix) = unsafe { buf.align_to::<u128>() };
+
+    prefix.iter().all(|&x| x == 0.0)
+        && suffix.iter().all(|&x| x == 0.0)
+        && aligned.iter().all(|&x| x == 0)
+}
+
+/// The opus part shared by all packet formats
+struct OpusEncoder {
     encoder: opus::Encoder,
     audio_buffer_size: usize,
-    talking: bool,
+    sample_rate: u32,
+    default_bitrate: i32,
 }
 
-impl UDPEncoder {
-    pub fn new(config: DeviceConfig) -> Self {
+impl OpusEncoder {
+    fn new(config: DeviceConfig) -> Self {
         let opus_channels = match config.channels {
             1 => Channels::Mono,
             2 => Channels::Stereo,
             _ => panic!("Unsupported channel count"),
         };
 
-        let encoder = opus::Encoder::new(config.sample_rate, opus_channels, QUALITY)
+        let mut encoder = opus::Encoder::new(config.sample_rate, opus_channels, QUALITY)
             .expect("Failed to create opus encoder");
+        let default_bitrate = match encoder.get_bitrate() {
+            Ok(opus::Bitrate::Bits(bits)) => bits,
+            _ => i32::MAX,
+        };
 
         Self {
             encoder,
             audio_buffer_size: config.buffer_size,
-            talking: false,
+            sample_rate: config.sample_rate,
+            default_bitrate,
+        }
+    }
+
+    fn encode(&mut self, data: &[f32]) -> Vec<u8> {
+        self.encoder
+            .encode_vec_float(data, self.audio_buffer_size)
+            .expect("Failed to encode audio data")
+    }
+
+    fn set_max_bandwidth(&mut self, max_bandwidth: u32) {
+        let samples_per_packet = u32::try_from(self.audio_buffer_size)
+            .unwrap_or(u32::MAX)
+            .max(1);
+        let packets_per_second = (self.sample_rate / samples_per_packet).max(1);
+        let overhead = packets_per_second * PACKET_OVERHEAD_BYTES * 8;
+
+        let allowed = i32::try_from(max_bandwidth.saturating_sub(overhead)).unwrap_or(i32::MAX);
+        let bitrate = allowed.clamp(MIN_BITRATE, self.default_bitrate.max(MIN_BITRATE));
+        info!("Setting encoder bitrate to {bitrate} (max bandwidth {max_bandwidth})");
+
+        if let Err(e) = self.encoder.set_bitrate(opus::Bitrate::Bits(bitrate)) {
+            error!("Failed to set encoder bitrate: {}", e);
         }
     }
+}
 
-    fn is_zero(buf: &[f32]) -> bool {
-        let (prefix, aligned, suffix) = unsafe { buf.align_to::<u128>() };
+#[allow(clippy::module_name_repetitions)] // yes
+pub struct UDPEncoder {
+    opus: OpusEncoder,
+    target: u8,
+    talking: bool,
+}
 
-        prefix.iter().all(|&x| x == 0.0)
-            && suffix.iter().all(|&x| x == 0.0)
-            && aligned.iter().all(|&x| x == 0)
+impl UDPEncoder {
+    pub fn new(config: DeviceConfig) -> Self {
+        Self {
+            opus: OpusEncoder::new(config),
+            target: CURRENT_CHANNEL_TARGET,
+            talking: false,
+        }
     }
 }
 
 impl Encoder for UDPEncoder {
     fn encode_audio(&mut self, data: &[f32], sequence_number: &mut u64) -> Option<Vec<u8>> {
-        let is_only_zero = Self::is_zero(data);
+        let is_only_zero = is_zero(data);
         if !self.talking && is_only_zero {
             return None;
         }
-        let output = self
-            .encoder
-            .encode_vec_float(data, self.audio_buffer_size)
-            .expect("Failed to encode audio data");
+        let output = self.opus.encode(data);
 
         let mut audio_buffer = Vec::new();
 
         let opus_audio_codec = 4u8 << 5;
-        let target = 0b0000_0000u8;
-        let first_byte = opus_audio_codec | target;
+        let first_byte = opus_audio_codec | (self.target & TARGET_MASK);
         audio_buffer.push(first_byte);
 
         let sequence_number_bytes = varint::Builder::new()
@@ -71,13 +139,10 @@ impl Encoder for UDPEncoder {
         audio_buffer.extend(sequence_number_bytes.parsed_vec());
         *sequence_number += 1;
 
-        let termination_bit = if is_only_zero {
-            self.talking = false;
-            0
-        } else {
-            1
-        };
-        let size_pre = (output.len() as i128) | termination_bit << 14; // termination bit
+        // the first silent frame after talking ends the transmission
+        self.talking = !is_only_zero;
+        let terminator = if is_only_zero { TERMINATOR_BIT } else { 0 };
+        let size_pre = (output.len() as i128) | terminator;
 
         if *sequence_number > MAXIMUM_SAMPLES_PER_TALK {
             *sequence_number = 0;
@@ -94,4 +159,87 @@ impl Encoder for UDPEncoder {
 
         Some(audio_buffer)
     }
+
+    fn set_max_bandwidth(&mut self, max_bandwidth: u32) {
+        self.opus.set_max_bandwidth(max_bandwidth);
+    }
+
+    fn set_target(&mut self, target: u8, sequence_number: &mut u64) -> Option<Vec<u8>> {
+        if self.target == target {
+            return None;
+        }
+        trace!("Switching voice target to {target}");
+        let terminator = if self.talking {
+            let silence = vec![0.0; self.opus.audio_buffer_size];
+            self.encode_audio(&silence, sequence_number)
+        } else {
+            None
+        };
+        self.target = target;
+
+        terminator
+    }
+}
+
+/// Builds `MumbleUDP.Audio` messages, used by servers since 1.5
+#[allow(clippy::module_name_repetitions)]
+pub struct ProtobufEncoder {
+    opus: OpusEncoder,
+    target: u8,
+    talking: bool,
+}
+
+impl ProtobufEncoder {
+    pub fn new(config: DeviceConfig) -> Self {
+        Self {
+            opus: OpusEncoder::new(config),
+            target: CURRENT_CHANNEL_TARGET,
+            talking: false,
+        }
+    }
+}
+
+impl Encoder for ProtobufEncoder {
+    fn encode_audio(&mut self, data: &[f32], sequence_number: &mut u64) -> Option<Vec<u8>> {
+        let is_only_zero = is_zero(data);
+        if !self.talking && is_only_zero {
+            return None;
+        }
+        // the first silent frame after talking ends the transmission
+        self.talking = !is_only_zero;
+
+        let audio = udp_proto::Audio {
+            header: Some(udp_proto::audio::Header::Target(u32::from(self.target))),
+            frame_number: *sequence_number,
+            opus_data: self.opus.encode(data),
+            is_terminator: is_only_zero,
+            ..Default::default()
+        };
+        *sequence_number += 1;
+
+        let mut audio_buffer = vec![UDP_AUDIO_MESSAGE];
+        audio_buffer.extend(audio.encode_to_vec());
+
+        Some(audio_buffer)
+    }
+
+    fn set_max_bandwidth(&mut self, max_bandwidth: u32) {
+        self.opus.set_max_bandwidth(max_bandwidth);
+    }
+
+    fn set_target(&mut self, target: u8, sequence_number: &mut u64) -> Option<Vec<u8>> {
+        if self.target == target {
+            return None;
+        }
+        trace!("Switching voice target to {target}");
+        let terminator = if self.talking {
+            let silence = vec![0.0; self.opus.audio_buffer_size];
+            self.encode_audio(&silence, sequence_number)
+        } else {
+            None
+        };
+        self.target = target;
+
+        terminator
+    }
 }
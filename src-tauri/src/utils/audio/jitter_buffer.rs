@@ -0,0 +1,214 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+// packets buffered before playback starts, every network stall adds one more
+const MIN_DELAY: usize = 2;
+const MAX_DELAY: usize = 10;
+// frames played without an underrun before the delay shrinks again
+const SHRINK_AFTER_FRAMES: u32 = 500;
+// frames concealed in a row before an empty buffer counts as underrun
+const MAX_CONCEALED_FRAMES: u32 = 5;
+// packets further behind are a restarted sequence instead of a late packet
+const RESYNC_DISTANCE: u64 = 100;
+// frames after an underrun, in which the sequence continuing means the network stalled
+const STALL_FRAMES: u64 = 20;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct JitterStats {
+    pub good: u32,
+    /// Packets which arrived after their frame was already played
+    pub late: u32,
+    /// Frames which had to be recovered or concealed
+    pub lost: u32,
+    pub resync: u32,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Frame {
+    Packet(Vec<u8>),
+    /// The packet is missing, but the following packet contains forward error correction data
+    Recovered(Vec<u8>),
+    /// The packet is missing, the decoder has to guess the frame
+    Concealed,
+}
+
+/// Reorders the packets of a single user by their sequence number
+///
+/// Playback starts once a few packets are buffered, the amount grows whenever the stream
+/// continues after an underrun and shrinks again while the connection is stable. Missing packets are returned as recovered or
+/// concealed frames, so the decoder keeps its timing.
+pub struct JitterBuffer {
+    packets: BTreeMap<u64, Vec<u8>>,
+    // sequence number of the next frame, `None` while buffering
+    next_sequence: Option<u64>,
+    // some clients count packets, others count 10ms frames
+    step: Option<u64>,
+    // maps the sequence numbers of the sender after it restarted counting
+    offset: u64,
+    end_sequence: Option<u64>,
+    last_played: Option<u64>,
+    // sequence number of the sender and step of the last packet played before an underrun
+    stalled: Option<(u64, u64)>,
+    delay: usize,
+    concealed_frames: u32,
+    stable_frames: u32,
+    stats: JitterStats,
+}
+
+impl JitterBuffer {
+    pub const fn new() -> Self {
+        Self {
+            packets: BTreeMap::new(),
+            next_sequence: None,
+            step: None,
+            offset: 0,
+            end_sequence: None,
+            last_played: None,
+            stalled: None,
+            delay: MIN_DELAY,
+            concealed_frames: 0,
+            stable_frames: 0,
+            stats: JitterStats {
+                good: 0,
+                late: 0,
+                lost: 0,
+                resync: 0,
+            },
+        }
+    }
+
+    pub const fn stats(&self) -> JitterStats {
+        self.stats
+    }
+
+    pub const fn delay(&self) -> usize {
+        self.delay
+    }
+
+    fn step(&self) -> u64 {
+        self.step.unwrap_or(1)
+    }
+
+    /// Adds a packet, `talking` is false for the last packet of a transmission
+    pub fn push(&mut self, sequence: u64, talking: bool, payload: Vec<u8>) {
+        if let Some((last, step)) = self.stalled.take() {
+            // a new transmission starts anywhere, only a stalled one goes on where it stopped
+            let stall_end = last.saturating_add(step.saturating_mul(STALL_FRAMES));
+            if sequence > last && sequence <= stall_end {
+                self.delay = (self.delay + 1).min(MAX_DELAY);
+            }
+        }
+        let mut key = sequence.saturating_add(self.offset);
+
+        let expected = self
+            .packets
+            .last_key_value()
+            .map(|(last, _)| last + self.step())
+            .or(self.next_sequence);
+        if let Some(expected) = expected {
+            if key.saturating_add(RESYNC_DISTANCE) < expected {
+                self.offset = expected - sequence;
+                self.stats.resync += 1;
+                key = expected;
+            }
+        }
+
+        if self.next_sequence.is_some_and(|next| key < next) || self.packets.contains_key(&key) {
+            self.stats.late += 1;
+            return;
+        }
+
+        self.update_step(key);
+        if !talking {
+            self.end_sequence = Some(key);
+        }
+        self.packets.insert(key, payload);
+    }
+
+    fn update_step(&mut self, key: u64) {
+        let previous = self.packets.range(..key).next_back().map(|(k, _)| key - k);
+        let following = self.packets.range(key..).next().map(|(k, _)| k - key);
+
+        for gap in [previous, following].into_iter().flatten() {
+            self.step = Some(self.step.map_or(gap, |step| step.min(gap)));
+        }
+    }
+
+    /// Returns the next frame to play, `None` while buffering
+    pub fn pop(&mut self) -> Option<Frame> {
+        let next = match self.next_sequence {
+            Some(next) => next,
+            None if self.packets.len() < self.delay && self.end_sequence.is_none() => return None,
+            None => *self.packets.first_key_value()?.0,
+        };
+        let step = self.step();
+
+        if let Some(payload) = self.packets.remove(&next) {
+            self.stats.good += 1;
+            self.concealed_frames = 0;
+            self.next_sequence = Some(next + step);
+            self.last_played = Some(next);
+            self.update_delay();
+            if self.end_sequence == Some(next) {
+                self.finish();
+            }
+
+            return Some(Frame::Packet(payload));
+        }
+
+        if self.packets.is_empty() {
+            if self.concealed_frames >= MAX_CONCEALED_FRAMES {
+                self.underrun();
+                return None;
+            }
+            self.concealed_frames += 1;
+        }
+
+        self.stats.lost += 1;
+        self.next_sequence = Some(next + step);
+
+        Some(
+            self.packets
+                .get(&(next + step))
+                .cloned()
+                .map_or(Frame::Concealed, Frame::Recovered),
+        )
+    }
+
+    fn update_delay(&mut self) {
+        self.stable_frames += 1;
+        if self.stable_frames >= SHRINK_AFTER_FRAMES {
+            self.stable_frames = 0;
+            self.delay = (self.delay - 1).max(MIN_DELAY);
+        }
+    }
+
+    // either the network stalls or the transmission ended without its last packet, which
+    // is only known once the next packet arrives
+    fn underrun(&mut self) {
+        self.concealed_frames = 0;
+        self.stable_frames = 0;
+        self.stalled = self
+            .last_played
+            .map(|last| (last.saturating_sub(self.offset), self.step()));
+        self.finish();
+    }
+
+    // the next transmission may start with any sequence number, unless it already arrived
+    fn finish(&mut self) {
+        if self.packets.is_empty() {
+            self.step = None;
+            self.offset = 0;
+        }
+        self.next_sequence = None;
+        self.end_sequence = None;
+        self.last_played = None;
+    }
+}
+
+impl Default for JitterBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
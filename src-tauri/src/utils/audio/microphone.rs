@@ -4,16 +4,15 @@ use std::{
 };
 
 use rodio::{
-    cpal::{
-        self,
-        traits::{HostTrait, StreamTrait},
-    },
+    cpal::{self, traits::StreamTrait},
     DeviceTrait,
 };
 use tracing::{error, trace};
 
 use crate::errors::{to_error, AnyError};
 
+use super::device_manager::{find_device, DeviceKind};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct DeviceConfig {
     pub channels: u16,
@@ -28,22 +27,35 @@ struct InputSettings {
 pub struct Microphone {
     input_device: cpal::Device,
     pub device_config: Option<cpal::StreamConfig>,
-    tx: Option<Sender<Vec<f32>>>,
+    tx: Sender<Vec<f32>>,
     device_info: DeviceConfig,
     stream: Option<cpal::Stream>,
     input_settings: Arc<Mutex<InputSettings>>,
 }
 
 impl Microphone {
-    pub fn new(tx: Sender<Vec<f32>>) -> AnyError<Self> {
-        let buffer_size = usize::pow(2, 10);
-        let host = cpal::default_host();
+    /// Uses the input device with the given name, or the default device
+    pub fn new(tx: Sender<Vec<f32>>, device: Option<&str>) -> AnyError<Self> {
+        let (input_device, config, device_info) = Self::open(device)?;
+
+        let decibel_adjustment = 15.0;
+        Ok(Self {
+            input_device,
+            device_config: Some(config),
+            device_info,
+            tx,
+            stream: None,
+            input_settings: Arc::new(Mutex::new(InputSettings {
+                volume_adjustment: f32::powf(10.0, decibel_adjustment / 20.0),
+            })),
+        })
+    }
 
-        let input_device = host
-            .default_input_device()
-            .ok_or("Failed to get default input device")?;
+    fn open(device: Option<&str>) -> AnyError<(cpal::Device, cpal::StreamConfig, DeviceConfig)> {
+        let buffer_size = usize::pow(2, 10);
+        let input_device = find_device(DeviceKind::Input, device)?;
 
-        trace!("Default input device: {:?}", input_device.name());
+        trace!("Input device: {:?}", input_device.name());
 
         let device_config = input_device
             .supported_input_configs()?
@@ -65,17 +77,24 @@ impl Microphone {
             sample_rate: config.sample_rate.0,
         };
 
-        let decibel_adjustment = 15.0;
-        Ok(Self {
-            input_device,
-            device_config: Some(config),
-            device_info,
-            tx: Some(tx),
-            stream: None,
-            input_settings: Arc::new(Mutex::new(InputSettings {
-                volume_adjustment: f32::powf(10.0, decibel_adjustment / 20.0),
-            })),
-        })
+        Ok((input_device, config, device_info))
+    }
+
+    /// Restarts the microphone with another device, the input settings are kept
+    pub fn switch_device(&mut self, device: Option<&str>) -> AnyError<()> {
+        let (input_device, config, device_info) = Self::open(device)?;
+        let was_running = self.stream.is_some();
+        self.stop()?;
+
+        self.input_device = input_device;
+        self.device_config = Some(config);
+        self.device_info = device_info;
+
+        if was_running {
+            self.start()?;
+        }
+
+        Ok(())
     }
 
     pub fn volume_adjustment(&self, adjustment: f32) -> AnyError<()> {
@@ -92,12 +111,12 @@ impl Microphone {
     }
 
     pub fn start(&mut self) -> AnyError<()> {
-        if self.tx.is_none() || self.device_config.is_none() {
+        if self.stream.is_some() || self.device_config.is_none() {
             error!("Audio thread already started");
             return Err("Audio thread already started".into());
         }
 
-        let tx = self.tx.take().ok_or("Failed to get audio queue")?;
+        let tx = self.tx.clone();
 
         let audio_settigns = self.input_settings.clone();
         let callback = move |data: &[f32], _: &cpal::InputCallbackInfo| {
@@ -1,7 +1,10 @@
+pub mod cue;
 pub mod decoder;
 pub mod device_manager;
 pub mod encoder;
+pub mod jitter_buffer;
 pub mod microphone;
 pub mod player;
+pub mod positional;
 pub mod processing;
 pub mod recorder;
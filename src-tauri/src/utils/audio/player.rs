@@ -6,28 +6,65 @@ use std::{
         Arc,
     },
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
-use rodio::{OutputStreamHandle, Sink};
-use tokio::sync::broadcast;
-use tracing::{error, trace};
+use rodio::{OutputStream, OutputStreamHandle, Sink};
+use serde::Serialize;
+use tokio::sync::broadcast::{self, Sender};
+use tracing::{error, info, trace};
 
-use crate::{commands::utils::settings::GlobalSettings, errors::AnyError};
+use crate::{
+    commands::utils::settings::GlobalSettings, errors::AnyError,
+    protocol::serialize::message_container::FrontendMessage, utils::frontend::send_to_frontend,
+};
+
+use super::{
+    decoder::DecodedMessage,
+    device_manager::{open_output_stream, DeviceKind, DeviceSelection},
+    jitter_buffer::{Frame, JitterBuffer, JitterStats},
+    positional::{spatialize, stereo_gains, ListenerPose, Position},
+};
 
-use super::decoder::DecodedMessage;
+const SAMPLE_RATE: u32 = 48_000;
+// = SampleRate * 60ms, the longest frame opus supports
+const MAX_FRAME_SIZE: usize = 2880;
+// = SampleRate * 20ms
+const DEFAULT_FRAME_SIZE: usize = 960;
+// frames handed to rodio ahead of time, everything else waits in the jitter buffer
+const QUEUED_FRAMES: usize = 2;
+const STATS_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Serialize)]
+struct UserJitterStats {
+    user_id: u32,
+    // packets buffered before playback starts
+    delay: usize,
+    #[serde(flatten)]
+    stats: JitterStats,
+}
+
+enum PlayerMessage {
+    Audio(DecodedMessage),
+    /// The user left, so the decoder and the buffered audio are no longer needed
+    UserRemoved(u32),
+}
 
 pub struct Player {
     audio_thread: Option<thread::JoinHandle<()>>,
-    queue_rx: Option<Receiver<DecodedMessage>>,
-    queue_tx: SyncSender<DecodedMessage>,
+    queue_rx: Option<Receiver<PlayerMessage>>,
+    queue_tx: SyncSender<PlayerMessage>,
     playing: Arc<AtomicBool>,
     settings_channel: Option<broadcast::Receiver<GlobalSettings>>,
+    frontend_channel: Sender<String>,
 }
 
 impl Player {
-    pub fn new(settings_channel: broadcast::Receiver<GlobalSettings>) -> Self {
-        let (tx, rx) = mpsc::sync_channel(4);
+    pub fn new(
+        settings_channel: broadcast::Receiver<GlobalSettings>,
+        frontend_channel: Sender<String>,
+    ) -> Self {
+        let (tx, rx) = mpsc::sync_channel(16);
 
         Self {
             audio_thread: None,
@@ -35,6 +72,7 @@ impl Player {
             queue_tx: tx,
             playing: Arc::new(AtomicBool::new(false)),
             settings_channel: Some(settings_channel),
+            frontend_channel,
         }
     }
 
@@ -45,6 +83,7 @@ impl Player {
 
         let audio_queue_ref = self.queue_rx.take().ok_or("failed to get audio queue")?;
         let playing_clone = self.playing.clone();
+        let frontend_channel = self.frontend_channel.clone();
 
         let mut settings_channel = self
             .settings_channel
@@ -53,7 +92,7 @@ impl Player {
         self.audio_thread = Some(thread::spawn(move || {
             trace!("Starting audio thread");
 
-            let (_stream, handle) = match rodio::OutputStream::try_default() {
+            let output = match open_output_stream(None) {
                 Ok(s) => s,
                 Err(e) => {
                     error!("Failed to create audio stream: {}", e);
@@ -61,25 +100,35 @@ impl Player {
                 }
             };
 
-            let mut user_audio_info_map = UserAudioInfoMap::new(handle);
+            let mut user_audio_info_map = UserAudioInfoMap::new(output);
+            let mut output_selection = DeviceSelection::new(DeviceKind::Output);
+            let mut last_stats = Instant::now();
 
             while playing_clone.load(Ordering::Relaxed) {
-                Self::update_settings(&mut settings_channel, &mut user_audio_info_map);
-                if let Ok(mut queue_value) =
-                    audio_queue_ref.recv_timeout(Duration::from_millis(100))
-                {
-                    if let Ok(user_info) = user_audio_info_map.get_audio_info(queue_value.user_id) {
-                        Self::adjust_volume_vec(&mut queue_value.data, user_info.volume_adjustment);
-
-                        user_info
-                            .sink
-                            .append(rodio::buffer::SamplesBuffer::<i16>::new(
-                                1,
-                                48000,
-                                queue_value.data,
-                            ));
+                Self::update_settings(
+                    &mut settings_channel,
+                    &mut user_audio_info_map,
+                    &mut output_selection,
+                );
+                if output_selection.poll() {
+                    match open_output_stream(output_selection.active()) {
+                        Ok(output) => user_audio_info_map.set_output(output),
+                        Err(e) => error!("Failed to switch output device: {}", e),
+                    }
+                }
+                // short timeout, the sinks have to be refilled before they run empty
+                if let Ok(queue_value) = audio_queue_ref.recv_timeout(Duration::from_millis(5)) {
+                    user_audio_info_map.handle(queue_value);
+                    while let Ok(queue_value) = audio_queue_ref.try_recv() {
+                        user_audio_info_map.handle(queue_value);
                     }
                 }
+                user_audio_info_map.play();
+
+                if last_stats.elapsed() >= STATS_INTERVAL {
+                    user_audio_info_map.send_stats(&frontend_channel);
+                    last_stats = Instant::now();
+                }
             }
         }));
 
@@ -89,7 +138,16 @@ impl Player {
     pub fn add_to_queue(&mut self, data: DecodedMessage) -> AnyError<()> {
         if self.playing.load(Ordering::Relaxed) {
             //todo add user id to audio data
-            self.queue_tx.try_send(data)?;
+            self.queue_tx.try_send(PlayerMessage::Audio(data))?;
+        }
+
+        Ok(())
+    }
+
+    pub fn remove_user(&self, user_id: u32) -> AnyError<()> {
+        if self.playing.load(Ordering::Relaxed) {
+            // unlike audio this must not get lost, the queue is emptied quickly
+            self.queue_tx.send(PlayerMessage::UserRemoved(user_id))?;
         }
 
         Ok(())
@@ -110,13 +168,30 @@ impl Player {
     fn update_settings(
         settings_channel: &mut broadcast::Receiver<GlobalSettings>,
         audio_map: &mut UserAudioInfoMap,
+        output_selection: &mut DeviceSelection,
     ) {
-        if let Ok(GlobalSettings::AudioOutputSettings(audio_settings)) = settings_channel.try_recv()
-        {
-            trace!("Received settings: {:?}", audio_settings);
-            for user_info in audio_settings.voice_adjustment {
-                audio_map.update_user_volume_adjustment(user_info.user_id, user_info.volume);
+        match settings_channel.try_recv() {
+            Ok(GlobalSettings::AudioOutputSettings(audio_settings)) => {
+                trace!("Received settings: {:?}", audio_settings);
+                for user_info in audio_settings.voice_adjustment {
+                    audio_map.update_user_volume_adjustment(user_info.user_id, user_info.volume);
+                }
+                if let Some(positional_audio) = audio_settings.positional_audio {
+                    audio_map.positional_audio = positional_audio;
+                }
+            }
+            Ok(GlobalSettings::ListenerPose(pose)) => {
+                trace!("Received listener pose: {:?}", pose);
+                audio_map.listener = Some(pose);
+            }
+            Ok(GlobalSettings::OutputDevice(device)) => {
+                info!("Received output device: {:?}", device);
+                output_selection.select(device);
             }
+            Ok(GlobalSettings::AvailableDevices(devices)) => {
+                output_selection.update_available(devices);
+            }
+            _ => {}
         }
     }
 
@@ -133,6 +208,19 @@ impl Player {
             *sample = (*sample as f32 * linear_adjustment).round() as i16;
         }
     }
+
+    // we need the cast from f32 to i16
+    #[allow(clippy::cast_possible_truncation)]
+    #[allow(clippy::cast_lossless)]
+    fn scale_volume_vec(audio_data: &mut [i16], factor: f32) {
+        if (factor - 1.0).abs() < std::f32::EPSILON {
+            return;
+        }
+
+        for sample in audio_data.iter_mut() {
+            *sample = (*sample as f32 * factor).round() as i16;
+        }
+    }
 }
 
 impl Drop for Player {
@@ -144,39 +232,187 @@ impl Drop for Player {
 struct UserAudioInfo {
     sink: Sink,
     volume_adjustment: f32,
+    listener_volume: f32,
+    position: Option<Position>,
+    jitter_buffer: JitterBuffer,
+    // opus keeps state between frames, so every user needs their own decoder
+    decoder: opus::Decoder,
+    frame_size: usize,
+    reported_stats: JitterStats,
+}
+
+impl UserAudioInfo {
+    fn decode(&mut self, frame: Frame) -> AnyError<Vec<i16>> {
+        let mut data = vec![0; MAX_FRAME_SIZE];
+        let samples = match frame {
+            Frame::Packet(payload) => {
+                let samples = self.decoder.decode(&payload, &mut data, false)?;
+                self.frame_size = samples;
+                samples
+            }
+            // a missing frame is assumed to be as long as the previous one
+            Frame::Recovered(payload) => {
+                self.decoder
+                    .decode(&payload, &mut data[..self.frame_size], true)?
+            }
+            Frame::Concealed => self
+                .decoder
+                .decode(&[], &mut data[..self.frame_size], false)?,
+        };
+        data.truncate(samples);
+
+        Ok(data)
+    }
 }
 
 struct UserAudioInfoMap {
-    handle: OutputStreamHandle,
+    // the stream stops playing once it is dropped
+    output: (OutputStream, OutputStreamHandle),
     sink_map: BTreeMap<u32, UserAudioInfo>,
+    positional_audio: bool,
+    // voices stay unpanned until an integration reports where we are
+    listener: Option<ListenerPose>,
 }
 
 impl UserAudioInfoMap {
-    fn new(handle: OutputStreamHandle) -> Self {
+    fn new(output: (OutputStream, OutputStreamHandle)) -> Self {
         Self {
-            handle,
+            output,
             sink_map: BTreeMap::new(),
+            positional_audio: false,
+            listener: None,
         }
     }
 
-    fn get_audio_info(&mut self, user_id: u32) -> Result<&UserAudioInfo, String> {
+    /// Moves playback to another stream, the volume adjustments are kept
+    fn set_output(&mut self, output: (OutputStream, OutputStreamHandle)) {
+        self.output = output;
+        self.sink_map.retain(
+            |user_id, user_info| match Self::create_sink(&self.output.1) {
+                Ok(sink) => {
+                    user_info.sink = sink;
+                    true
+                }
+                Err(e) => {
+                    error!("Failed to move user {} to new output: {}", user_id, e);
+                    false
+                }
+            },
+        );
+    }
+
+    fn get_audio_info(&mut self, user_id: u32) -> Result<&mut UserAudioInfo, String> {
         let result = match self.sink_map.entry(user_id) {
             Entry::Occupied(entry) => entry.into_mut(),
             Entry::Vacant(entry) => entry.insert(UserAudioInfo {
-                sink: Self::create_sink(&self.handle)?,
+                sink: Self::create_sink(&self.output.1)?,
                 volume_adjustment: 1.0,
+                listener_volume: 1.0,
+                position: None,
+                jitter_buffer: JitterBuffer::new(),
+                decoder: Self::create_decoder()?,
+                frame_size: DEFAULT_FRAME_SIZE,
+                reported_stats: JitterStats::default(),
             }),
         };
 
         Ok(result)
     }
 
+    fn handle(&mut self, message: PlayerMessage) {
+        match message {
+            PlayerMessage::Audio(message) => self.push(message),
+            PlayerMessage::UserRemoved(user_id) => {
+                if self.sink_map.remove(&user_id).is_some() {
+                    trace!("Removed audio of user {}", user_id);
+                }
+            }
+        }
+    }
+
+    fn push(&mut self, message: DecodedMessage) {
+        match self.get_audio_info(message.user_id) {
+            Ok(user_info) => {
+                user_info.listener_volume = message.listener_volume;
+                user_info.position = message.position;
+                user_info.jitter_buffer.push(
+                    message.sequence_number,
+                    message.talking,
+                    message.payload,
+                );
+            }
+            Err(e) => error!("Failed to buffer audio of user {}: {}", message.user_id, e),
+        }
+    }
+
+    /// Moves due frames from the jitter buffers to the sinks
+    fn play(&mut self) {
+        for (user_id, user_info) in &mut self.sink_map {
+            while user_info.sink.len() < QUEUED_FRAMES {
+                let Some(frame) = user_info.jitter_buffer.pop() else {
+                    break;
+                };
+
+                match user_info.decode(frame) {
+                    Ok(mut data) => {
+                        Player::adjust_volume_vec(&mut data, user_info.volume_adjustment);
+                        Player::scale_volume_vec(&mut data, user_info.listener_volume);
+
+                        let samples = match (user_info.position, self.listener) {
+                            (Some(position), Some(listener)) if self.positional_audio => {
+                                let gains = stereo_gains(&position, &listener);
+                                rodio::buffer::SamplesBuffer::<i16>::new(
+                                    2,
+                                    SAMPLE_RATE,
+                                    spatialize(&data, gains),
+                                )
+                            }
+                            _ => rodio::buffer::SamplesBuffer::<i16>::new(1, SAMPLE_RATE, data),
+                        };
+                        user_info.sink.append(samples);
+                    }
+                    Err(e) => error!("Failed to decode audio of user {}: {}", user_id, e),
+                }
+            }
+        }
+    }
+
+    fn send_stats(&mut self, frontend_channel: &Sender<String>) {
+        for (user_id, user_info) in &mut self.sink_map {
+            let stats = user_info.jitter_buffer.stats();
+            if stats == user_info.reported_stats {
+                continue;
+            }
+
+            trace!("Jitter buffer stats of user {}: {:?}", user_id, stats);
+            send_to_frontend(
+                frontend_channel,
+                &FrontendMessage::new(
+                    "jitter_stats",
+                    UserJitterStats {
+                        user_id: *user_id,
+                        delay: user_info.jitter_buffer.delay(),
+                        stats,
+                    },
+                ),
+            );
+            user_info.reported_stats = stats;
+        }
+    }
+
     fn update_user_volume_adjustment(&mut self, user_id: u32, volume_adjustment: f32) {
         if let Some(user_info) = self.sink_map.get_mut(&user_id) {
             user_info.volume_adjustment = volume_adjustment;
         }
     }
 
+    fn create_decoder() -> Result<opus::Decoder, String> {
+        match opus::Decoder::new(SAMPLE_RATE, opus::Channels::Mono) {
+            Ok(d) => Ok(d),
+            Err(e) => Err(format!("Failed to create decoder: {e}")),
+        }
+    }
+
     fn create_sink(handle: &OutputStreamHandle) -> Result<Sink, String> {
         match rodio::Sink::try_new(handle) {
             Ok(s) => Ok(s),
@@ -0,0 +1,167 @@
+use serde::{Deserialize, Serialize};
+
+// full volume up to this distance in meters
+const MIN_DISTANCE: f32 = 1.0;
+// quietest volume from this distance on
+const MAX_DISTANCE: f32 = 15.0;
+const MIN_VOLUME: f32 = 0.1;
+
+/// A point in the coordinate system of mumble, x points to the right, y up and z to the front
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub struct Position {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+impl Position {
+    /// Reads the three floats mumble appends to legacy voice packets
+    ///
+    /// Mumble copies the floats as they are in memory, so they are in the byte order of the
+    /// sender, which is little endian on every platform mumble runs on.
+    pub fn from_le_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut coordinates = bytes
+            .chunks_exact(4)
+            .map(|chunk| chunk.try_into().map(f32::from_le_bytes));
+
+        match (coordinates.next(), coordinates.next(), coordinates.next()) {
+            (Some(Ok(x)), Some(Ok(y)), Some(Ok(z))) => Some(Self { x, y, z }),
+            _ => None,
+        }
+    }
+
+    pub fn distance(&self, other: &Self) -> f32 {
+        self.minus(other).length()
+    }
+
+    fn minus(&self, other: &Self) -> Self {
+        Self {
+            x: self.x - other.x,
+            y: self.y - other.y,
+            z: self.z - other.z,
+        }
+    }
+
+    fn scaled(&self, factor: f32) -> Self {
+        Self {
+            x: self.x * factor,
+            y: self.y * factor,
+            z: self.z * factor,
+        }
+    }
+
+    fn dot(&self, other: &Self) -> f32 {
+        self.z
+            .mul_add(other.z, self.x.mul_add(other.x, self.y * other.y))
+    }
+
+    fn cross(&self, other: &Self) -> Self {
+        Self {
+            x: self.y.mul_add(other.z, -self.z * other.y),
+            y: self.z.mul_add(other.x, -self.x * other.z),
+            z: self.x.mul_add(other.y, -self.y * other.x),
+        }
+    }
+
+    fn length(&self) -> f32 {
+        self.x.hypot(self.y).hypot(self.z)
+    }
+
+    fn normalized(&self) -> Option<Self> {
+        let length = self.length();
+        (length > f32::EPSILON).then(|| self.scaled(1.0 / length))
+    }
+}
+
+const fn default_front() -> Position {
+    Position {
+        x: 0.0,
+        y: 0.0,
+        z: 1.0,
+    }
+}
+
+const fn default_top() -> Position {
+    Position {
+        x: 0.0,
+        y: 1.0,
+        z: 0.0,
+    }
+}
+
+/// Where the local user is and where they look, the same as a game reports it to mumble
+///
+/// Nothing in the client knows this on its own, an integration like a game plugin has to set it.
+/// `front` and `top` are directions, they don't have to be normalized.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub struct ListenerPose {
+    pub position: Position,
+    #[serde(default = "default_front")]
+    pub front: Position,
+    #[serde(default = "default_top")]
+    pub top: Position,
+}
+
+impl Default for ListenerPose {
+    fn default() -> Self {
+        Self {
+            position: Position::default(),
+            front: default_front(),
+            top: default_top(),
+        }
+    }
+}
+
+impl ListenerPose {
+    /// Unit vectors pointing up and to the right of the listener, invalid directions fall back
+    /// to looking along the z axis
+    fn axes(&self) -> (Position, Position) {
+        let top = self.top.normalized().unwrap_or_else(default_top);
+        let front = self.front.normalized().unwrap_or_else(default_front);
+
+        match top.cross(&front).normalized() {
+            Some(right) => (top, right),
+            None => (default_top(), default_top().cross(&default_front())),
+        }
+    }
+}
+
+/// Volume of the left and right channel for a source heard by the listener
+pub fn stereo_gains(source: &Position, listener: &ListenerPose) -> (f32, f32) {
+    let distance = source.distance(&listener.position);
+    let attenuation = if distance <= MIN_DISTANCE {
+        1.0
+    } else {
+        let progress = ((distance - MIN_DISTANCE) / (MAX_DISTANCE - MIN_DISTANCE)).min(1.0);
+        (1.0 - MIN_VOLUME).mul_add(-progress, 1.0)
+    };
+
+    // -1 is fully left, 1 is fully right, the height above the listener doesn't matter for panning
+    let (top, right) = listener.axes();
+    let relative = source.minus(&listener.position);
+    let horizontal = relative.minus(&top.scaled(relative.dot(&top)));
+    let pan = horizontal
+        .normalized()
+        .map_or(0.0, |direction| direction.dot(&right));
+
+    (
+        (1.0 - pan).min(1.0) * attenuation,
+        (1.0 + pan).min(1.0) * attenuation,
+    )
+}
+
+/// Turns mono samples into interleaved stereo samples
+// we need the cast from f32 to i16
+#[allow(clippy::cast_possible_truncation)]
+pub fn spatialize(samples: &[i16], (left, right): (f32, f32)) -> Vec<i16> {
+    samples
+        .iter()
+        .flat_map(|sample| {
+            let sample = f32::from(*sample);
+            [
+                (sample * left).round() as i16,
+                (sample * right).round() as i16,
+            ]
+        })
+        .collect()
+}
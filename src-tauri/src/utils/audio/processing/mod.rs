@@ -1,3 +1,6 @@
 pub mod compress;
 pub mod hysteresis;
+pub mod noise_suppression;
+pub mod push_to_talk;
+pub mod resampler;
 pub mod voice_activation;
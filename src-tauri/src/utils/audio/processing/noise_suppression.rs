@@ -0,0 +1,69 @@
+use std::collections::VecDeque;
+
+use nnnoiseless::DenoiseState;
+
+// RNNoise works on 16 bit samples stored as floats
+const SAMPLE_SCALE: f32 = 32_767.0;
+/// Number of samples the output lags behind the input
+pub const DELAY: usize = DenoiseState::FRAME_SIZE;
+
+/// Converts a sample in `-1.0..=1.0` to the range RNNoise expects
+pub fn to_model_scale(sample: f32) -> f32 {
+    sample * SAMPLE_SCALE
+}
+
+pub fn from_model_scale(sample: f32) -> f32 {
+    sample / SAMPLE_SCALE
+}
+
+/// Removes background noise with RNNoise, expects mono audio with 48kHz
+///
+/// The model works on fixed frames, so the output lags one frame behind the input.
+pub struct NoiseSuppression {
+    state: Box<DenoiseState<'static>>,
+    input: VecDeque<f32>,
+    output: VecDeque<f32>,
+    input_frame: Vec<f32>,
+    output_frame: Vec<f32>,
+}
+
+impl NoiseSuppression {
+    pub fn new() -> Self {
+        Self {
+            state: DenoiseState::new(),
+            input: VecDeque::with_capacity(DenoiseState::FRAME_SIZE * 2),
+            // the first frame of the model is garbage, so it is replaced by silence
+            output: VecDeque::from(vec![0.0; DELAY]),
+            input_frame: vec![0.0; DenoiseState::FRAME_SIZE],
+            output_frame: vec![0.0; DenoiseState::FRAME_SIZE],
+        }
+    }
+
+    pub fn process(&mut self, data: &mut [f32]) {
+        self.input.extend(data.iter().copied().map(to_model_scale));
+
+        while self.input.len() >= DenoiseState::FRAME_SIZE {
+            for (target, sample) in self
+                .input_frame
+                .iter_mut()
+                .zip(self.input.drain(..DenoiseState::FRAME_SIZE))
+            {
+                *target = sample;
+            }
+            self.state
+                .process_frame(&mut self.output_frame, &self.input_frame);
+            self.output
+                .extend(self.output_frame.iter().copied().map(from_model_scale));
+        }
+
+        for sample in data.iter_mut() {
+            *sample = self.output.pop_front().unwrap_or_default();
+        }
+    }
+}
+
+impl Default for NoiseSuppression {
+    fn default() -> Self {
+        Self::new()
+    }
+}
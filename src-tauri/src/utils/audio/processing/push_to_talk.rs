@@ -0,0 +1,74 @@
+use std::time::{Duration, Instant};
+
+use device_query::Keycode;
+
+use crate::utils::shortcut::Shortcut;
+
+/// Gates the microphone with a held key, or toggles it with every key press
+pub struct PushToTalk {
+    shortcut: Option<Shortcut>,
+    toggle: bool,
+    release_delay: Duration,
+    audio_cue: bool,
+    transmitting: bool,
+    was_held: bool,
+    released_at: Option<Instant>,
+}
+
+impl PushToTalk {
+    /// Without a shortcut the microphone stays muted
+    pub const fn new(
+        shortcut: Option<Shortcut>,
+        toggle: bool,
+        release_delay: Duration,
+        audio_cue: bool,
+    ) -> Self {
+        Self {
+            shortcut,
+            toggle,
+            release_delay,
+            audio_cue,
+            transmitting: false,
+            was_held: false,
+            released_at: None,
+        }
+    }
+
+    /// Updates the state from the pressed keys, returns the new state if it changed
+    pub fn update(&mut self, pressed: &[Keycode], now: Instant) -> Option<bool> {
+        let held = self
+            .shortcut
+            .as_ref()
+            .map_or(false, |shortcut| shortcut.is_held(pressed));
+        let pressed_now = held && !self.was_held;
+        self.was_held = held;
+        let was_transmitting = self.transmitting;
+
+        if self.toggle {
+            if pressed_now {
+                self.transmitting = !self.transmitting;
+            }
+        } else if held {
+            self.transmitting = true;
+            self.released_at = None;
+        } else if self.transmitting {
+            let released_at = *self.released_at.get_or_insert(now);
+            if now.duration_since(released_at) >= self.release_delay {
+                self.transmitting = false;
+                self.released_at = None;
+            }
+        }
+
+        (was_transmitting != self.transmitting).then_some(self.transmitting)
+    }
+
+    pub const fn audio_cue(&self) -> bool {
+        self.audio_cue
+    }
+
+    pub fn process(&self, data: &mut [f32]) {
+        if !self.transmitting {
+            data.fill(0.0);
+        }
+    }
+}
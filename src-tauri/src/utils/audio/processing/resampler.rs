@@ -1,119 +1,113 @@
-use symphonia::core::audio::{AudioBufferRef, Signal};
-use symphonia::core::codecs::CodecParameters;
-use symphonia::core::formats::FormatOptions;
-use symphonia::core::io::MediaSourceStream;
-use symphonia::core::meta::MetadataOptions;
-use symphonia::core::probe::Hint;
-
-use crate::errors::AnyError;
-
-struct Resampler {
-    target_sampling_rate: u32,
-    target_bit_depth: u32,
+use std::f64::consts::PI;
+
+// zero crossings of the sinc on each side, more taps give a steeper low pass
+const KERNEL_ZERO_CROSSINGS: f64 = 16.0;
+// keeps the transition band of the low pass below the new nyquist frequency
+const CUTOFF_MARGIN: f64 = 0.95;
+
+/// Converts interleaved audio of any rate and channel count to mono audio with the target rate
+///
+/// Uses a windowed sinc interpolation, which also filters everything above the target nyquist
+/// frequency. Output samples are only produced once enough input after them is buffered.
+pub struct Resampler {
+    channels: usize,
+    // input samples per output sample
+    step: f64,
+    cutoff: f64,
+    half_width: usize,
+    history: Vec<f32>,
+    position: f64,
 }
 
 impl Resampler {
-    fn new(target_sampling_rate: u32, target_bit_depth: u32) -> Resampler {
-        Resampler {
-            target_sampling_rate,
-            target_bit_depth,
+    #[allow(clippy::cast_possible_truncation)]
+    #[allow(clippy::cast_sign_loss)]
+    pub fn new(input_rate: u32, output_rate: u32, channels: u16) -> Self {
+        let step = f64::from(input_rate) / f64::from(output_rate.max(1));
+        let cutoff = if step > 1.0 {
+            CUTOFF_MARGIN / step
+        } else {
+            1.0
+        };
+        let half_width = (KERNEL_ZERO_CROSSINGS / cutoff).ceil() as usize;
+
+        Self {
+            channels: usize::from(channels.max(1)),
+            step,
+            cutoff,
+            half_width,
+            history: vec![0.0; half_width],
+            position: 0.0,
         }
     }
 
-    fn resample(&self, samples: &mut [f32]) -> AnyError<()> {
-        /*// Define the input and output byte slices
-        let mut output_bytes: Vec<u8> = Vec::new(); // create an empty vector for the output bytes
-
-        // Create a hint to help the format detector
-        let mut hint = Hint::new();
-
-        // Set the file extension
-        hint.with_extension("wav");
-
-        // Create a media source from the input byte slice
-        let mss = MediaSourceStream::new(Box::new(samples), Default::default());
-
-        // Use the default options for metadata and format
-        let metadata_opts = MetadataOptions::default();
-        let format_opts = FormatOptions::default();
-
-        // Probe the media source for the format
-        let format =
-            symphonia::default::get_probe().format(&hint, mss, &format_opts, &metadata_opts)?;
-
-        // Get the default stream from the format
-        let stream = format.default_stream()?;
-
-        // Get the codec parameters from the stream
-        let codec_params = stream.codec_params();
-
-        // Get the original sampling rate and bit depth from the codec parameters
-        let original_sampling_rate = codec_params.sample_rate.unwrap_or(0);
-        let original_bit_depth = codec_params.bits_per_sample.unwrap_or(0);
-
-        // Check if the input byte slice is already PCM encoded
-        if codec_params.codec != symphonia::core::codecs::CODEC_TYPE_PCM_F32BE {
-            return Err("The input byte slice is not PCM encoded".into());
-        }
+    fn is_passthrough(&self) -> bool {
+        (self.step - 1.0).abs() < f64::EPSILON
+    }
 
-        // Check if the input byte slice has a higher bitrate than the target bitrate
-        if original_sampling_rate * original_bit_depth
-            <= self.target_sampling_rate * self.target_bit_depth
-        {
-            return Err(
-                "The input byte slice already has a lower or equal bitrate than the target bitrate"
-                    .into(),
-            );
+    pub fn process(&mut self, input: &[f32]) -> Vec<f32> {
+        if self.is_passthrough() {
+            return downmix(input, self.channels).collect();
         }
 
-        // Create a new codec parameters for the output byte slice
-        let mut output_codec_params = CodecParameters::new();
+        self.history.extend(downmix(input, self.channels));
 
-        // Set the codec to PCM
-        output_codec_params.with_codec(symphonia::core::codecs::CODEC_TYPE_PCM_F32BE);
-
-        // Set the target sampling rate and bit depth
-        output_codec_params.with_sample_rate(self.target_sampling_rate);
-        output_codec_params.with_bits_per_sample(self.target_bit_depth);
-
-        // Set the number of channels and channel layout to match the input byte slice
-        output_codec_params.with_channels(codec_params.channels);
-        output_codec_params.with_channel_layout(codec_params.channel_layout);
-
-        // Create a resampler to convert the input sampling rate to the target sampling rate
-        let resampler = symphonia::core::audio::resample::Converter::new(
-            codec_params.channels,
-            original_sampling_rate,
-            TARGET_SAMPLING_RATE,
-        );
-
-        // Create a quantizer to convert the input bit depth to the target bit depth
-        let quantizer = symphonia::core::audio::quantize::ScalarQuantizer::new(
-            codec_params.channels,
-            original_bit_depth,
-            TARGET_BIT_DEPTH,
-        );
+        let mut output = Vec::new();
+        // the position is relative to the first sample after the initial padding
+        while self.position.floor() as usize + 2 * self.half_width < self.history.len() {
+            output.push(self.interpolate(self.position + self.half_width as f64));
+            self.position += self.step;
+        }
 
-        // Loop through the packets in the input byte slice
-        while let Ok(packet) = format.next_packet() {
-            // Decode the packet into an audio buffer
-            let decoded = format.decode(&packet)?;
+        // drop everything, which isn't needed for the next output sample anymore
+        let consumed = (self.position.floor() as usize).min(self.history.len());
+        self.history.drain(..consumed);
+        self.position -= consumed as f64;
 
-            // Resample the audio buffer to the target sampling rate
-            let resampled = resampler.process(decoded);
+        output
+    }
 
-            // Quantize the audio buffer to the target bit depth
-            let quantized = quantizer.process(resampled);
+    #[allow(clippy::cast_possible_truncation)]
+    #[allow(clippy::cast_possible_wrap)]
+    #[allow(clippy::cast_precision_loss)]
+    #[allow(clippy::cast_sign_loss)]
+    fn interpolate(&self, position: f64) -> f32 {
+        let center = position.floor() as isize;
+        let half_width = self.half_width as isize;
+        let first = (center - half_width + 1).max(0) as usize;
+        let last = ((center + half_width) as usize).min(self.history.len() - 1);
+
+        let mut sum = 0.0;
+        for (index, sample) in self.history[first..=last].iter().enumerate() {
+            let distance = position - (first + index) as f64;
+            sum += f64::from(*sample) * self.kernel(distance);
+        }
 
-            // Convert the quantized audio buffer to a byte slice
-            let bytes = quantized.as_bytes();
+        sum as f32
+    }
 
-            // Append the bytes to the output vector
-            output_bytes.extend_from_slice(bytes);
+    #[allow(clippy::cast_precision_loss)]
+    fn kernel(&self, distance: f64) -> f64 {
+        let half_width = self.half_width as f64;
+        if distance.abs() >= half_width {
+            return 0.0;
         }
 
-        // Return the output vector as a byte slice
-        Ok(&output_bytes[..])*/
-        Ok(())
+        let x = self.cutoff * distance;
+        let sinc = if x.abs() < f64::EPSILON {
+            1.0
+        } else {
+            (PI * x).sin() / (PI * x)
+        };
+        let window = 0.5 * (1.0 + (PI * distance / half_width).cos());
+
+        self.cutoff * sinc * window
     }
 }
+
+#[allow(clippy::cast_precision_loss)]
+fn downmix(input: &[f32], channels: usize) -> impl Iterator<Item = f32> + '_ {
+    input
+        .chunks_exact(channels)
+        .map(move |frame| frame.iter().sum::<f32>() / channels as f32)
+}
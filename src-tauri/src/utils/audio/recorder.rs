@@ -1,48 +1,83 @@
 use std::{
+    collections::BTreeMap,
     sync::{
-        atomic::{AtomicBool, Ordering},
+        atomic::{AtomicBool, AtomicU32, Ordering},
         mpsc::{self},
         Arc,
     },
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
-use tokio::sync::broadcast::{self, Receiver};
+use device_query::Keycode;
+use serde::Serialize;
+use tokio::sync::broadcast::{self, Receiver, Sender};
 use tracing::{error, info, trace, warn};
 
 use crate::{
     commands::utils::settings::{AudioOptions, AudioPreviewContainer, GlobalSettings, InputMode},
     errors::AnyError,
-    mumble::proto::UdpTunnel,
+    manager::voice_target::CURRENT_CHANNEL_TARGET,
+    protocol::{serialize::message_container::FrontendMessage, VoiceFormat},
     utils::{
-        audio::{encoder::UDPEncoder, microphone::Microphone, processing::compress::Compressor},
-        messages::raw_message_builder,
+        audio::{microphone::Microphone, processing::compress::Compressor},
+        frontend::send_to_frontend,
+        shortcut::{Keyboard, Shortcut},
     },
 };
 
 use super::{
-    encoder::Encoder,
-    processing::voice_activation::{VoiceActivation, VoiceActivationType},
+    cue::AudioCue,
+    device_manager::{DeviceKind, DeviceSelection},
+    encoder::{create_encoder, Encoder},
+    microphone::DeviceConfig,
+    processing::{
+        noise_suppression::NoiseSuppression,
+        push_to_talk::PushToTalk,
+        resampler::Resampler,
+        voice_activation::{VoiceActivation, VoiceActivationType},
+    },
+};
+
+// everything after the resampler works on 20ms of 48kHz mono audio, as expected by opus
+const SAMPLE_RATE: u32 = 48_000;
+const FRAME_SIZE: usize = 960;
+const ENCODER_CONFIG: DeviceConfig = DeviceConfig {
+    channels: 1,
+    sample_rate: SAMPLE_RATE,
+    buffer_size: FRAME_SIZE,
 };
 
+#[derive(Debug, Clone, Serialize)]
+struct PushToTalkState {
+    transmitting: bool,
+}
+
 pub struct Recorder {
     audio_thread: Option<thread::JoinHandle<()>>,
     playing: Arc<AtomicBool>,
-    server_channel: Option<broadcast::Sender<Vec<u8>>>,
+    // 0 as long as the server didn't limit the bandwidth
+    max_bandwidth: Arc<AtomicU32>,
+    protobuf_voice: Arc<AtomicBool>,
+    voice_channel: Option<broadcast::Sender<Vec<u8>>>,
     settings_channel: Option<broadcast::Receiver<GlobalSettings>>,
+    frontend_channel: Sender<String>,
 }
 
 impl Recorder {
     pub fn new(
-        server_channel: broadcast::Sender<Vec<u8>>,
+        voice_channel: broadcast::Sender<Vec<u8>>,
         settings_channel: broadcast::Receiver<GlobalSettings>,
+        frontend_channel: Sender<String>,
     ) -> Self {
         Self {
             audio_thread: None,
             playing: Arc::new(AtomicBool::new(false)),
-            server_channel: Some(server_channel),
+            max_bandwidth: Arc::new(AtomicU32::new(0)),
+            protobuf_voice: Arc::new(AtomicBool::new(false)),
+            voice_channel: Some(voice_channel),
             settings_channel: Some(settings_channel),
+            frontend_channel,
         }
     }
 
@@ -53,8 +88,11 @@ impl Recorder {
         }
 
         let playing_clone = self.playing.clone();
+        let max_bandwidth = self.max_bandwidth.clone();
+        let protobuf_voice = self.protobuf_voice.clone();
+        let frontend_channel = self.frontend_channel.clone();
         let audio_queue_ref = self
-            .server_channel
+            .voice_channel
             .take()
             .ok_or("failed to get audio queue")
             .expect("failed to get audio queue");
@@ -68,8 +106,11 @@ impl Recorder {
             trace!("Starting audio thread");
 
             let (tx, rx) = mpsc::channel();
-            let mut microphone = Microphone::new(tx).expect("Failed to create microphone");
-            let mut encoder = UDPEncoder::new(microphone.config());
+            let mut microphone = Microphone::new(tx, None).expect("Failed to create microphone");
+            let mut voice_format = VoiceFormat::Legacy;
+            let mut encoder = create_encoder(voice_format, ENCODER_CONFIG);
+            let mut resampler = new_resampler(&microphone);
+            let mut frame_buffer: Vec<f32> = Vec::with_capacity(FRAME_SIZE * 2);
             match microphone.start() {
                 Ok(()) => {}
                 Err(e) => {
@@ -82,19 +123,10 @@ impl Recorder {
             trace!("Playing: {:?}", playing_clone.load(Ordering::Relaxed));
 
             let mut sequence_number = 0u64;
-            let sample_rate = microphone.device_config.as_ref().map_or(0, |config| {
-                config.sample_rate.0.try_into().unwrap_or_default()
-            });
-
-            let mut va: Option<VoiceActivation<f32>> = Some(VoiceActivation::new(
-                sample_rate,
-                Duration::from_millis(100),
-                Duration::from_secs(1),
-                0.6,
-                0.3,
-            ));
+
+            let mut va: Option<VoiceActivation<f32>> = Some(default_voice_activation());
             let mut compressor: Option<Compressor> = Some(Compressor::new(
-                sample_rate,
+                SAMPLE_RATE as usize,
                 0.0,
                 0.0,
                 Duration::from_millis(0),
@@ -102,6 +134,15 @@ impl Recorder {
             ));
 
             let mut audio_preview: Option<AudioPreviewContainer> = None;
+            let mut applied_max_bandwidth = 0;
+            let keyboard = Keyboard::new();
+            let mut voice_targets: BTreeMap<u32, Shortcut> = BTreeMap::new();
+            let mut push_to_talk: Option<PushToTalk> = None;
+            // enabled by default, like in the frontend, until the settings arrive
+            let mut noise_suppression: Option<NoiseSuppression> = Some(NoiseSuppression::new());
+            let mut input_selection = DeviceSelection::new(DeviceKind::Input);
+            let mut output_selection = DeviceSelection::new(DeviceKind::Output);
+            let mut audio_cue = AudioCue::new();
 
             while playing_clone.load(Ordering::Relaxed) {
                 update_settings(
@@ -110,30 +151,108 @@ impl Recorder {
                     &mut compressor,
                     &microphone,
                     &mut audio_preview,
+                    &mut voice_targets,
+                    &mut push_to_talk,
+                    &mut noise_suppression,
+                    &mut input_selection,
+                    &mut output_selection,
                 );
-                let mut max_amplitude = 0.0;
-
-                let mut value = rx.recv().expect("Failed to receive audio data");
-                if let Some(va) = va.as_mut() {
-                    max_amplitude = va.process(&mut value);
+                if input_selection.poll() {
+                    let previous_config = microphone.config();
+                    if let Err(e) = microphone.switch_device(input_selection.active()) {
+                        error!("Failed to switch input device: {}", e);
+                    } else if microphone.config() != previous_config {
+                        resampler = new_resampler(&microphone);
+                        frame_buffer.clear();
+                    }
                 }
-                if let Some(compress) = compressor.as_mut() {
-                    compress.process(&mut value);
+                if output_selection.poll() {
+                    audio_cue.set_device(output_selection.active());
                 }
-
-                if let Some(audio_preview) = audio_preview.as_mut() {
-                    let _ = audio_preview.window.try_lock().map(|window| {
-                        let _ = window.emit("audio_preview", max_amplitude);
-                    });
+                let current_voice_format = if protobuf_voice.load(Ordering::Relaxed) {
+                    VoiceFormat::Protobuf
+                } else {
+                    VoiceFormat::Legacy
+                };
+                if current_voice_format != voice_format {
+                    info!("Switching voice format to {current_voice_format:?}");
+                    encoder = create_encoder(current_voice_format, ENCODER_CONFIG);
+                    voice_format = current_voice_format;
+                    applied_max_bandwidth = 0;
+                }
+                let current_max_bandwidth = max_bandwidth.load(Ordering::Relaxed);
+                if current_max_bandwidth != applied_max_bandwidth {
+                    encoder.set_max_bandwidth(current_max_bandwidth);
+                    applied_max_bandwidth = current_max_bandwidth;
+                }
+                let pressed = if voice_targets.is_empty() && push_to_talk.is_none() {
+                    Vec::new()
+                } else {
+                    keyboard.pressed_keys()
+                };
+                let whisper_target = held_voice_target(&pressed, &voice_targets);
+                // the listeners of the previous target have to know that we stopped talking to them
+                if let Some(terminator) = encoder.set_target(
+                    whisper_target.unwrap_or(CURRENT_CHANNEL_TARGET),
+                    &mut sequence_number,
+                ) {
+                    if let Err(e) = audio_queue_ref.send(terminator) {
+                        warn!("Failed to send audio data: {e}");
+                    }
+                }
+                if let Some(push_to_talk) = push_to_talk.as_mut() {
+                    if let Some(transmitting) = push_to_talk.update(&pressed, Instant::now()) {
+                        trace!("Push to talk transmitting: {transmitting}");
+                        send_to_frontend(
+                            &frontend_channel,
+                            &FrontendMessage::new("push_to_talk", PushToTalkState { transmitting }),
+                        );
+                        if push_to_talk.audio_cue() {
+                            audio_cue.play(transmitting);
+                        }
+                    }
                 }
 
-                let audio_buffer = encoder.encode_audio(&value, &mut sequence_number);
+                let value = match rx.recv_timeout(Duration::from_millis(100)) {
+                    Ok(value) => value,
+                    // keep reacting to settings and device changes while the microphone is silent
+                    Err(mpsc::RecvTimeoutError::Timeout) => continue,
+                    Err(mpsc::RecvTimeoutError::Disconnected) => break,
+                };
+                frame_buffer.extend(resampler.process(&value));
 
-                if let Some(audio_buffer) = audio_buffer {
-                    let result_buffer =
-                        raw_message_builder::<UdpTunnel>(&audio_buffer).unwrap_or_default();
-                    if let Err(e) = audio_queue_ref.send(result_buffer) {
-                        warn!("Failed to send audio data: {e}");
+                while frame_buffer.len() >= FRAME_SIZE {
+                    let mut frame: Vec<f32> = frame_buffer.drain(..FRAME_SIZE).collect();
+                    let mut max_amplitude = 0.0;
+
+                    if let Some(noise_suppression) = noise_suppression.as_mut() {
+                        noise_suppression.process(&mut frame);
+                    }
+                    // holding a whisper key transmits regardless of the input mode
+                    if whisper_target.is_none() {
+                        if let Some(push_to_talk) = push_to_talk.as_ref() {
+                            push_to_talk.process(&mut frame);
+                        } else if let Some(va) = va.as_mut() {
+                            max_amplitude = va.process(&mut frame);
+                        }
+                    }
+                    if let Some(compress) = compressor.as_mut() {
+                        compress.process(&mut frame);
+                    }
+
+                    if let Some(audio_preview) = audio_preview.as_mut() {
+                        let _ = audio_preview.window.try_lock().map(|window| {
+                            let _ = window.emit("audio_preview", max_amplitude);
+                        });
+                    }
+
+                    let audio_buffer = encoder.encode_audio(&frame, &mut sequence_number);
+
+                    if let Some(audio_buffer) = audio_buffer {
+                        // the connection decides whether this is sent via UDP or tunneled through TCP
+                        if let Err(e) = audio_queue_ref.send(audio_buffer) {
+                            warn!("Failed to send audio data: {e}");
+                        }
                     }
                 }
             }
@@ -142,6 +261,15 @@ impl Recorder {
         Ok(())
     }
 
+    pub fn set_max_bandwidth(&self, max_bandwidth: u32) {
+        self.max_bandwidth.store(max_bandwidth, Ordering::Relaxed);
+    }
+
+    pub fn set_voice_format(&self, voice_format: VoiceFormat) {
+        self.protobuf_voice
+            .store(voice_format == VoiceFormat::Protobuf, Ordering::Relaxed);
+    }
+
     pub fn stop(&mut self) {
         if self.playing.swap(false, Ordering::Relaxed) {
             trace!("Stopping audio thread");
@@ -155,18 +283,26 @@ impl Recorder {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn update_settings<T: VoiceActivationType>(
     settings_channel: &mut Receiver<GlobalSettings>,
     va: &mut Option<VoiceActivation<T>>,
     compressor: &mut Option<Compressor>,
     microphone: &Microphone,
     audio_settings: &mut Option<AudioPreviewContainer>,
+    voice_targets: &mut BTreeMap<u32, Shortcut>,
+    push_to_talk: &mut Option<PushToTalk>,
+    noise_suppression: &mut Option<NoiseSuppression>,
+    input_selection: &mut DeviceSelection,
+    output_selection: &mut DeviceSelection,
 ) {
     match settings_channel.try_recv() {
         Ok(GlobalSettings::AudioInputSettings(audio_settings)) => {
             info!("Received settings: {:?}", audio_settings);
             update_voice_activation_options(&audio_settings, va);
+            update_push_to_talk_options(&audio_settings, push_to_talk);
             update_compressor_options(&audio_settings, compressor);
+            update_noise_suppression(&audio_settings, noise_suppression);
 
             let _ = microphone.volume_adjustment(audio_settings.amplification);
         }
@@ -178,10 +314,39 @@ fn update_settings<T: VoiceActivationType>(
                 *audio_settings = None;
             }
         }
+        Ok(GlobalSettings::InputDevice(device)) => {
+            info!("Received input device: {:?}", device);
+            input_selection.select(device);
+        }
+        // the audio cues are played on the output device
+        Ok(GlobalSettings::OutputDevice(device)) => output_selection.select(device),
+        Ok(GlobalSettings::AvailableDevices(devices)) => {
+            input_selection.update_available(devices.clone());
+            output_selection.update_available(devices);
+        }
+        Ok(GlobalSettings::VoiceTargetShortcut(binding)) => {
+            info!("Received shortcut for voice target {}", binding.target_id);
+            match binding.shortcut {
+                Some(shortcut) => {
+                    voice_targets.insert(binding.target_id, shortcut);
+                }
+                None => {
+                    voice_targets.remove(&binding.target_id);
+                }
+            }
+        }
         _ => {}
     }
 }
 
+/// Returns the voice target with the lowest id whose shortcut is held
+fn held_voice_target(pressed: &[Keycode], voice_targets: &BTreeMap<u32, Shortcut>) -> Option<u8> {
+    voice_targets
+        .iter()
+        .find(|(_, shortcut)| shortcut.is_held(pressed))
+        .and_then(|(target_id, _)| u8::try_from(*target_id).ok())
+}
+
 // f32 to u64
 #[allow(clippy::cast_possible_truncation)]
 #[allow(clippy::cast_sign_loss)]
@@ -194,18 +359,85 @@ fn update_voice_activation_options<T: VoiceActivationType>(
         return;
     }
 
-    if let Some(va) = va.as_mut() {
-        if let Some(va_options) = &audio_settings.voice_activation_options {
-            va.set_durations(
-                Duration::from_millis(va_options.fade_out_duration as u64),
-                Duration::from_millis(va_options.voice_hold as u64),
-            );
-            va.set_thresholds(
-                T::from(va_options.voice_hysteresis_upper_threshold).unwrap_or_else(T::zero),
-                T::from(va_options.voice_hysteresis_lower_threshold).unwrap_or_else(T::zero),
-            );
+    // switching back from push to talk
+    let va = va.get_or_insert_with(default_voice_activation);
+    if let Some(va_options) = &audio_settings.voice_activation_options {
+        va.set_durations(
+            Duration::from_millis(va_options.fade_out_duration as u64),
+            Duration::from_millis(va_options.voice_hold as u64),
+        );
+        va.set_thresholds(
+            T::from(va_options.voice_hysteresis_upper_threshold).unwrap_or_else(T::zero),
+            T::from(va_options.voice_hysteresis_lower_threshold).unwrap_or_else(T::zero),
+        );
+    }
+}
+
+/// Converts the microphone input to the format of [`ENCODER_CONFIG`]
+fn new_resampler(microphone: &Microphone) -> Resampler {
+    let config = microphone.config();
+    trace!(
+        "Resampling {} channel(s) from {}Hz to {SAMPLE_RATE}Hz",
+        config.channels,
+        config.sample_rate
+    );
+    Resampler::new(config.sample_rate, SAMPLE_RATE, config.channels)
+}
+
+fn default_voice_activation<T: VoiceActivationType>() -> VoiceActivation<T> {
+    VoiceActivation::new(
+        SAMPLE_RATE as usize,
+        Duration::from_millis(100),
+        Duration::from_secs(1),
+        T::from(0.6).unwrap_or_else(T::zero),
+        T::from(0.3).unwrap_or_else(T::zero),
+    )
+}
+
+#[allow(clippy::cast_possible_truncation)]
+#[allow(clippy::cast_sign_loss)]
+fn update_push_to_talk_options(
+    audio_settings: &AudioOptions,
+    push_to_talk: &mut Option<PushToTalk>,
+) {
+    let toggle = match audio_settings.input_mode {
+        InputMode::VoiceActivation => {
+            push_to_talk.take();
+            return;
         }
+        InputMode::PushToTalk => false,
+        InputMode::ToggleToTalk => true,
     };
+
+    let options = audio_settings.push_to_talk_options.as_ref();
+    let shortcut = options.and_then(|options| match options.shortcut.parse::<Shortcut>() {
+        Ok(shortcut) => Some(shortcut),
+        Err(e) => {
+            warn!("Invalid push to talk shortcut: {}", e);
+            None
+        }
+    });
+
+    *push_to_talk = Some(PushToTalk::new(
+        shortcut,
+        toggle,
+        Duration::from_millis(options.map_or(0, |options| options.release_delay as u64)),
+        options.map_or(false, |options| options.audio_cue),
+    ));
+}
+
+fn update_noise_suppression(
+    audio_settings: &AudioOptions,
+    noise_suppression: &mut Option<NoiseSuppression>,
+) {
+    match (
+        audio_settings.noise_suppression,
+        noise_suppression.is_some(),
+    ) {
+        (true, false) => *noise_suppression = Some(NoiseSuppression::new()),
+        (false, true) => *noise_suppression = None,
+        _ => {}
+    }
 }
 
 fn update_compressor_options(audio_settings: &AudioOptions, compressor: &mut Option<Compressor>) {
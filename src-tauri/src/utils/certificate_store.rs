@@ -3,14 +3,13 @@ use std::path::PathBuf;
 use openssl::{
     asn1::Asn1Time,
     hash::MessageDigest,
-    pkey::PKey,
+    pkey::{PKey, Private},
     rsa::Rsa,
     x509::{
         extension::{BasicConstraints, KeyUsage},
         X509NameBuilder, X509,
     },
 };
-use tokio_native_tls::native_tls::{self, Identity};
 use tracing::trace;
 
 use crate::errors::{certificate_error::CertificateError, AnyError};
@@ -117,11 +116,18 @@ pub struct CertificateStore {
     private_key: Vec<u8>,
 }
 
-impl CertificateStore {
-    pub fn get_client_certificate(&mut self) -> AnyError<Identity> {
-        let identity = native_tls::Identity::from_pkcs8(&self.certificate, &self.private_key)?;
+/// Certificate and key we authenticate with on the server
+pub struct ClientIdentity {
+    pub certificate: X509,
+    pub private_key: PKey<Private>,
+}
 
-        Ok(identity)
+impl CertificateStore {
+    pub fn get_client_certificate(&mut self) -> AnyError<ClientIdentity> {
+        Ok(ClientIdentity {
+            certificate: X509::from_pem(&self.certificate)?,
+            private_key: PKey::private_key_from_pem(&self.private_key)?,
+        })
     }
 }
 
@@ -0,0 +1,184 @@
+use std::path::Path;
+
+use rusqlite::{params, Connection, Row};
+use serde::{Deserialize, Serialize};
+
+use crate::errors::{application_error::ApplicationError, AnyError};
+
+use super::constants::get_project_dirs;
+
+const CHAT_HISTORY_FILE: &str = "chat_history.sqlite";
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS messages (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        server TEXT NOT NULL,
+        channel_id INTEGER,
+        message_id TEXT,
+        sender_id INTEGER NOT NULL,
+        sender_name TEXT NOT NULL,
+        message TEXT NOT NULL,
+        timestamp INTEGER NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS messages_by_channel ON messages (server, channel_id, id);
+    CREATE VIRTUAL TABLE IF NOT EXISTS messages_fts USING fts5(
+        message, content='messages', content_rowid='id'
+    );
+    CREATE TRIGGER IF NOT EXISTS messages_fts_insert AFTER INSERT ON messages BEGIN
+        INSERT INTO messages_fts (rowid, message) VALUES (new.id, new.message);
+    END;
+    CREATE TRIGGER IF NOT EXISTS messages_fts_delete AFTER DELETE ON messages BEGIN
+        INSERT INTO messages_fts (messages_fts, rowid, message) VALUES ('delete', old.id, old.message);
+    END;
+";
+
+const SELECT_COLUMNS: &str =
+    "m.id, m.channel_id, m.message_id, m.sender_id, m.sender_name, m.message, m.timestamp";
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct StoredMessage {
+    pub history_id: i64,
+    pub channel_id: Option<u32>,
+    pub message_id: Option<String>,
+    pub sender_id: u32,
+    pub sender_name: String,
+    pub message: String,
+    pub timestamp: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct NewMessage<'a> {
+    pub channel_id: Option<u32>,
+    pub message_id: Option<&'a str>,
+    pub sender_id: u32,
+    pub sender_name: &'a str,
+    pub message: &'a str,
+    pub timestamp: u64,
+}
+
+/// Which messages of a server to load
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(tag = "kind", content = "channel_id", rename_all = "snake_case")]
+pub enum HistoryScope {
+    All,
+    Channel(u32),
+    Private,
+}
+
+impl StoredMessage {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            history_id: row.get(0)?,
+            channel_id: row.get(1)?,
+            message_id: row.get(2)?,
+            sender_id: row.get(3)?,
+            sender_name: row.get(4)?,
+            message: row.get(5)?,
+            timestamp: u64::try_from(row.get::<_, i64>(6)?).unwrap_or_default(),
+        })
+    }
+}
+
+/// Messages of all servers we have been connected to, stored in a local `SQLite` database.
+///
+/// Messages are grouped by `host:port` of the server and the channel they were sent to,
+/// private messages don't have a channel.
+pub struct ChatHistory {
+    connection: Connection,
+    server: String,
+}
+
+impl ChatHistory {
+    pub fn open(server_host: &str, server_port: u16) -> AnyError<Self> {
+        let project_dirs = get_project_dirs()
+            .ok_or_else(|| ApplicationError::new("Unable to load project dir"))?;
+        let data_dir = project_dirs.data_dir();
+
+        if !data_dir.exists() {
+            std::fs::create_dir_all(data_dir)?;
+        }
+
+        Self::open_at(&data_dir.join(CHAT_HISTORY_FILE), server_host, server_port)
+    }
+
+    pub fn open_at(path: &Path, server_host: &str, server_port: u16) -> AnyError<Self> {
+        let connection = Connection::open(path)?;
+        connection.execute_batch(SCHEMA)?;
+
+        Ok(Self {
+            connection,
+            server: format!("{}:{server_port}", server_host.to_lowercase()),
+        })
+    }
+
+    pub fn insert(&self, message: &NewMessage) -> AnyError<i64> {
+        self.connection.execute(
+            "INSERT INTO messages (server, channel_id, message_id, sender_id, sender_name, message, timestamp)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                self.server,
+                message.channel_id,
+                message.message_id,
+                message.sender_id,
+                message.sender_name,
+                message.message,
+                i64::try_from(message.timestamp)?,
+            ],
+        )?;
+
+        Ok(self.connection.last_insert_rowid())
+    }
+
+    /// Returns up to `limit` messages of the scope older than `before` (a `history_id`), oldest first.
+    pub fn page(
+        &self,
+        scope: HistoryScope,
+        before: Option<i64>,
+        limit: u32,
+    ) -> AnyError<Vec<StoredMessage>> {
+        let (channel_id, private) = match scope {
+            HistoryScope::All => (None, false),
+            HistoryScope::Channel(channel_id) => (Some(channel_id), false),
+            HistoryScope::Private => (None, true),
+        };
+
+        let mut statement = self.connection.prepare(&format!(
+            "SELECT {SELECT_COLUMNS} FROM messages m
+             WHERE m.server = ?1 AND (?2 IS NULL OR m.channel_id = ?2)
+             AND (NOT ?3 OR m.channel_id IS NULL) AND m.id < ?4
+             ORDER BY m.id DESC LIMIT ?5"
+        ))?;
+        let mut messages = statement
+            .query_map(
+                params![
+                    self.server,
+                    channel_id,
+                    private,
+                    before.unwrap_or(i64::MAX),
+                    limit
+                ],
+                StoredMessage::from_row,
+            )?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        messages.reverse();
+
+        Ok(messages)
+    }
+
+    /// Full text search over all messages of the server, newest first
+    pub fn search(&self, query: &str, limit: u32) -> AnyError<Vec<StoredMessage>> {
+        // search for the text as a phrase, so the user doesn't have to care about the FTS syntax
+        let phrase = format!("\"{}\"", query.replace('"', "\"\""));
+
+        let mut statement = self.connection.prepare(&format!(
+            "SELECT {SELECT_COLUMNS} FROM messages_fts f JOIN messages m ON m.id = f.rowid
+             WHERE messages_fts MATCH ?1 AND m.server = ?2
+             ORDER BY m.id DESC LIMIT ?3"
+        ))?;
+        let messages = statement
+            .query_map(params![phrase, self.server, limit], StoredMessage::from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(messages)
+    }
+}
@@ -1,10 +1,13 @@
 pub mod audio;
 pub mod certificate_store;
+pub mod chat_history;
 pub mod constants;
 pub mod file;
 pub mod frontend;
 pub mod messages;
+pub mod pinned_certificate_store;
 pub mod server;
+pub mod shortcut;
 pub mod varint;
 
 #[cfg(test)]
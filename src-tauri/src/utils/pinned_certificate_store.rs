@@ -0,0 +1,154 @@
+use std::{collections::HashMap, fmt::Write, path::PathBuf};
+
+use openssl::sha::sha256;
+use serde::{Deserialize, Serialize};
+use tracing::{info, trace};
+
+use crate::errors::{certificate_error::CertificateError, AnyError};
+
+use super::constants::get_project_dirs;
+
+const PINNED_CERTIFICATES_FILE: &str = "pinned_certificates.json";
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum PinStatus {
+    Trusted,
+    FirstUse,
+    Mismatch { pinned: String },
+}
+
+/// A server presented a different certificate than the one we pinned for it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CertificateMismatch {
+    pub host: String,
+    pub port: u16,
+    pub pinned: String,
+    pub presented: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct PinnedCertificates {
+    #[serde(default)]
+    pins: HashMap<String, String>,
+    /// Changed certificates we refused, the user may only accept exactly these
+    #[serde(default)]
+    presented: HashMap<String, String>,
+}
+
+/// Server certificate fingerprints we have seen before (trust on first use).
+///
+/// Self-signed servers are very common for Mumble, so instead of rejecting them we
+/// remember the first certificate and refuse the connection if it changes later on.
+pub struct PinnedCertificateStore {
+    path: PathBuf,
+    certificates: PinnedCertificates,
+}
+
+impl PinnedCertificateStore {
+    pub fn load() -> AnyError<Self> {
+        let project_dirs = get_project_dirs()
+            .ok_or_else(|| CertificateError::new("Unable to load project dir"))?;
+        let data_dir = project_dirs.data_dir();
+
+        if !data_dir.exists() {
+            std::fs::create_dir_all(data_dir)?;
+        }
+
+        Self::load_from(data_dir.join(PINNED_CERTIFICATES_FILE))
+    }
+
+    pub fn load_from(path: PathBuf) -> AnyError<Self> {
+        let certificates: PinnedCertificates = if path.exists() {
+            serde_json::from_slice(&std::fs::read(&path)?)?
+        } else {
+            PinnedCertificates::default()
+        };
+        trace!("Loaded {} pinned certificates", certificates.pins.len());
+
+        Ok(Self { path, certificates })
+    }
+
+    pub fn check(&self, server_host: &str, server_port: u16, fingerprint: &str) -> PinStatus {
+        match self
+            .certificates
+            .pins
+            .get(&Self::key(server_host, server_port))
+        {
+            None => PinStatus::FirstUse,
+            Some(pinned) if pinned == fingerprint => PinStatus::Trusted,
+            Some(pinned) => PinStatus::Mismatch {
+                pinned: pinned.clone(),
+            },
+        }
+    }
+
+    pub fn pin(&mut self, server_host: &str, server_port: u16, fingerprint: &str) -> AnyError<()> {
+        let key = Self::key(server_host, server_port);
+        if self
+            .certificates
+            .pins
+            .get(&key)
+            .is_some_and(|pinned| pinned == fingerprint)
+        {
+            return Ok(());
+        }
+
+        info!("Pinning certificate {fingerprint} for {key}");
+        self.certificates.presented.remove(&key);
+        self.certificates.pins.insert(key, fingerprint.to_string());
+        self.save()
+    }
+
+    /// Remembers the changed certificate a server presented, so the user can accept it later on
+    pub fn remember_presented(
+        &mut self,
+        server_host: &str,
+        server_port: u16,
+        fingerprint: &str,
+    ) -> AnyError<()> {
+        self.certificates
+            .presented
+            .insert(Self::key(server_host, server_port), fingerprint.to_string());
+        self.save()
+    }
+
+    /// Pins a changed certificate, but only if the server actually presented it to us
+    pub fn accept(
+        &mut self,
+        server_host: &str,
+        server_port: u16,
+        fingerprint: &str,
+    ) -> AnyError<()> {
+        let key = Self::key(server_host, server_port);
+        if self.certificates.presented.get(&key).map(String::as_str) != Some(fingerprint) {
+            return Err(Box::new(CertificateError::new(&format!(
+                "The certificate {fingerprint} was not presented by {key}"
+            ))));
+        }
+
+        self.pin(server_host, server_port, fingerprint)
+    }
+
+    fn save(&self) -> AnyError<()> {
+        std::fs::write(
+            &self.path,
+            serde_json::to_string_pretty(&self.certificates)?,
+        )?;
+
+        Ok(())
+    }
+
+    fn key(server_host: &str, server_port: u16) -> String {
+        format!("{}:{server_port}", server_host.to_lowercase())
+    }
+}
+
+/// SHA-256 fingerprint of a DER encoded certificate as lower case hex string
+pub fn fingerprint(certificate: &[u8]) -> String {
+    sha256(certificate)
+        .iter()
+        .fold(String::new(), |mut output, byte| {
+            let _ = write!(output, "{byte:02x}");
+            output
+        })
+}
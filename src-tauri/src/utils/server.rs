@@ -1,9 +1,14 @@
+use crate::errors::AnyError;
+
+const KEYRING_SERVICE: &str = "fancy-mumble";
+
 #[derive(serde::Serialize, serde::Deserialize, Debug)]
 pub struct UserIdentity {
     pub name: String,
     pub file: String,
 }
 
+/// A saved server, the password is kept in the keyring of the system instead of the server file
 #[derive(serde::Serialize, serde::Deserialize, Debug)]
 pub struct Server {
     pub description: String,
@@ -11,4 +16,36 @@ pub struct Server {
     pub port: u16,
     pub username: String,
     pub identity: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    #[serde(default)]
+    pub access_tokens: Vec<String>,
+}
+
+impl Server {
+    fn keyring_entry(&self) -> AnyError<keyring::Entry> {
+        let account = format!("{}@{}:{}", self.username, self.host, self.port);
+
+        Ok(keyring::Entry::new(KEYRING_SERVICE, &account)?)
+    }
+
+    /// Moves the password into the keyring, so it isn't written to the server file
+    pub fn store_password(&mut self) -> AnyError<()> {
+        if let Some(password) = self.password.take() {
+            self.keyring_entry()?.set_password(&password)?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads the password back from the keyring, servers without one are left unchanged
+    pub fn load_password(&mut self) -> AnyError<()> {
+        match self.keyring_entry()?.get_password() {
+            Ok(password) => self.password = Some(password),
+            Err(keyring::Error::NoEntry) => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        Ok(())
+    }
 }
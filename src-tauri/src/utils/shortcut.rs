@@ -0,0 +1,166 @@
+use std::str::FromStr;
+
+use device_query::{DeviceQuery, DeviceState, Keycode};
+use tracing::warn;
+
+use crate::errors::application_error::ApplicationError;
+
+/// A key combination like `Ctrl+Shift+W`, which is held as long as all of its keys are pressed
+///
+/// Modifiers match both the left and the right key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Shortcut {
+    keys: Vec<Vec<Keycode>>,
+}
+
+impl Shortcut {
+    pub fn is_held(&self, pressed: &[Keycode]) -> bool {
+        self.keys
+            .iter()
+            .all(|alternatives| alternatives.iter().any(|key| pressed.contains(key)))
+    }
+}
+
+impl FromStr for Shortcut {
+    type Err = ApplicationError;
+
+    fn from_str(shortcut: &str) -> Result<Self, Self::Err> {
+        let keys = shortcut
+            .split('+')
+            .map(|key| {
+                parse_key(key.trim()).ok_or_else(|| {
+                    ApplicationError::new(&format!("Unknown key {key:?} in shortcut {shortcut:?}"))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self { keys })
+    }
+}
+
+fn parse_key(key: &str) -> Option<Vec<Keycode>> {
+    let key = key.to_ascii_lowercase();
+    let keycode = match key.as_str() {
+        "ctrl" | "control" => return Some(vec![Keycode::LControl, Keycode::RControl]),
+        "shift" => return Some(vec![Keycode::LShift, Keycode::RShift]),
+        "alt" => return Some(vec![Keycode::LAlt, Keycode::RAlt]),
+        "meta" | "super" => return Some(vec![Keycode::LMeta, Keycode::RMeta]),
+        "space" => Keycode::Space,
+        "tab" => Keycode::Tab,
+        "enter" | "return" => Keycode::Enter,
+        "backspace" => Keycode::Backspace,
+        "esc" | "escape" => Keycode::Escape,
+        "capslock" => Keycode::CapsLock,
+        "up" => Keycode::Up,
+        "down" => Keycode::Down,
+        "left" => Keycode::Left,
+        "right" => Keycode::Right,
+        "home" => Keycode::Home,
+        "end" => Keycode::End,
+        "pageup" => Keycode::PageUp,
+        "pagedown" => Keycode::PageDown,
+        "insert" => Keycode::Insert,
+        "delete" => Keycode::Delete,
+        "f1" => Keycode::F1,
+        "f2" => Keycode::F2,
+        "f3" => Keycode::F3,
+        "f4" => Keycode::F4,
+        "f5" => Keycode::F5,
+        "f6" => Keycode::F6,
+        "f7" => Keycode::F7,
+        "f8" => Keycode::F8,
+        "f9" => Keycode::F9,
+        "f10" => Keycode::F10,
+        "f11" => Keycode::F11,
+        "f12" => Keycode::F12,
+        _ => parse_character(&key)?,
+    };
+
+    Some(vec![keycode])
+}
+
+fn parse_character(key: &str) -> Option<Keycode> {
+    let mut chars = key.chars();
+    let (Some(character), None) = (chars.next(), chars.next()) else {
+        return None;
+    };
+
+    let keycode = match character {
+        '0' => Keycode::Key0,
+        '1' => Keycode::Key1,
+        '2' => Keycode::Key2,
+        '3' => Keycode::Key3,
+        '4' => Keycode::Key4,
+        '5' => Keycode::Key5,
+        '6' => Keycode::Key6,
+        '7' => Keycode::Key7,
+        '8' => Keycode::Key8,
+        '9' => Keycode::Key9,
+        'a' => Keycode::A,
+        'b' => Keycode::B,
+        'c' => Keycode::C,
+        'd' => Keycode::D,
+        'e' => Keycode::E,
+        'f' => Keycode::F,
+        'g' => Keycode::G,
+        'h' => Keycode::H,
+        'i' => Keycode::I,
+        'j' => Keycode::J,
+        'k' => Keycode::K,
+        'l' => Keycode::L,
+        'm' => Keycode::M,
+        'n' => Keycode::N,
+        'o' => Keycode::O,
+        'p' => Keycode::P,
+        'q' => Keycode::Q,
+        'r' => Keycode::R,
+        's' => Keycode::S,
+        't' => Keycode::T,
+        'u' => Keycode::U,
+        'v' => Keycode::V,
+        'w' => Keycode::W,
+        'x' => Keycode::X,
+        'y' => Keycode::Y,
+        'z' => Keycode::Z,
+        _ => return None,
+    };
+
+    Some(keycode)
+}
+
+/// Reads the currently pressed keys, independent of the focused window
+///
+/// The global shortcuts of tauri can't be used for this. Their handler is only called once when
+/// the combination is pressed and there is no event for releasing it, so we couldn't tell when to
+/// stop transmitting. They also take the combination away from other applications, which is bad
+/// for a key held in a game. So held keys are polled instead.
+///
+/// On Linux this queries the X server, so shortcuts only work in X11 sessions. Wayland
+/// compositors don't let applications read the global keyboard state, there only keys
+/// pressed while an XWayland window is focused are seen.
+pub struct Keyboard {
+    device_state: Option<DeviceState>,
+}
+
+impl Keyboard {
+    pub fn new() -> Self {
+        let device_state = DeviceState::checked_new();
+        if device_state.is_none() {
+            warn!("Unable to read the keyboard state, shortcuts are disabled");
+        }
+
+        Self { device_state }
+    }
+
+    pub fn pressed_keys(&self) -> Vec<Keycode> {
+        self.device_state
+            .as_ref()
+            .map_or_else(Vec::new, DeviceQuery::get_keys)
+    }
+}
+
+impl Default for Keyboard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
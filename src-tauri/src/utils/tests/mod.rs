@@ -4,5 +4,16 @@ macro_rules! aw {
     };
 }
 
+mod test_chat_history;
+mod test_device_selection;
 mod test_file_utils;
+mod test_jitter_buffer;
+mod test_legacy_voice;
+mod test_noise_suppression;
+mod test_pinned_certificate_store;
+mod test_positional;
+mod test_protobuf_voice;
+mod test_push_to_talk;
+mod test_resampler;
+mod test_shortcut;
 mod test_varint;
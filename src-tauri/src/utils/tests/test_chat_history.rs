@@ -0,0 +1,133 @@
+mod tests {
+    use crate::utils::chat_history::{ChatHistory, HistoryScope, NewMessage};
+    use tempfile::tempdir;
+
+    fn message(channel_id: Option<u32>, text: &str, timestamp: u64) -> NewMessage {
+        NewMessage {
+            channel_id,
+            message_id: None,
+            sender_id: 1,
+            sender_name: "alice",
+            message: text,
+            timestamp,
+        }
+    }
+
+    #[test]
+    fn test_page_history() {
+        let tmp_dir = tempdir().expect("Failed to create temp dir");
+        let path = tmp_dir.path().join("history.sqlite");
+        let history = ChatHistory::open_at(&path, "example.com", 64738).expect("Failed to open");
+
+        let mut ids = Vec::new();
+        for i in 0..5 {
+            let id = history
+                .insert(&message(Some(1), &format!("message {i}"), i))
+                .expect("Failed to insert message");
+            ids.push(id);
+        }
+        history
+            .insert(&message(Some(2), "other channel", 10))
+            .expect("Failed to insert message");
+
+        let last = history
+            .page(HistoryScope::Channel(1), None, 2)
+            .expect("Failed to load page");
+        let texts = last.iter().map(|m| m.message.as_str()).collect::<Vec<_>>();
+        assert_eq!(texts, vec!["message 3", "message 4"]);
+
+        let previous = history
+            .page(HistoryScope::Channel(1), Some(last[0].history_id), 10)
+            .expect("Failed to load page");
+        assert_eq!(previous.len(), 3);
+        assert_eq!(previous[0].history_id, ids[0]);
+
+        let all = history
+            .page(HistoryScope::All, None, 10)
+            .expect("Failed to load page");
+        assert_eq!(all.len(), 6);
+    }
+
+    #[test]
+    fn test_page_private_history() {
+        let tmp_dir = tempdir().expect("Failed to create temp dir");
+        let path = tmp_dir.path().join("history.sqlite");
+        let history = ChatHistory::open_at(&path, "example.com", 64738).expect("Failed to open");
+
+        history
+            .insert(&message(Some(1), "channel", 1))
+            .expect("Failed to insert message");
+        history
+            .insert(&message(None, "private", 2))
+            .expect("Failed to insert message");
+
+        let private = history
+            .page(HistoryScope::Private, None, 10)
+            .expect("Failed to load page");
+        let texts = private
+            .iter()
+            .map(|m| m.message.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(texts, vec!["private"]);
+        assert_eq!(private[0].channel_id, None);
+    }
+
+    #[test]
+    fn test_scope_from_frontend() {
+        let scope: HistoryScope =
+            serde_json::from_str(r#"{"kind":"channel","channel_id":3}"#).expect("Invalid scope");
+        assert_eq!(scope, HistoryScope::Channel(3));
+
+        let scope: HistoryScope =
+            serde_json::from_str(r#"{"kind":"private"}"#).expect("Invalid scope");
+        assert_eq!(scope, HistoryScope::Private);
+    }
+
+    #[test]
+    fn test_history_is_separated_by_server() {
+        let tmp_dir = tempdir().expect("Failed to create temp dir");
+        let path = tmp_dir.path().join("history.sqlite");
+        let first = ChatHistory::open_at(&path, "example.com", 64738).expect("Failed to open");
+        let second = ChatHistory::open_at(&path, "example.com", 64739).expect("Failed to open");
+
+        first
+            .insert(&message(None, "private", 1))
+            .expect("Failed to insert message");
+
+        assert_eq!(
+            first
+                .page(HistoryScope::All, None, 10)
+                .expect("Failed to load")
+                .len(),
+            1
+        );
+        assert!(second
+            .page(HistoryScope::All, None, 10)
+            .expect("Failed to load")
+            .is_empty());
+    }
+
+    #[test]
+    fn test_search_history() {
+        let tmp_dir = tempdir().expect("Failed to create temp dir");
+        let path = tmp_dir.path().join("history.sqlite");
+        let history = ChatHistory::open_at(&path, "example.com", 64738).expect("Failed to open");
+
+        history
+            .insert(&message(Some(1), "hello world", 1))
+            .expect("Failed to insert message");
+        history
+            .insert(&message(Some(1), "goodbye world", 2))
+            .expect("Failed to insert message");
+        history
+            .insert(&message(Some(1), "say \"hello\"", 3))
+            .expect("Failed to insert message");
+
+        let found = history.search("world", 10).expect("Failed to search");
+        let texts = found.iter().map(|m| m.message.as_str()).collect::<Vec<_>>();
+        assert_eq!(texts, vec!["goodbye world", "hello world"]);
+
+        let quoted = history.search("\"hello", 10).expect("Failed to search");
+        assert_eq!(quoted.len(), 2);
+    }
+}
@@ -0,0 +1,72 @@
+#[cfg(test)]
+mod tests {
+    use crate::utils::audio::device_manager::{AvailableDevices, DeviceKind, DeviceSelection};
+
+    fn available(kind: DeviceKind, names: &[&str]) -> AvailableDevices {
+        AvailableDevices {
+            kind,
+            names: names.iter().map(ToString::to_string).collect(),
+        }
+    }
+
+    #[test]
+    fn test_default_device_needs_no_switch() {
+        let mut selection = DeviceSelection::new(DeviceKind::Input);
+        selection.update_available(available(DeviceKind::Input, &["Headset"]));
+
+        assert!(!selection.poll());
+        assert_eq!(selection.active(), None);
+    }
+
+    #[test]
+    fn test_switches_once_the_device_is_known() {
+        let mut selection = DeviceSelection::new(DeviceKind::Input);
+        selection.select(Some("Headset".to_string()));
+
+        // nothing was looked up yet
+        assert!(!selection.poll());
+
+        selection.update_available(available(DeviceKind::Input, &["Headset", "Webcam"]));
+        assert!(selection.poll());
+        assert_eq!(selection.active(), Some("Headset"));
+        assert!(!selection.poll());
+    }
+
+    #[test]
+    fn test_falls_back_while_the_device_is_missing() {
+        let mut selection = DeviceSelection::new(DeviceKind::Output);
+        selection.select(Some("Speaker".to_string()));
+        selection.update_available(available(DeviceKind::Output, &["Speaker"]));
+        assert!(selection.poll());
+
+        selection.update_available(available(DeviceKind::Output, &["Monitor"]));
+        assert!(selection.poll());
+        assert_eq!(selection.active(), None);
+
+        selection.update_available(available(DeviceKind::Output, &["Monitor", "Speaker"]));
+        assert!(selection.poll());
+        assert_eq!(selection.active(), Some("Speaker"));
+    }
+
+    #[test]
+    fn test_ignores_devices_of_the_other_kind() {
+        let mut selection = DeviceSelection::new(DeviceKind::Output);
+        selection.select(Some("Headset".to_string()));
+        selection.update_available(available(DeviceKind::Input, &["Headset"]));
+
+        assert!(!selection.poll());
+        assert_eq!(selection.active(), None);
+    }
+
+    #[test]
+    fn test_selecting_the_default_device() {
+        let mut selection = DeviceSelection::new(DeviceKind::Input);
+        selection.select(Some("Headset".to_string()));
+        selection.update_available(available(DeviceKind::Input, &["Headset"]));
+        assert!(selection.poll());
+
+        selection.select(None);
+        assert!(selection.poll());
+        assert_eq!(selection.active(), None);
+    }
+}
@@ -0,0 +1,174 @@
+#[cfg(test)]
+mod tests {
+    use crate::utils::audio::jitter_buffer::{Frame, JitterBuffer, JitterStats};
+
+    fn payload(sequence: u64) -> Vec<u8> {
+        sequence.to_le_bytes().to_vec()
+    }
+
+    fn push_all(buffer: &mut JitterBuffer, sequences: &[u64]) {
+        for sequence in sequences {
+            buffer.push(*sequence, true, payload(*sequence));
+        }
+    }
+
+    #[test]
+    fn test_waits_until_buffered() {
+        let mut buffer = JitterBuffer::new();
+
+        buffer.push(0, true, payload(0));
+        assert_eq!(buffer.pop(), None);
+
+        buffer.push(1, true, payload(1));
+        assert_eq!(buffer.pop(), Some(Frame::Packet(payload(0))));
+        assert_eq!(buffer.pop(), Some(Frame::Packet(payload(1))));
+    }
+
+    #[test]
+    fn test_reorders_packets() {
+        let mut buffer = JitterBuffer::new();
+        push_all(&mut buffer, &[1, 0, 3, 2]);
+
+        for sequence in 0..4 {
+            assert_eq!(buffer.pop(), Some(Frame::Packet(payload(sequence))));
+        }
+        assert_eq!(buffer.stats().good, 4);
+    }
+
+    #[test]
+    fn test_recovers_missing_packet() {
+        let mut buffer = JitterBuffer::new();
+        push_all(&mut buffer, &[0, 1, 3]);
+
+        assert_eq!(buffer.pop(), Some(Frame::Packet(payload(0))));
+        assert_eq!(buffer.pop(), Some(Frame::Packet(payload(1))));
+        assert_eq!(buffer.pop(), Some(Frame::Recovered(payload(3))));
+        assert_eq!(buffer.pop(), Some(Frame::Packet(payload(3))));
+        assert_eq!(buffer.stats().lost, 1);
+    }
+
+    #[test]
+    fn test_conceals_missing_packets() {
+        let mut buffer = JitterBuffer::new();
+        push_all(&mut buffer, &[0, 1, 4]);
+
+        assert_eq!(buffer.pop(), Some(Frame::Packet(payload(0))));
+        assert_eq!(buffer.pop(), Some(Frame::Packet(payload(1))));
+        assert_eq!(buffer.pop(), Some(Frame::Concealed));
+        assert_eq!(buffer.pop(), Some(Frame::Recovered(payload(4))));
+        assert_eq!(buffer.pop(), Some(Frame::Packet(payload(4))));
+        assert_eq!(buffer.stats().lost, 2);
+    }
+
+    #[test]
+    fn test_counts_late_packets() {
+        let mut buffer = JitterBuffer::new();
+        push_all(&mut buffer, &[0, 2, 3]);
+
+        assert_eq!(buffer.pop(), Some(Frame::Packet(payload(0))));
+        assert_eq!(buffer.pop(), Some(Frame::Recovered(payload(2))));
+
+        buffer.push(1, true, payload(1));
+        buffer.push(3, true, payload(3));
+        assert_eq!(buffer.pop(), Some(Frame::Packet(payload(2))));
+        assert_eq!(
+            buffer.stats(),
+            JitterStats {
+                good: 2,
+                late: 2,
+                lost: 1,
+                resync: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_detects_frame_counting_sequence() {
+        let mut buffer = JitterBuffer::new();
+        push_all(&mut buffer, &[10, 12, 18]);
+
+        assert_eq!(buffer.pop(), Some(Frame::Packet(payload(10))));
+        assert_eq!(buffer.pop(), Some(Frame::Packet(payload(12))));
+        assert_eq!(buffer.pop(), Some(Frame::Concealed));
+        assert_eq!(buffer.pop(), Some(Frame::Recovered(payload(18))));
+        assert_eq!(buffer.pop(), Some(Frame::Packet(payload(18))));
+    }
+
+    #[test]
+    fn test_resyncs_restarted_sequence() {
+        let mut buffer = JitterBuffer::new();
+        push_all(&mut buffer, &[598, 599, 0, 1]);
+
+        for sequence in [598, 599, 0, 1] {
+            assert_eq!(buffer.pop(), Some(Frame::Packet(payload(sequence))));
+        }
+        assert_eq!(buffer.stats().resync, 1);
+        assert_eq!(buffer.stats().late, 0);
+    }
+
+    #[test]
+    fn test_plays_short_transmission() {
+        let mut buffer = JitterBuffer::new();
+        buffer.push(0, false, payload(0));
+
+        assert_eq!(buffer.pop(), Some(Frame::Packet(payload(0))));
+        assert_eq!(buffer.pop(), None);
+
+        // the next transmission starts with a new sequence
+        push_all(&mut buffer, &[0, 1]);
+        assert_eq!(buffer.pop(), Some(Frame::Packet(payload(0))));
+        assert_eq!(buffer.stats().late, 0);
+    }
+
+    #[test]
+    fn test_stall_increases_delay() {
+        let mut buffer = JitterBuffer::new();
+        push_all(&mut buffer, &[0, 1]);
+        let delay = buffer.delay();
+
+        assert_eq!(buffer.pop(), Some(Frame::Packet(payload(0))));
+        assert_eq!(buffer.pop(), Some(Frame::Packet(payload(1))));
+        while buffer.pop().is_some() {}
+        // it might just have ended without a terminator
+        assert_eq!(buffer.delay(), delay);
+
+        // the stream goes on where it stopped, so it stalled
+        push_all(&mut buffer, &[7, 8]);
+        assert_eq!(buffer.delay(), delay + 1);
+        assert_eq!(buffer.pop(), None);
+        buffer.push(9, true, payload(9));
+        assert_eq!(buffer.pop(), Some(Frame::Packet(payload(7))));
+    }
+
+    #[test]
+    fn test_ended_stream_keeps_delay() {
+        let mut buffer = JitterBuffer::new();
+        push_all(&mut buffer, &[40, 41]);
+        let delay = buffer.delay();
+
+        assert_eq!(buffer.pop(), Some(Frame::Packet(payload(40))));
+        assert_eq!(buffer.pop(), Some(Frame::Packet(payload(41))));
+        while buffer.pop().is_some() {}
+
+        // the next transmission starts with a new sequence
+        push_all(&mut buffer, &[0, 1]);
+        assert_eq!(buffer.delay(), delay);
+        assert_eq!(buffer.pop(), Some(Frame::Packet(payload(0))));
+    }
+
+    #[test]
+    fn test_terminated_stream_keeps_delay() {
+        let mut buffer = JitterBuffer::new();
+        push_all(&mut buffer, &[0, 1]);
+        buffer.push(2, false, payload(2));
+        let delay = buffer.delay();
+
+        for sequence in 0..3 {
+            assert_eq!(buffer.pop(), Some(Frame::Packet(payload(sequence))));
+        }
+        assert_eq!(buffer.pop(), None);
+
+        push_all(&mut buffer, &[3, 4]);
+        assert_eq!(buffer.delay(), delay);
+    }
+}
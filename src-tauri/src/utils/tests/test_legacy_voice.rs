@@ -0,0 +1,121 @@
+#[cfg(test)]
+mod tests {
+    use crate::utils::{
+        audio::{
+            decoder::{AudioContext, Decoder, UDPDecoder},
+            encoder::{Encoder, UDPEncoder},
+            microphone::DeviceConfig,
+        },
+        varint,
+    };
+
+    const SESSION: u64 = 5;
+    const CONFIG: DeviceConfig = DeviceConfig {
+        channels: 1,
+        sample_rate: 48_000,
+        buffer_size: 960,
+    };
+
+    /// The server inserts the session of the sender after the header
+    fn relay(packet: &[u8]) -> Vec<u8> {
+        let session = varint::Builder::new()
+            .number(&SESSION)
+            .build()
+            .expect("Failed to build session");
+
+        let mut relayed = vec![packet[0]];
+        relayed.extend(session.parsed_vec());
+        relayed.extend(&packet[1..]);
+        relayed
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn voice() -> Vec<f32> {
+        (0..960).map(|i| (i as f32 * 0.05).sin() * 0.5).collect()
+    }
+
+    #[test]
+    fn test_transmission_ends_with_terminator() {
+        let mut encoder = UDPEncoder::new(CONFIG);
+        let mut sequence_number = 0;
+
+        for _ in 0..3 {
+            let packet = encoder
+                .encode_audio(&voice(), &mut sequence_number)
+                .expect("Voice wasn't encoded");
+            let decoded = UDPDecoder
+                .decode_audio(&relay(&packet))
+                .expect("Failed to decode packet");
+
+            assert_eq!(decoded.user_id, 5);
+            assert!(decoded.talking);
+            assert!(!decoded.payload.is_empty());
+        }
+
+        let terminator = encoder
+            .encode_audio(&[0.0; 960], &mut sequence_number)
+            .expect("Missing terminator");
+        let decoded = UDPDecoder
+            .decode_audio(&relay(&terminator))
+            .expect("Failed to decode terminator");
+        assert!(!decoded.talking);
+        assert_eq!(decoded.sequence_number, 3);
+
+        // nothing is sent while we are silent
+        assert!(encoder
+            .encode_audio(&[0.0; 960], &mut sequence_number)
+            .is_none());
+    }
+
+    #[test]
+    fn test_target_switch_ends_transmission() {
+        let mut encoder = UDPEncoder::new(CONFIG);
+        let mut sequence_number = 0;
+
+        // not talking yet, so nothing has to be ended
+        assert!(encoder.set_target(1, &mut sequence_number).is_none());
+        encoder
+            .encode_audio(&voice(), &mut sequence_number)
+            .expect("Voice wasn't encoded");
+
+        let terminator = encoder
+            .set_target(0, &mut sequence_number)
+            .expect("Missing terminator");
+        // the terminator still goes to the previous target
+        assert_eq!(terminator[0] & 0b0001_1111, 1);
+        let decoded = UDPDecoder
+            .decode_audio(&relay(&terminator))
+            .expect("Failed to decode terminator");
+        assert!(!decoded.talking);
+
+        let packet = encoder
+            .encode_audio(&voice(), &mut sequence_number)
+            .expect("Voice wasn't encoded");
+        assert_eq!(packet[0] & 0b0001_1111, 0);
+    }
+
+    #[test]
+    fn test_decode_context() {
+        let mut encoder = UDPEncoder::new(CONFIG);
+        let mut sequence_number = 0;
+        let packet = encoder
+            .encode_audio(&voice(), &mut sequence_number)
+            .expect("Voice wasn't encoded");
+
+        // the server replaces our target with the context it sends the audio in
+        for (context, expected) in [
+            (0, AudioContext::Normal),
+            (1, AudioContext::Shout),
+            (2, AudioContext::Whisper),
+            (3, AudioContext::Listen),
+        ] {
+            let mut relayed = relay(&packet);
+            relayed[0] = (relayed[0] & 0b1110_0000) | context;
+            let decoded = UDPDecoder
+                .decode_audio(&relayed)
+                .expect("Failed to decode packet");
+
+            assert_eq!(decoded.context, expected);
+        }
+    }
+}
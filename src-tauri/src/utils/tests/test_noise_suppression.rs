@@ -0,0 +1,63 @@
+#[cfg(test)]
+mod tests {
+    use crate::utils::audio::processing::noise_suppression::{
+        from_model_scale, to_model_scale, NoiseSuppression, DELAY,
+    };
+
+    #[allow(clippy::cast_precision_loss)]
+    fn signal(length: usize) -> Vec<f32> {
+        (0..length).map(|i| (i as f32 * 0.05).sin() * 0.5).collect()
+    }
+
+    #[test]
+    fn test_delay_is_one_model_frame() {
+        assert_eq!(DELAY, 480);
+    }
+
+    #[test]
+    fn test_length_is_preserved() {
+        let mut noise_suppression = NoiseSuppression::new();
+
+        // chunk sizes which don't line up with the frames of the model
+        for length in [960, 100, 479, 1, 1000] {
+            let mut data = signal(length);
+            noise_suppression.process(&mut data);
+            assert_eq!(data.len(), length);
+        }
+    }
+
+    #[test]
+    fn test_output_lags_one_frame() {
+        let mut noise_suppression = NoiseSuppression::new();
+        let mut output = Vec::new();
+
+        for _ in 0..10 {
+            let mut data = signal(100);
+            noise_suppression.process(&mut data);
+            output.extend(data);
+        }
+
+        assert!(output[..DELAY]
+            .iter()
+            .all(|sample| sample.abs() < f32::EPSILON));
+    }
+
+    #[test]
+    fn test_output_stays_in_sample_range() {
+        let mut noise_suppression = NoiseSuppression::new();
+
+        for _ in 0..20 {
+            let mut data = signal(960);
+            noise_suppression.process(&mut data);
+            assert!(data.iter().all(|sample| sample.abs() <= 1.0));
+        }
+    }
+
+    #[test]
+    fn test_scaling_round_trip() {
+        for sample in [-1.0, -0.5, 0.0, 0.25, 1.0] {
+            assert!((from_model_scale(to_model_scale(sample)) - sample).abs() < f32::EPSILON);
+        }
+        assert!((to_model_scale(1.0) - 32_767.0).abs() < f32::EPSILON);
+    }
+}
@@ -0,0 +1,103 @@
+mod tests {
+    use crate::utils::pinned_certificate_store::{fingerprint, PinStatus, PinnedCertificateStore};
+    use tempfile::tempdir;
+
+    const FINGERPRINT: &str = "a1b2c3";
+
+    #[test]
+    fn test_fingerprint() {
+        assert_eq!(
+            fingerprint(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_trust_on_first_use() {
+        let tmp_dir = tempdir().expect("Failed to create temp dir");
+        let path = tmp_dir.path().join("pins.json");
+
+        let mut store =
+            PinnedCertificateStore::load_from(path.clone()).expect("Failed to load store");
+        assert_eq!(
+            store.check("example.com", 64738, FINGERPRINT),
+            PinStatus::FirstUse
+        );
+
+        store
+            .pin("example.com", 64738, FINGERPRINT)
+            .expect("Failed to pin certificate");
+        assert_eq!(
+            store.check("Example.com", 64738, FINGERPRINT),
+            PinStatus::Trusted
+        );
+        assert_eq!(
+            store.check("example.com", 64739, FINGERPRINT),
+            PinStatus::FirstUse
+        );
+
+        let reloaded = PinnedCertificateStore::load_from(path).expect("Failed to reload store");
+        assert_eq!(
+            reloaded.check("example.com", 64738, FINGERPRINT),
+            PinStatus::Trusted
+        );
+    }
+
+    #[test]
+    fn test_mismatch() {
+        let tmp_dir = tempdir().expect("Failed to create temp dir");
+        let mut store = PinnedCertificateStore::load_from(tmp_dir.path().join("pins.json"))
+            .expect("Failed to load store");
+
+        store
+            .pin("example.com", 64738, FINGERPRINT)
+            .expect("Failed to pin certificate");
+        assert_eq!(
+            store.check("example.com", 64738, "d4e5f6"),
+            PinStatus::Mismatch {
+                pinned: FINGERPRINT.to_string()
+            }
+        );
+
+        store
+            .pin("example.com", 64738, "d4e5f6")
+            .expect("Failed to accept certificate");
+        assert_eq!(
+            store.check("example.com", 64738, "d4e5f6"),
+            PinStatus::Trusted
+        );
+    }
+
+    #[test]
+    fn test_accept_presented_certificate() {
+        let tmp_dir = tempdir().expect("Failed to create temp dir");
+        let path = tmp_dir.path().join("pins.json");
+        let mut store =
+            PinnedCertificateStore::load_from(path.clone()).expect("Failed to load store");
+
+        store
+            .pin("example.com", 64738, FINGERPRINT)
+            .expect("Failed to pin certificate");
+        assert!(store.accept("example.com", 64738, "d4e5f6").is_err());
+
+        store
+            .remember_presented("example.com", 64738, "d4e5f6")
+            .expect("Failed to remember certificate");
+        let mut reloaded = PinnedCertificateStore::load_from(path).expect("Failed to reload store");
+        assert!(reloaded.accept("example.com", 64738, "0a0b0c").is_err());
+        assert_eq!(
+            reloaded.check("example.com", 64738, FINGERPRINT),
+            PinStatus::Trusted
+        );
+
+        reloaded
+            .accept("example.com", 64738, "d4e5f6")
+            .expect("Failed to accept certificate");
+        assert_eq!(
+            reloaded.check("example.com", 64738, "d4e5f6"),
+            PinStatus::Trusted
+        );
+        // accepting only works once for each presented certificate
+        assert!(reloaded.accept("example.com", 64738, "d4e5f6").is_err());
+    }
+}
@@ -0,0 +1,162 @@
+#[cfg(test)]
+mod tests {
+    use crate::utils::audio::{
+        decoder::{Decoder, UDPDecoder},
+        positional::{spatialize, stereo_gains, ListenerPose, Position},
+    };
+
+    const LISTENER: ListenerPose = ListenerPose {
+        position: Position {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        },
+        front: Position {
+            x: 0.0,
+            y: 0.0,
+            z: 1.0,
+        },
+        top: Position {
+            x: 0.0,
+            y: 1.0,
+            z: 0.0,
+        },
+    };
+
+    fn at(x: f32, y: f32, z: f32) -> Position {
+        Position { x, y, z }
+    }
+
+    #[test]
+    fn test_source_in_front_is_centered() {
+        let (left, right) = stereo_gains(&at(0.0, 0.0, 0.5), &LISTENER);
+
+        assert!((left - 1.0).abs() < f32::EPSILON);
+        assert!((right - 1.0).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn test_source_on_the_right() {
+        let (left, right) = stereo_gains(&at(0.5, 0.0, 0.0), &LISTENER);
+
+        assert!(left.abs() < f32::EPSILON);
+        assert!((right - 1.0).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn test_pan_is_relative_to_listener() {
+        let listener = ListenerPose {
+            position: at(2.0, 0.0, 0.0),
+            ..LISTENER
+        };
+        let (left, right) = stereo_gains(&at(1.5, 0.0, 0.0), &listener);
+
+        assert!(left > right);
+    }
+
+    #[test]
+    fn test_pan_follows_orientation() {
+        // looking along x, the z axis points to the left
+        let listener = ListenerPose {
+            front: at(1.0, 0.0, 0.0),
+            ..LISTENER
+        };
+        let (left, right) = stereo_gains(&at(0.0, 0.0, 0.5), &listener);
+        assert!((left - 1.0).abs() < f32::EPSILON);
+        assert!(right.abs() < f32::EPSILON);
+
+        let (left, right) = stereo_gains(&at(0.5, 0.0, 0.0), &listener);
+        assert!((left - right).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn test_height_does_not_pan() {
+        let (left, right) = stereo_gains(&at(0.0, 0.5, 0.0), &LISTENER);
+
+        assert!((left - 1.0).abs() < f32::EPSILON);
+        assert!((right - 1.0).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn test_invalid_orientation_falls_back() {
+        let listener = ListenerPose {
+            front: at(0.0, 0.0, 0.0),
+            top: at(0.0, 0.0, 0.0),
+            ..LISTENER
+        };
+
+        assert_eq!(
+            stereo_gains(&at(0.5, 0.0, 0.0), &listener),
+            stereo_gains(&at(0.5, 0.0, 0.0), &LISTENER)
+        );
+    }
+
+    #[test]
+    fn test_pose_defaults_orientation() {
+        let pose: ListenerPose =
+            serde_json::from_str(r#"{"position": {"x": 1.0, "y": 2.0, "z": 3.0}}"#)
+                .expect("Failed to parse pose");
+
+        assert_eq!(pose.position, at(1.0, 2.0, 3.0));
+        assert_eq!(pose.front, LISTENER.front);
+        assert_eq!(pose.top, LISTENER.top);
+    }
+
+    #[test]
+    fn test_distance_attenuation() {
+        let (near, _) = stereo_gains(&at(0.0, 0.0, 1.0), &LISTENER);
+        let (middle, _) = stereo_gains(&at(0.0, 0.0, 8.0), &LISTENER);
+        let (far, _) = stereo_gains(&at(0.0, 0.0, 15.0), &LISTENER);
+        let (beyond, _) = stereo_gains(&at(0.0, 0.0, 100.0), &LISTENER);
+
+        assert!(near > middle);
+        assert!(middle > far);
+        assert!((far - beyond).abs() < f32::EPSILON);
+        assert!(beyond > 0.0);
+    }
+
+    #[test]
+    fn test_spatialize_interleaves_channels() {
+        let stereo = spatialize(&[100, -200], (1.0, 0.5));
+
+        assert_eq!(stereo, vec![100, 50, -200, -100]);
+    }
+
+    /// Voice packet as a legacy mumble server relays it, with the position appended by the
+    /// `PacketDataStream` of the sender on x86: opus audio to the current channel, session 5,
+    /// sequence 1, a payload of 3 bytes and the position (1.5, -2.0, 10.25)
+    const POSITIONAL_PACKET: [u8; 20] = [
+        0x80, 0x05, 0x01, 0x80, 0x03, 0x01, 0x02, 0x03, // header, session, sequence, payload
+        0x00, 0x00, 0xc0, 0x3f, // x
+        0x00, 0x00, 0x00, 0xc0, // y
+        0x00, 0x00, 0x24, 0x41, // z
+    ];
+
+    #[test]
+    fn test_position_from_bytes() {
+        let bytes: Vec<u8> = [1.0f32, -2.5, 3.25]
+            .iter()
+            .flat_map(|coordinate| coordinate.to_le_bytes())
+            .collect();
+
+        assert_eq!(Position::from_le_bytes(&bytes), Some(at(1.0, -2.5, 3.25)));
+        assert_eq!(Position::from_le_bytes(&bytes[..8]), None);
+    }
+
+    #[test]
+    fn test_decoder_extracts_position() {
+        let decoded = UDPDecoder
+            .decode_audio(&POSITIONAL_PACKET[..8])
+            .expect("Failed to decode packet");
+        assert_eq!(decoded.position, None);
+
+        let decoded = UDPDecoder
+            .decode_audio(&POSITIONAL_PACKET)
+            .expect("Failed to decode packet");
+
+        assert_eq!(decoded.user_id, 5);
+        assert_eq!(decoded.sequence_number, 1);
+        assert_eq!(decoded.payload, vec![1, 2, 3]);
+        assert_eq!(decoded.position, Some(at(1.5, -2.0, 10.25)));
+    }
+}
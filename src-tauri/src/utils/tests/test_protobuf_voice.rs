@@ -0,0 +1,205 @@
+#[cfg(test)]
+mod tests {
+    use prost::Message;
+
+    use crate::{
+        mumble::udp_proto,
+        protocol::{from_components, VoiceFormat, UDP_AUDIO_MESSAGE, UDP_PING_MESSAGE},
+        utils::audio::{
+            decoder::{AudioContext, Decoder, ProtobufDecoder},
+            encoder::{Encoder, ProtobufEncoder},
+            microphone::DeviceConfig,
+            positional::Position,
+        },
+    };
+
+    const SESSION: u32 = 5;
+    const CONFIG: DeviceConfig = DeviceConfig {
+        channels: 1,
+        sample_rate: 48_000,
+        buffer_size: 960,
+    };
+
+    fn packet(audio: &udp_proto::Audio) -> Vec<u8> {
+        let mut packet = vec![UDP_AUDIO_MESSAGE];
+        packet.extend(audio.encode_to_vec());
+        packet
+    }
+
+    /// The server replaces the target with the context it was received in and adds the sender,
+    /// returns the relayed packet and the target the client sent to
+    fn relay(packet: &[u8]) -> (Vec<u8>, u32) {
+        assert_eq!(packet[0], UDP_AUDIO_MESSAGE);
+        let mut audio = udp_proto::Audio::decode(&packet[1..]).expect("Failed to parse packet");
+        let Some(udp_proto::audio::Header::Target(target)) = audio.header else {
+            panic!("Packet has no target: {:?}", audio.header);
+        };
+        audio.header = Some(udp_proto::audio::Header::Context(0));
+        audio.sender_session = SESSION;
+
+        (packet(&audio), target)
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn voice() -> Vec<f32> {
+        (0..960).map(|i| (i as f32 * 0.05).sin() * 0.5).collect()
+    }
+
+    #[test]
+    fn test_format_for_server_version() {
+        assert_eq!(
+            VoiceFormat::for_server_version(from_components(1, 4, 287)),
+            VoiceFormat::Legacy
+        );
+        assert_eq!(
+            VoiceFormat::for_server_version(from_components(1, 5, 0)),
+            VoiceFormat::Protobuf
+        );
+        assert_eq!(
+            VoiceFormat::for_server_version(from_components(1, 6, 1)),
+            VoiceFormat::Protobuf
+        );
+    }
+
+    #[test]
+    fn test_decode_audio() {
+        let audio = udp_proto::Audio {
+            header: Some(udp_proto::audio::Header::Context(0)),
+            sender_session: 5,
+            frame_number: 42,
+            opus_data: vec![1, 2, 3],
+            positional_data: vec![4.0, 0.0, -1.0],
+            ..Default::default()
+        };
+
+        let decoded = ProtobufDecoder
+            .decode_audio(&packet(&audio))
+            .expect("Failed to decode packet");
+
+        assert_eq!(decoded.user_id, 5);
+        assert_eq!(decoded.sequence_number, 42);
+        assert_eq!(decoded.payload, vec![1, 2, 3]);
+        assert!(decoded.talking);
+        assert_eq!(decoded.context, AudioContext::Normal);
+        assert_eq!(
+            decoded.position,
+            Some(Position {
+                x: 4.0,
+                y: 0.0,
+                z: -1.0
+            })
+        );
+    }
+
+    #[test]
+    fn test_decode_listener_context() {
+        let audio = udp_proto::Audio {
+            header: Some(udp_proto::audio::Header::Context(3)),
+            sender_session: 5,
+            opus_data: vec![1, 2, 3],
+            ..Default::default()
+        };
+
+        let decoded = ProtobufDecoder
+            .decode_audio(&packet(&audio))
+            .expect("Failed to decode packet");
+
+        assert_eq!(decoded.context, AudioContext::Listen);
+    }
+
+    #[test]
+    fn test_decode_terminator() {
+        let audio = udp_proto::Audio {
+            sender_session: 1,
+            is_terminator: true,
+            ..Default::default()
+        };
+
+        let decoded = ProtobufDecoder
+            .decode_audio(&packet(&audio))
+            .expect("Failed to decode packet");
+
+        assert!(!decoded.talking);
+        assert_eq!(decoded.position, None);
+    }
+
+    #[test]
+    fn test_reject_other_message_types() {
+        let mut ping = vec![UDP_PING_MESSAGE];
+        ping.extend(
+            udp_proto::Ping {
+                timestamp: 1,
+                ..Default::default()
+            }
+            .encode_to_vec(),
+        );
+
+        assert!(ProtobufDecoder.decode_audio(&ping).is_err());
+        assert!(ProtobufDecoder.decode_audio(&[]).is_err());
+    }
+
+    #[test]
+    fn test_transmission_ends_with_terminator() {
+        let mut encoder = ProtobufEncoder::new(CONFIG);
+        let mut sequence_number = 0;
+
+        for expected in 0..3 {
+            let packet = encoder
+                .encode_audio(&voice(), &mut sequence_number)
+                .expect("Voice wasn't encoded");
+            let (relayed, target) = relay(&packet);
+            let decoded = ProtobufDecoder
+                .decode_audio(&relayed)
+                .expect("Failed to decode packet");
+
+            assert_eq!(target, 0);
+            assert_eq!(decoded.user_id, SESSION);
+            assert_eq!(decoded.sequence_number, expected);
+            assert!(decoded.talking);
+            assert!(!decoded.payload.is_empty());
+        }
+
+        let terminator = encoder
+            .encode_audio(&[0.0; 960], &mut sequence_number)
+            .expect("Missing terminator");
+        let decoded = ProtobufDecoder
+            .decode_audio(&relay(&terminator).0)
+            .expect("Failed to decode terminator");
+        assert!(!decoded.talking);
+        assert_eq!(decoded.sequence_number, 3);
+
+        // nothing is sent while we are silent
+        assert!(encoder
+            .encode_audio(&[0.0; 960], &mut sequence_number)
+            .is_none());
+    }
+
+    #[test]
+    fn test_target_switch_ends_transmission() {
+        let mut encoder = ProtobufEncoder::new(CONFIG);
+        let mut sequence_number = 0;
+
+        // not talking yet, so nothing has to be ended
+        assert!(encoder.set_target(1, &mut sequence_number).is_none());
+        let packet = encoder
+            .encode_audio(&voice(), &mut sequence_number)
+            .expect("Voice wasn't encoded");
+        assert_eq!(relay(&packet).1, 1);
+
+        let terminator = encoder
+            .set_target(0, &mut sequence_number)
+            .expect("Missing terminator");
+        // the terminator still goes to the previous target
+        let (relayed, target) = relay(&terminator);
+        assert_eq!(target, 1);
+        let decoded = ProtobufDecoder
+            .decode_audio(&relayed)
+            .expect("Failed to decode terminator");
+        assert!(!decoded.talking);
+
+        let packet = encoder
+            .encode_audio(&voice(), &mut sequence_number)
+            .expect("Voice wasn't encoded");
+        assert_eq!(relay(&packet).1, 0);
+    }
+}
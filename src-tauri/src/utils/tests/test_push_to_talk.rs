@@ -0,0 +1,64 @@
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use device_query::Keycode;
+
+    use crate::utils::{audio::processing::push_to_talk::PushToTalk, shortcut::Shortcut};
+
+    fn shortcut() -> Option<Shortcut> {
+        Some("Ctrl+T".parse().expect("Failed to parse shortcut"))
+    }
+
+    const HELD: [Keycode; 2] = [Keycode::LControl, Keycode::T];
+
+    #[test]
+    fn test_transmits_while_held() {
+        let mut push_to_talk = PushToTalk::new(shortcut(), false, Duration::ZERO, false);
+        let now = Instant::now();
+
+        assert_eq!(push_to_talk.update(&[], now), None);
+        assert_eq!(push_to_talk.update(&HELD, now), Some(true));
+        assert_eq!(push_to_talk.update(&HELD, now), None);
+        assert_eq!(push_to_talk.update(&[Keycode::T], now), Some(false));
+    }
+
+    #[test]
+    fn test_release_delay() {
+        let mut push_to_talk =
+            PushToTalk::new(shortcut(), false, Duration::from_millis(200), false);
+        let now = Instant::now();
+
+        assert_eq!(push_to_talk.update(&HELD, now), Some(true));
+        assert_eq!(push_to_talk.update(&[], now), None);
+        assert_eq!(
+            push_to_talk.update(&[], now + Duration::from_millis(100)),
+            None
+        );
+        assert_eq!(
+            push_to_talk.update(&[], now + Duration::from_millis(200)),
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn test_toggle() {
+        let mut push_to_talk = PushToTalk::new(shortcut(), true, Duration::ZERO, false);
+        let now = Instant::now();
+
+        assert_eq!(push_to_talk.update(&HELD, now), Some(true));
+        assert_eq!(push_to_talk.update(&[], now), None);
+        assert_eq!(push_to_talk.update(&HELD, now), Some(false));
+        assert_eq!(push_to_talk.update(&HELD, now), None);
+    }
+
+    #[test]
+    fn test_mutes_without_shortcut() {
+        let mut push_to_talk = PushToTalk::new(None, false, Duration::ZERO, false);
+        let mut data = vec![0.5f32; 4];
+
+        assert_eq!(push_to_talk.update(&HELD, Instant::now()), None);
+        push_to_talk.process(&mut data);
+        assert!(data.iter().all(|sample| sample.abs() < f32::EPSILON));
+    }
+}
@@ -0,0 +1,88 @@
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use crate::utils::audio::processing::resampler::Resampler;
+
+    const TARGET_RATE: u32 = 48_000;
+    const CHUNK_SIZE: usize = 1024;
+
+    #[allow(clippy::cast_precision_loss)]
+    fn sine(frequency: f32, sample_rate: u32, samples: usize) -> Vec<f32> {
+        (0..samples)
+            .map(|n| (2.0 * PI * frequency * n as f32 / sample_rate as f32).sin())
+            .collect()
+    }
+
+    fn resample(resampler: &mut Resampler, input: &[f32]) -> Vec<f32> {
+        input
+            .chunks(CHUNK_SIZE)
+            .flat_map(|chunk| resampler.process(chunk))
+            .collect()
+    }
+
+    // skips the start, where the filter still sees the silence before the signal
+    fn max_error(output: &[f32], expected: &[f32]) -> f32 {
+        output
+            .iter()
+            .zip(expected)
+            .skip(100)
+            .map(|(a, b)| (a - b).abs())
+            .fold(0.0, f32::max)
+    }
+
+    #[test]
+    fn test_44_1khz_to_48khz() {
+        let mut resampler = Resampler::new(44_100, TARGET_RATE, 1);
+        let output = resample(&mut resampler, &sine(1000.0, 44_100, 44_100));
+
+        assert!(output.len().abs_diff(48_000) < 100);
+        let expected = sine(1000.0, TARGET_RATE, output.len());
+        assert!(max_error(&output, &expected) < 0.01);
+    }
+
+    #[test]
+    fn test_96khz_to_48khz() {
+        let mut resampler = Resampler::new(96_000, TARGET_RATE, 1);
+        let output = resample(&mut resampler, &sine(1000.0, 96_000, 96_000));
+
+        assert!(output.len().abs_diff(48_000) < 100);
+        let expected = sine(1000.0, TARGET_RATE, output.len());
+        assert!(max_error(&output, &expected) < 0.01);
+    }
+
+    #[test]
+    fn test_96khz_filters_above_nyquist() {
+        let mut resampler = Resampler::new(96_000, TARGET_RATE, 1);
+        let output = resample(&mut resampler, &sine(30_000.0, 96_000, 96_000));
+
+        let silence = vec![0.0; output.len()];
+        assert!(max_error(&output, &silence) < 0.05);
+    }
+
+    #[test]
+    fn test_stereo_downmix() {
+        let left = sine(1000.0, TARGET_RATE, 4800);
+        let stereo = left
+            .iter()
+            .flat_map(|sample| [*sample, *sample])
+            .collect::<Vec<_>>();
+        let mut resampler = Resampler::new(TARGET_RATE, TARGET_RATE, 2);
+        let output = resample(&mut resampler, &stereo);
+
+        assert_eq!(output, left);
+    }
+
+    #[test]
+    fn test_stereo_44_1khz_cancels_opposite_channels() {
+        let stereo = sine(1000.0, 44_100, 4410)
+            .iter()
+            .flat_map(|sample| [*sample, -*sample])
+            .collect::<Vec<_>>();
+        let mut resampler = Resampler::new(44_100, TARGET_RATE, 2);
+        let output = resample(&mut resampler, &stereo);
+
+        assert!(output.len().abs_diff(4800) < 100);
+        assert!(output.iter().all(|sample| sample.abs() < f32::EPSILON));
+    }
+}
@@ -0,0 +1,53 @@
+#[cfg(test)]
+mod tests {
+    use device_query::Keycode;
+
+    use crate::utils::shortcut::Shortcut;
+
+    #[test]
+    fn test_parse_single_key() {
+        let shortcut = "F5".parse::<Shortcut>().expect("Failed to parse shortcut");
+
+        assert!(shortcut.is_held(&[Keycode::F5]));
+        assert!(!shortcut.is_held(&[Keycode::F6]));
+    }
+
+    #[test]
+    fn test_modifiers_match_both_sides() {
+        let shortcut = "Ctrl+Shift+W"
+            .parse::<Shortcut>()
+            .expect("Failed to parse shortcut");
+
+        assert!(shortcut.is_held(&[Keycode::LControl, Keycode::LShift, Keycode::W]));
+        assert!(shortcut.is_held(&[Keycode::RControl, Keycode::RShift, Keycode::W]));
+        assert!(!shortcut.is_held(&[Keycode::LControl, Keycode::W]));
+    }
+
+    #[test]
+    fn test_additional_keys_are_ignored() {
+        let shortcut = "alt + 1"
+            .parse::<Shortcut>()
+            .expect("Failed to parse shortcut");
+
+        assert!(shortcut.is_held(&[Keycode::A, Keycode::RAlt, Keycode::Key1]));
+    }
+
+    #[test]
+    fn test_meta_modifier() {
+        let shortcut = "Meta+Backspace"
+            .parse::<Shortcut>()
+            .expect("Failed to parse shortcut");
+
+        assert!(shortcut.is_held(&[Keycode::RMeta, Keycode::Backspace]));
+        assert!(!shortcut.is_held(&[Keycode::Backspace]));
+    }
+
+    #[test]
+    fn test_unknown_key() {
+        assert!("Ctrl+Foo".parse::<Shortcut>().is_err());
+        assert!("".parse::<Shortcut>().is_err());
+        // shifted or non-ASCII characters have no key of their own
+        assert!("Shift+!".parse::<Shortcut>().is_err());
+        assert!("Ö".parse::<Shortcut>().is_err());
+    }
+}
@@ -17,6 +17,7 @@ export interface ServerInfo {
     port: string;
     username: string;
     identity?: string;
+    password?: string;
 }
 
 interface AddNewServerProps {
@@ -42,6 +43,7 @@ function AddNewServer({ serverInfo, identityCerts, onSave, onConnect }: Readonly
     const [server, setServer] = useState("magical.rocks");
     const [port, setPort] = useState("64738");
     const [username, setUsername] = useState("Endor");
+    const [password, setPassword] = useState("");
     const [t, i18n] = useTranslation();
 
     let identityCertsObj = identityCerts.map(e => { return { name: e } });
@@ -127,7 +129,7 @@ function AddNewServer({ serverInfo, identityCerts, onSave, onConnect }: Readonly
             return;
         }
 
-        action({ description, server, port, username, identity: identity?.name }).then(() => {
+        action({ description, server, port, username, identity: identity?.name, password: password || undefined }).then(() => {
             // Do nothing
         }).finally(() => {
             setSaving(false);
@@ -161,6 +163,9 @@ function AddNewServer({ serverInfo, identityCerts, onSave, onConnect }: Readonly
                     <Grid item={true} xs={12}>
                         <TextField fullWidth label={t("Username")} value={username} onChange={e => setUsername(e.target.value)} />
                     </Grid>
+                    <Grid item={true} xs={12} mt={2}>
+                        <TextField fullWidth type="password" label={t("Password")} value={password} onChange={e => setPassword(e.target.value)} />
+                    </Grid>
                     {additionalOptions}
                     <Grid item={true} xs={6} container justifyContent="flex-start">
                         <Box mt={2}>
@@ -7,6 +7,8 @@ import { useEffect, useMemo } from 'react';
 import AutoStoriesIcon from '@mui/icons-material/AutoStories';
 import KeyboardDoubleArrowRightIcon from '@mui/icons-material/KeyboardDoubleArrowRight';
 import React from 'react';
+import SyncProblemIcon from '@mui/icons-material/SyncProblem';
+import { useTranslation } from 'react-i18next';
 import { updateUIState } from '../store/features/users/frontendSettings';
 import { persistFrontendSettings } from '../store/persistance/persist';
 
@@ -16,12 +18,14 @@ interface ChatInfoBarProps {
 
 const ChatInfoBar: React.FC<ChatInfoBarProps> = React.memo(({ onShowLog }) => {
     const dispatch = useDispatch();
+    const { t } = useTranslation();
     const frontendSettings = useSelector((state: RootState) => state.reducer.frontendSettings);
     const showSidebar = frontendSettings.ui_state.show_sidebar;
 
 
     const currentChannelId = useSelector((state: RootState) => state.reducer.userInfo.currentUser?.channel_id);
     const channelInfo = useSelector((state: RootState) => state.reducer.channel.find(e => e.channel_id === currentChannelId));
+    const reconnecting = useSelector((state: RootState) => state.reducer.server.reconnecting);
 
     const eventLogIcon = useMemo(() => {
         if (!showSidebar) return (<AutoStoriesIcon sx={{ fontSize: 20 }} />);
@@ -50,6 +54,11 @@ const ChatInfoBar: React.FC<ChatInfoBarProps> = React.memo(({ onShowLog }) => {
                     </Box>
                     <Box sx={{ flexGrow: 0 }}>
                         <Box sx={{ display: 'flex', flexDirection: 'row', alignItems: 'center' }}>
+                            {reconnecting ? (
+                                <Tooltip title={t('Reconnecting', { ns: 'user_interaction', attempt: reconnecting.attempt, seconds: Math.round(reconnecting.delay_ms / 1000) })}>
+                                    <SyncProblemIcon color="warning" sx={{ fontSize: 20 }} />
+                                </Tooltip>
+                            ) : null}
                             <Tooltip title={showSidebar ? 'Hide Log' : 'Show Log'}>
                                 <IconButton size="small" onClick={() => toggleSidebar()}>
                                     {eventLogIcon}
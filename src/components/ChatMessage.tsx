@@ -8,6 +8,8 @@ import ThumbUpOffAltIcon from '@mui/icons-material/ThumbUpOffAlt';
 import { invoke } from "@tauri-apps/api";
 import { TextMessage, deleteChatMessage } from "../store/features/users/chatMessageSlice";
 import ClearIcon from '@mui/icons-material/Clear';
+import ScheduleIcon from '@mui/icons-material/Schedule';
+import ErrorOutlineIcon from '@mui/icons-material/ErrorOutline';
 import { useDispatch, useSelector } from "react-redux";
 import React, { useEffect } from "react";
 import { RootState } from "../store/store";
@@ -125,6 +127,21 @@ const ChatMessage: React.FC<ChatMessageProps> = React.memo(({ message, messageId
         </Grid>);
     }, [parsedMessage]);
 
+    const stateIndicator = React.useMemo(() => {
+        switch (message.state) {
+            case 'pending':
+                return (<Tooltip title={t("Message Pending", { ns: "user_interaction" })}>
+                    <ScheduleIcon fontSize="small" color="disabled" />
+                </Tooltip>);
+            case 'failed':
+                return (<Tooltip title={t("Message Failed", { ns: "user_interaction" })}>
+                    <ErrorOutlineIcon fontSize="small" color="error" />
+                </Tooltip>);
+            default:
+                return null;
+        }
+    }, [message.state, t]);
+
     return (
         <Grid item xs={10} className="message-container">
             {messageElement}
@@ -132,8 +149,9 @@ const ChatMessage: React.FC<ChatMessageProps> = React.memo(({ message, messageId
                 <Typography variant="subtitle2" className="metadata">
                     <Link className="user-info" href="#">{message.sender.user_name}</Link> - {date}
                 </Typography>
+                {stateIndicator}
                 <Tooltip title={t("Like")}>
-                    <IconButton aria-label="Example" size="small" onClick={e => likeMessage(message.id)}>
+                    <IconButton aria-label="Example" size="small" disabled={!message.id} onClick={e => message.id && likeMessage(message.id)}>
                         <ThumbUpOffAltIcon fontSize="small" color="disabled" />
                     </IconButton>
                 </Tooltip>
@@ -5,17 +5,19 @@ import { useEffect, useState } from "react";
 import KeyboardIcon from '@mui/icons-material/Keyboard';
 import FloatingApply from "./components/FloatingApply";
 import { listen } from "@tauri-apps/api/event";
-import { InputMode, setAmplification, setAttackTime, setCompressorRatio, setCompressorThreshold, setFadeOutDuration, setInputMode, setReleaseTime, setVoiceHold, setVoiceHysteresis } from "../../store/features/users/audioSettings";
+import { InputMode, setInputDevice, setOutputDevice, setAmplification, setAttackTime, setAudioCue, setCompressorRatio, setCompressorThreshold, setFadeOutDuration, setInputMode, setNoiseSuppression, setPositionalAudio, setPushToTalkShortcut, setReleaseDelay, setReleaseTime, setVoiceHold, setVoiceHysteresis } from "../../store/features/users/audioSettings";
 import { RootState } from "../../store/store";
 import { useDispatch, useSelector } from "react-redux";
 import { useTranslation } from "react-i18next";
 import { persistAudioSettings } from "../../store/persistance/persist";
 
 function AudioSettings() {
-    let [inputDevice, setInputDevice] = useState('');
     let [inputDeviceList, setInputDeviceList] = useState([]);
+    let [outputDeviceList, setOutputDeviceList] = useState([]);
     const [advancedOptions, showAdvanceOptions] = useState(true);
     const [audioLevel, setAudioLevel] = useState<number>(10);
+    const [recordingShortcut, setRecordingShortcut] = useState(false);
+    const [shortcutError, setShortcutError] = useState<string | null>(null);
     const audioSettings = useSelector((state: RootState) => state.reducer.audioSettings);
     const dispatch = useDispatch();
     const [t, i18n] = useTranslation();
@@ -32,11 +34,70 @@ function AudioSettings() {
         }
     }, []);
 
+    useEffect(() => {
+        if (!recordingShortcut) {
+            return;
+        }
+
+        const recordShortcut = (event: KeyboardEvent) => {
+            event.preventDefault();
+            if (/^(Control|Shift|Alt|Meta)(Left|Right)$/.test(event.code)) {
+                return;
+            }
+
+            let keys = [];
+            if (event.ctrlKey) keys.push("Ctrl");
+            if (event.shiftKey) keys.push("Shift");
+            if (event.altKey) keys.push("Alt");
+            if (event.metaKey) keys.push("Meta");
+            // the physical key, so Shift+1 stays "1" and the keyboard layout doesn't matter
+            keys.push(event.code.replace(/^(Key|Digit|Arrow)/, ""));
+
+            const shortcut = keys.join("+");
+            invoke('validate_shortcut', { shortcut })
+                .then(() => {
+                    dispatch(setPushToTalkShortcut(shortcut));
+                    setShortcutError(null);
+                })
+                .catch(() => setShortcutError(t("Unsupported Shortcut", { ns: "audio", shortcut })))
+                .finally(() => setRecordingShortcut(false));
+        };
+
+        window.addEventListener('keydown', recordShortcut);
+        return () => window.removeEventListener('keydown', recordShortcut);
+    }, [recordingShortcut]);
+
     function getAudioDevices() {
         invoke('get_audio_devices')
             .then((devices: any) => {
                 setInputDeviceList(devices);
-            })
+            });
+        invoke('get_audio_output_devices')
+            .then((devices: any) => {
+                setOutputDeviceList(devices);
+            });
+    }
+
+    useEffect(() => {
+        getAudioDevices();
+    }, []);
+
+    function selectInputDevice(device: string) {
+        dispatch(setInputDevice(device));
+        invoke('set_input_device', { device: device || null });
+        persistAudioSettings({ ...audioSettings, input_device: device });
+    }
+
+    function selectOutputDevice(device: string) {
+        dispatch(setOutputDevice(device));
+        invoke('set_output_device', { device: device || null });
+        persistAudioSettings({ ...audioSettings, output_device: device });
+    }
+
+    function togglePositionalAudio(enabled: boolean) {
+        dispatch(setPositionalAudio(enabled));
+        invoke('set_audio_output_setting', { 'settings': { 'positional_audio': enabled } });
+        persistAudioSettings({ ...audioSettings, positional_audio: enabled });
     }
 
     function saveAudioSettings() {
@@ -49,15 +110,22 @@ function AudioSettings() {
                 voice_hysteresis_lower_threshold: audioSettings.voice_activation_options.voice_hysteresis_lower_threshold,
                 voice_hysteresis_upper_threshold: audioSettings.voice_activation_options.voice_hysteresis_upper_threshold,
             },
+            push_to_talk_options: {
+                shortcut: audioSettings.push_to_talk_options.shortcut,
+                release_delay: Math.floor(audioSettings.push_to_talk_options.release_delay),
+                audio_cue: audioSettings.push_to_talk_options.audio_cue,
+            },
             compressor_options: {
                 threshold: audioSettings.compressor_options.threshold,
                 ratio: audioSettings.compressor_options.ratio,
                 attack_time: Math.floor(audioSettings.compressor_options.attack_time),
                 release_time: Math.floor(audioSettings.compressor_options.release_time),
-            }
+            },
+            noise_suppression: audioSettings.noise_suppression
         };
-        invoke('set_audio_input_setting', { 'settings': settings });
-        persistAudioSettings({...audioSettings});
+        invoke('set_audio_input_setting', { 'settings': settings })
+            .then(() => persistAudioSettings({...audioSettings}))
+            .catch(e => setShortcutError(String(e)));
     }
 
     function calculateVoiceHold(value: number) {
@@ -110,19 +178,43 @@ function AudioSettings() {
                 <Select
                     labelId="demo-simple-select-helper-label"
                     id="demo-simple-select-helper"
-                    value={inputDevice}
+                    value={audioSettings.input_device}
                     label={t("Microphone", { ns: "audio" })}
-                    onChange={(e) => setInputDevice(e.target.value as any)}
+                    onChange={(e) => selectInputDevice(e.target.value as string)}
                 >
                     <MenuItem value="">
-                        <em>{t("None")}</em>
+                        <em>{t("Default Device", { ns: "audio" })}</em>
                     </MenuItem>
                     {inputDeviceList.map((value) => {
-                        return (<MenuItem value={value}>{value}</MenuItem>);
+                        return (<MenuItem key={value} value={value}>{value}</MenuItem>);
+                    })}
+                </Select>
+            </FormControl>
+            <FormControl sx={{ m: 1, minWidth: 120, justifyContent: 'center' }} size="small">
+                <InputLabel id="output-device-select-label">{t("Speaker", { ns: "audio" })}</InputLabel>
+                <Select
+                    labelId="output-device-select-label"
+                    id="output-device-select"
+                    value={audioSettings.output_device}
+                    label={t("Speaker", { ns: "audio" })}
+                    onChange={(e) => selectOutputDevice(e.target.value as string)}
+                >
+                    <MenuItem value="">
+                        <em>{t("Default Device", { ns: "audio" })}</em>
+                    </MenuItem>
+                    {outputDeviceList.map((value) => {
+                        return (<MenuItem key={value} value={value}>{value}</MenuItem>);
                     })}
                 </Select>
             </FormControl>
             <IconButton color="primary" onClick={getAudioDevices}><LoopIcon /></IconButton >
+            <Box>
+                <FormControlLabel
+                    control={<Switch checked={audioSettings.positional_audio} onChange={(e, checked) => togglePositionalAudio(checked)} />}
+                    label={t("Positional Audio", { ns: "audio" })}
+                />
+                <Typography color="text.secondary" variant="caption">{t("Positional Audio Limitation", { ns: "audio" })}</Typography>
+            </Box>
             <Divider sx={{ my: 4 }} />
             <Box>
                 <Box mt={2} mb={2}>
@@ -135,6 +227,7 @@ function AudioSettings() {
                     >
                         <FormControlLabel value={InputMode.VoiceActivation} control={<Radio />} label={t("Voice Activation", { ns: "audio" })} />
                         <FormControlLabel value={InputMode.PushToTalk} control={<Radio />} label={t("Push To Talk", { ns: "audio" })} />
+                        <FormControlLabel value={InputMode.ToggleToTalk} control={<Radio />} label={t("Toggle To Talk", { ns: "audio" })} />
                     </RadioGroup>
                 </Box>
                 <Collapse in={audioSettings.input_mode === InputMode.VoiceActivation}>
@@ -207,32 +300,46 @@ function AudioSettings() {
                         </Grid>
                     </Collapse>
                 </Collapse>
-                <Collapse in={audioSettings.input_mode === InputMode.PushToTalk}>
+                <Collapse in={audioSettings.input_mode === InputMode.PushToTalk || audioSettings.input_mode === InputMode.ToggleToTalk}>
                     <Paper
                         component="form"
                         sx={{ p: '2px 4px', display: 'flex', alignItems: 'center', width: 400 }}
                     >
                         <InputBase
                             sx={{ ml: 1, flex: 1 }}
-                            placeholder="Record Button..."
+                            placeholder={recordingShortcut ? t("Press a key", { ns: "audio" }) : "Record Button..."}
                             inputProps={{ 'aria-label': 'select button' }}
+                            value={recordingShortcut ? "" : audioSettings.push_to_talk_options.shortcut}
                             disabled
                         />
                         <Divider sx={{ height: 28, m: 0.5 }} orientation="vertical" />
-                        <IconButton color="primary" sx={{ p: '10px' }} aria-label="select button">
+                        <IconButton color={recordingShortcut ? "secondary" : "primary"} sx={{ p: '10px' }} aria-label="select button" onClick={() => setRecordingShortcut(!recordingShortcut)}>
                             <KeyboardIcon />
                         </IconButton>
                     </Paper>
+                    {shortcutError && <Typography color="error" variant="body2">{shortcutError}</Typography>}
+                    <Typography color="text.secondary" variant="caption">{t("Shortcut Limitation", { ns: "audio" })}</Typography>
 
-                    <Slider
-                        min={0}
-                        step={1}
-                        max={60}
-                        scale={calculateVoiceHold}
-                        getAriaValueText={valueLabelFormat}
-                        valueLabelFormat={valueLabelFormat}
-                        valueLabelDisplay="auto"
-                        aria-labelledby="non-linear-slider"
+                    <Collapse in={audioSettings.input_mode === InputMode.PushToTalk}>
+                        <Typography id="non-linear-slider" gutterBottom>
+                            {t("Release Delay", { ns: "audio", duration: valueLabelFormat(audioSettings.push_to_talk_options.release_delay) })}
+                        </Typography>
+                        <Slider
+                            value={calculateVoiceHoldInverse(audioSettings.push_to_talk_options.release_delay)}
+                            min={0}
+                            step={1}
+                            max={40}
+                            scale={calculateVoiceHold}
+                            getAriaValueText={valueLabelFormat}
+                            valueLabelFormat={valueLabelFormat}
+                            onChange={(e, value) => dispatch(setReleaseDelay(calculateVoiceHold(value as number)))}
+                            valueLabelDisplay="auto"
+                            aria-labelledby="non-linear-slider"
+                        />
+                    </Collapse>
+                    <FormControlLabel
+                        control={<Switch checked={audioSettings.push_to_talk_options.audio_cue} onChange={(e, checked) => dispatch(setAudioCue(checked))} />}
+                        label={t("Audio Cue", { ns: "audio" })}
                     />
                 </Collapse>
             </Box>
@@ -333,17 +440,10 @@ function AudioSettings() {
                 </RadioGroup>
             </Box>
             <Box sx={{ my: 4 }}>
-                <Typography id="non-linear-slider" gutterBottom>
-                    {t("Noise Suppression", { ns: "audio" })}
-                </Typography>
-                <RadioGroup
-                    aria-labelledby="demo-radio-buttons-group-label"
-                    defaultValue={1}
-                    name="radio-buttons-group"
-                >
-                    <FormControlLabel value={0} control={<Radio />} label={t("Feature Not Implemented", { feature: t("Noise Suppression", { ns: "audio" }) })} />
-                    <FormControlLabel value={1} control={<Radio />} label={t("Feature Not Implemented", { feature: t("Noise Suppression", { ns: "audio" }) })} />
-                </RadioGroup>
+                <FormControlLabel
+                    control={<Switch checked={audioSettings.noise_suppression} onChange={(e, checked) => dispatch(setNoiseSuppression(checked))} />}
+                    label={t("Noise Suppression", { ns: "audio" })}
+                />
             </Box>
             <Divider sx={{ my: 4 }} />
             <FloatingApply discardText={t("Discard", { ns: "user_interaction" })} saveText={t("Apply", { ns: "user_interaction" })} onDiscard={() => { }} onSave={() => saveAudioSettings()} />
@@ -1,10 +1,12 @@
-import { deleteUser, updateConnected, updateCurrentUserById, updateUser, updateUserComment, updateUserImage, updateUserTalkingInfo } from '../store/features/users/userSlice';
+import { deleteUser, updateConnected, updateCurrentUserById, updateUser, updateUserComment, updateUserImage, updateUserTalkingInfo, updateCurrentUserTalkingInfo } from '../store/features/users/userSlice';
 import { updateChannel, updateChannelDescription } from '../store/features/users/channelSlice';
 import { Event } from '@tauri-apps/api/event';
-import { addChatMessage } from '../store/features/users/chatMessageSlice';
+import { addChatHistory, addChatMessage, updateChatMessageState } from '../store/features/users/chatMessageSlice';
 import { Dispatch } from 'react';
 import { AnyAction } from '@reduxjs/toolkit';
-import { ServerSync, updateServerInfo } from '../store/features/server/serverSlice';
+import { ReconnectInfo, ServerSync, updateReconnecting, updateServerInfo } from '../store/features/server/serverSlice';
+import { eventLogSlice } from '../store/features/users/eventLogReducer';
+import i18next from 'i18next';
 import { invoke } from '@tauri-apps/api/tauri';
 import { useNavigate } from 'react-router-dom';
 
@@ -13,6 +15,8 @@ enum MessageTypes {
     Disconnected = "disconnected",
     Ping = "Ping",
     TextMessage = "text_message",
+    TextMessageState = "text_message_state",
+    ChatHistory = "chat_history",
     UserList = "user_list",
     UserImage = "user_image",
     UserComment = "user_comment",
@@ -22,8 +26,11 @@ enum MessageTypes {
     ChannelDescription = "channel_description",
     NotifyCurrentUser = "current_user_id",
     AudioInfo = "audio_info",
+    PushToTalk = "push_to_talk",
     SyncInfo = "sync_info",
-    PingTimeout = "ping_timeout"
+    PingTimeout = "ping_timeout",
+    Reconnecting = "reconnecting",
+    Reconnected = "reconnected"
 }
 
 interface BackendMessage {
@@ -47,10 +54,31 @@ export function handleBackendMessage<T>(event: Event<T>, dispatch: Dispatch<AnyA
             invoke('logout');
             break;
         }
+        case MessageTypes.Reconnecting: {
+            let data = message.data as ReconnectInfo;
+            dispatch(updateReconnecting(data));
+            dispatch(eventLogSlice.actions.dispatchEventLog({
+                message: i18next.t("Reconnecting", { ns: "user_interaction", attempt: data.attempt, seconds: Math.round(data.delay_ms / 1000) })
+            }));
+            break;
+        }
+        case MessageTypes.Reconnected: {
+            dispatch(updateReconnecting(undefined));
+            dispatch(eventLogSlice.actions.dispatchEventLog({ message: i18next.t("Reconnected", { ns: "user_interaction" }) }));
+            break;
+        }
         case MessageTypes.TextMessage: {
             dispatch(addChatMessage(message.data));
             break;
         }
+        case MessageTypes.ChatHistory: {
+            dispatch(addChatHistory(message.data));
+            break;
+        }
+        case MessageTypes.TextMessageState: {
+            dispatch(updateChatMessageState(message.data));
+            break;
+        }
         case MessageTypes.UserImage: {
             dispatch(updateUserImage(message.data));
             break;
@@ -84,6 +112,10 @@ export function handleBackendMessage<T>(event: Event<T>, dispatch: Dispatch<AnyA
             dispatch(updateUserTalkingInfo(message.data));
             break;
         }
+        case MessageTypes.PushToTalk: {
+            dispatch(updateCurrentUserTalkingInfo(message.data.transmitting));
+            break;
+        }
         case MessageTypes.SyncInfo: {
             let data = message.data as ServerSync;
             if (data.session !== undefined) {
@@ -19,19 +19,8 @@ export class ChatMessageHandler {
     }
 
     public sendCustomChatMessage(data: string, userInfo: UsersState | undefined) {
+        // the backend adds the message to the log, so it is shown as pending until the server processed it
         invoke('send_message', { chatMessage: data, channelId: userInfo?.channel_id });
-        this.pushChatMessage({
-            actor: userInfo?.id ?? 0,
-            sender: {
-                user_id: userInfo?.id ?? 0,
-                user_name: userInfo?.name ?? 'unknown'
-            },
-            channel_id: [0],
-            tree_id: [0],
-            message: data,
-            timestamp: Date.now(),
-            id: ""
-        });
         this.setChatMessage("");
     }
 
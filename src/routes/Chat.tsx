@@ -1,4 +1,4 @@
-import { Box } from '@mui/material';
+import { Alert, Box } from '@mui/material';
 
 import ChatMessageContainer from '../components/ChatMessageContainer';
 
@@ -17,11 +17,14 @@ import { updateAudioSettings } from '../store/features/users/audioSettings';
 import { invoke } from '@tauri-apps/api';
 import i18n from '../i18n/i18n';
 import { updateCurrentUserListeningInfo } from '../store/features/users/userSlice';
+import { useTranslation } from 'react-i18next';
 
 
 function Chat() {
     const [showLog, setShowLog] = useState(false);
     const [loading, setLoading] = useState(false);
+    const [audioSettingsError, setAudioSettingsError] = useState('');
+    const { t } = useTranslation();
 
     const messageLog = useSelector((state: RootState) => state.reducer.chatMessage);
     const useWYSIWYG = useSelector((state: RootState) => state.reducer.frontendSettings?.advancedSettings?.useWYSIWYG);
@@ -30,13 +33,23 @@ function Chat() {
     const fetchSettings = useCallback(async () => {
         const frontendSettings = await persistentStorage.get<FrontendSettings>("frontendSettings");
         const audioSettings = await persistentStorage.get("audioSettings");
-        invoke('set_audio_input_setting', { 'settings': audioSettings });
+        // rejected settings, e.g. with an invalid shortcut, leave voice activation in place,
+        // so we stay muted instead of transmitting while the user expects push to talk
+        const inputAccepted = await invoke('set_audio_input_setting', { 'settings': audioSettings })
+            .then(() => true)
+            .catch(e => {
+                setAudioSettingsError(String(e));
+                return false;
+            });
+        invoke('set_input_device', { device: (audioSettings as any)?.input_device || null });
+        invoke('set_output_device', { device: (audioSettings as any)?.output_device || null });
+        invoke('set_audio_output_setting', { 'settings': { 'positional_audio': (audioSettings as any)?.positional_audio ?? false } });
 
         dispatch(updateFrontendSettings(frontendSettings));
         dispatch(updateAudioSettings(audioSettings));
         console.log("Settings fetched: ", frontendSettings);
 
-        let self_mute = frontendSettings?.user_state.self_mute ?? true;
+        let self_mute = !inputAccepted || (frontendSettings?.user_state.self_mute ?? true);
         let self_deaf = frontendSettings?.user_state.self_deaf ?? true;
         dispatch(updateCurrentUserListeningInfo({
             self_mute: self_mute,
@@ -79,6 +92,11 @@ function Chat() {
                         zIndex: -1
                     }}></Box>
                     <ChatInfoBar onShowLog={setShowLog} />
+                    {audioSettingsError ? (
+                        <Alert severity="error" onClose={() => setAudioSettingsError('')}>
+                            {t('Audio Settings Rejected', { ns: 'audio', error: audioSettingsError })}
+                        </Alert>
+                    ) : null}
                     <ChatMessageContainer messages={messageLog}></ChatMessageContainer>
                     {selectChatInput}
                 </Box>
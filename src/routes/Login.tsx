@@ -1,8 +1,9 @@
 import React, { useEffect, useState } from 'react'
 import '../App.css';
 import './styles/Login.css';
-import { Accordion, AccordionDetails, AccordionSummary, Alert, Avatar, Box, IconButton, LinearProgress, List, ListItem, ListItemAvatar, ListItemButton, ListItemText, MenuItem, Typography, Menu } from '@mui/material'
+import { Accordion, AccordionDetails, AccordionSummary, Alert, Avatar, Box, Button, IconButton, LinearProgress, List, ListItem, ListItemAvatar, ListItemButton, ListItemText, MenuItem, Typography, Menu } from '@mui/material'
 import { invoke } from '@tauri-apps/api/tauri'
+import { listen } from '@tauri-apps/api/event';
 import { useLocation, useNavigate } from 'react-router-dom';
 import { useDispatch, useSelector } from 'react-redux';
 import { RootState } from '../store/store';
@@ -17,7 +18,15 @@ interface ServerEntry {
     host: string,
     port: number,
     username: string,
-    identity?: string
+    identity?: string,
+    password?: string
+}
+
+interface CertificateMismatch {
+    host: string,
+    port: number,
+    pinned: string,
+    presented: string
 }
 
 function Login() {
@@ -29,6 +38,8 @@ function Login() {
     const [connecting, setConnecting] = useState(false);
     const [errorInfo, setErrorInfo] = useState({ show: false, text: "" });
     const [serverInfo, setServerInfo] = useState({ show: false, text: "" });
+    const [certificateMismatch, setCertificateMismatch] = useState<CertificateMismatch | undefined>(undefined);
+    const [lastConnect, setLastConnect] = useState<ServerEntry | undefined>(undefined);
     const [languageMenuAnchorEl, setLanguageMenuAnchorEl] = React.useState<null | HTMLElement>(null);
     const languageMenuOpen = Boolean(languageMenuAnchorEl);
 
@@ -51,6 +62,19 @@ function Login() {
         }
     }, [location]);
 
+    useEffect(() => {
+        const unlisten = listen('backend_update', (event) => {
+            let message = JSON.parse(event.payload as string);
+            if (message.message_type === "certificate_mismatch") {
+                setCertificateMismatch(message.data as CertificateMismatch);
+            }
+        });
+
+        return () => {
+            unlisten.then(stop => stop());
+        }
+    }, []);
+
     useEffect(() => {
         invoke('get_server_list').then((e: any) => {
             setServerList(e);
@@ -72,13 +96,15 @@ function Login() {
         navigate("/chat");
     }
 
-    function connect(serverHost: string, serverPort: number, serverUsername: string, identity?: string): Promise<void> {
+    function connect(serverHost: string, serverPort: number, serverUsername: string, identity?: string, password?: string): Promise<void> {
         console.log("connecting to server: ", serverHost, serverPort, serverUsername);
         setConnecting(true);
         setErrorInfo({ show: false, text: "" });
+        setCertificateMismatch(undefined);
+        setLastConnect({ description: "", host: serverHost, port: serverPort, username: serverUsername, identity: identity, password: password });
 
         return new Promise<void>((resolve, reject) => {
-            invoke('connect_to_server', { serverHost: serverHost, serverPort: serverPort, username: serverUsername, identity: identity }).then(e => {
+            invoke('connect_to_server', { serverHost: serverHost, serverPort: serverPort, username: serverUsername, identity: identity, password: password }).then(e => {
                 setConnecting(false);
                 resolve();
             }).catch(e => {
@@ -94,9 +120,9 @@ function Login() {
         setServerInfo({ show: false, text: "" });
         return new Promise<void>((resolve, reject) => {
 
-            invoke('save_server', { description: serverInfo.description, serverHost: serverInfo.server, serverPort: parseInt(serverInfo.port), username: serverInfo.username, identity: serverInfo.identity }).then(e => {
+            invoke('save_server', { description: serverInfo.description, serverHost: serverInfo.server, serverPort: parseInt(serverInfo.port), username: serverInfo.username, identity: serverInfo.identity, password: serverInfo.password }).then(e => {
                 setServerInfo({ show: true, text: "Server saved" });
-                setServerList([...serverList, { description: serverInfo.description, host: serverInfo.server, port: parseInt(serverInfo.port), username: serverInfo.username, identity: serverInfo.identity }]);
+                setServerList([...serverList, { description: serverInfo.description, host: serverInfo.server, port: parseInt(serverInfo.port), username: serverInfo.username, identity: serverInfo.identity, password: serverInfo.password }]);
                 resolve();
             }).catch(e => {
                 console.log("error saving server: ", e);
@@ -111,7 +137,25 @@ function Login() {
             setExpanded(newExpanded ? panel : false);
         };
 
+    function acceptCertificate(mismatch: CertificateMismatch) {
+        invoke('accept_server_certificate', { serverHost: mismatch.host, serverPort: mismatch.port, fingerprint: mismatch.presented }).then(() => {
+            setCertificateMismatch(undefined);
+            if (lastConnect) {
+                connect(lastConnect.host, lastConnect.port, lastConnect.username, lastConnect.identity, lastConnect.password);
+            }
+        }).catch(e => {
+            setErrorInfo({ show: true, text: e });
+        });
+    }
+
     let errorBox = errorInfo.show ? (<Box mb={3}><Alert severity="error">{errorInfo.text}</Alert></Box>) : (<div></div>);
+    let certificateBox = certificateMismatch ? (
+        <Box mb={3}>
+            <Alert severity="warning" action={<Button color="inherit" size="small" onClick={() => acceptCertificate(certificateMismatch)}>{t('Accept Certificate')}</Button>}>
+                {t('Certificate Changed', { host: certificateMismatch.host, port: certificateMismatch.port, pinned: certificateMismatch.pinned, presented: certificateMismatch.presented })}
+            </Alert>
+        </Box>
+    ) : (<div></div>);
     let connectionLoading = connecting ? (<LinearProgress />) : (<div></div>);
 
     return (
@@ -132,6 +176,7 @@ function Login() {
                         {t('Fancy Mumble Title')}
                     </Typography >
                     {errorBox}
+                    {certificateBox}
                     <Accordion expanded={expanded === 'panel1'} onChange={handleChange('panel1')}>
                         <AccordionSummary aria-controls="panel2d-content" id="panel2d-header">
                             <Typography>{t('User Profiles')}</Typography>
@@ -142,7 +187,7 @@ function Login() {
                                 {serverList.map((e) => {
                                     return (
                                         <ListItem disablePadding key={(e.host || '') + (e.port || '') + (e.username || '')}>
-                                            <ListItemButton onClick={() => connect(e.host, e.port, e.username, e.identity)}>
+                                            <ListItemButton onClick={() => connect(e.host, e.port, e.username, e.identity, e.password)}>
                                                 <ListItemAvatar>
                                                     <Avatar>
                                                         <StorageIcon />
@@ -163,7 +208,7 @@ function Login() {
                                 serverInfo={serverInfo}
                                 identityCerts={identityCerts}
                                 onSave={(serverInfo) => saveServer(serverInfo)}
-                                onConnect={(serverInfo) => connect(serverInfo.server, parseInt(serverInfo.port), serverInfo.username, serverInfo.identity)}
+                                onConnect={(serverInfo) => connect(serverInfo.server, parseInt(serverInfo.port), serverInfo.username, serverInfo.identity, serverInfo.password)}
                             />
                         </AccordionDetails>
                     </Accordion>
@@ -9,11 +9,18 @@ export interface ServerSync {
     permissions?: number;
 }
 
+export interface ReconnectInfo {
+    attempt: number;
+    delay_ms: number;
+}
+
 export interface ServerState {
     connected: boolean;
     maxBandwidth: number;
     welcomeText: string;
     permissions: number;
+    // set while the backend tries to get the lost connection back
+    reconnecting?: ReconnectInfo;
 }
 
 interface ChannelDataUpdate {
@@ -44,10 +51,13 @@ export const serverSlice = createSlice({
             state.permissions = permissions;
         }
     },
+    updateReconnecting: (state, action: PayloadAction<ReconnectInfo | undefined>) => {
+        state.reconnecting = action.payload;
+    },
   },
 })
 
 // Action creators are generated for each case reducer function
-export const { updateServerInfo } = serverSlice.actions
+export const { updateServerInfo, updateReconnecting } = serverSlice.actions
 
 export default serverSlice.reducer
\ No newline at end of file
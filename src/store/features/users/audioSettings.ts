@@ -4,6 +4,7 @@ import { persistentStorage } from "../../persistance/persist";
 export enum InputMode {
     VoiceActivation = 0,
     PushToTalk = 1,
+    ToggleToTalk = 2,
 }
 
 interface VoiceActivationOptions {
@@ -13,6 +14,12 @@ interface VoiceActivationOptions {
     voice_hysteresis_upper_threshold: number;
 }
 
+interface PushToTalkOptions {
+    shortcut: string;
+    release_delay: number;
+    audio_cue: boolean;
+}
+
 interface CompressorOptions {
     attack_time: number,
     release_time: number,
@@ -21,13 +28,21 @@ interface CompressorOptions {
 }
 
 export interface AudioInputSettings {
+    // device names, empty for the default device
+    input_device: string;
+    output_device: string;
     amplification: number;
     input_mode: InputMode;
     voice_activation_options: VoiceActivationOptions;
+    push_to_talk_options: PushToTalkOptions;
     compressor_options: CompressorOptions;
+    noise_suppression: boolean;
+    positional_audio: boolean;
 }
 
 const defaultValues: AudioInputSettings = {
+    input_device: "",
+    output_device: "",
     amplification: 13.0,
     input_mode: InputMode.VoiceActivation,
     voice_activation_options: {
@@ -36,12 +51,19 @@ const defaultValues: AudioInputSettings = {
         voice_hysteresis_lower_threshold: 0.03,
         voice_hysteresis_upper_threshold: 0.07
     },
+    push_to_talk_options: {
+        shortcut: "",
+        release_delay: 200,
+        audio_cue: true
+    },
     compressor_options: {
         attack_time: 0.1,
         release_time: 0.1,
         threshold: -30.0,
         ratio: 10.0
-    }
+    },
+    noise_suppression: true,
+    positional_audio: false
 };
 
 const initialState: AudioInputSettings = defaultValues;
@@ -53,6 +75,12 @@ export const frontendSettings = createSlice({
         updateAudioSettings: (state, action) => {
             Object.assign(state, action.payload);
         },
+        setInputDevice: (state, action) => {
+            state.input_device = action.payload;
+        },
+        setOutputDevice: (state, action) => {
+            state.output_device = action.payload;
+        },
         setAmplification: (state, action) => {
             state.amplification = action.payload;
         },
@@ -69,6 +97,21 @@ export const frontendSettings = createSlice({
             state.voice_activation_options.voice_hysteresis_lower_threshold = action.payload[0];
             state.voice_activation_options.voice_hysteresis_upper_threshold = action.payload[1];
         },
+        setPushToTalkShortcut(state, action) {
+            state.push_to_talk_options.shortcut = action.payload;
+        },
+        setReleaseDelay(state, action) {
+            state.push_to_talk_options.release_delay = action.payload;
+        },
+        setAudioCue(state, action) {
+            state.push_to_talk_options.audio_cue = action.payload;
+        },
+        setNoiseSuppression(state, action) {
+            state.noise_suppression = action.payload;
+        },
+        setPositionalAudio(state, action) {
+            state.positional_audio = action.payload;
+        },
         setAttackTime(state, action) {
             state.compressor_options.attack_time = action.payload
         },
@@ -86,11 +129,18 @@ export const frontendSettings = createSlice({
 
 export const {
     updateAudioSettings,
+    setInputDevice,
+    setOutputDevice,
     setAmplification,
     setInputMode,
     setVoiceHold,
     setFadeOutDuration,
     setVoiceHysteresis,
+    setPushToTalkShortcut,
+    setReleaseDelay,
+    setAudioCue,
+    setNoiseSuppression,
+    setPositionalAudio,
     setAttackTime,
     setReleaseTime,
     setCompressorThreshold,
@@ -5,21 +5,24 @@ export interface SenderInfo {
     user_name: string,
 }
 export interface TextMessage {
-    // The message sender, identified by its session.
-    actor: number,
     sender: SenderInfo,
-    // The channels to which the message is sent, identified by their
-    // channel_ids.
-    channel_id: number[]
-    // The root channels when sending message recursively to several channels,
-    // identified by their channel_ids.
-    tree_id: number[]
+    // The channel to which the message is sent, null for private messages.
+    channel_id: number | null,
     // The UTF-8 encoded message. May be HTML if the server allows.
     message: string,
     // custom property to keep track of time
     timestamp: number,
-    // unique id of the message
-    id: string
+    // unique id of the message, older clients don't send one
+    id: string | null,
+    // own messages are pending until the server processed them
+    state?: MessageState
+}
+
+export type MessageState = 'pending' | 'confirmed' | 'failed';
+
+export interface MessageStateUpdate {
+    id: string,
+    state: MessageState
 }
 
 
@@ -33,6 +36,16 @@ export const chatMessageSlice = createSlice({
             console.log("addChatMessage: ", action.payload);
             state.push(action.payload);
         },
+        addChatHistory: (state, action: PayloadAction<TextMessage[]>) => {
+            // the history is older than everything received so far
+            state.unshift(...action.payload);
+        },
+        updateChatMessageState: (state, action: PayloadAction<MessageStateUpdate>) => {
+            let message = state.find(e => e.id === action.payload.id);
+            if (message) {
+                message.state = action.payload.state;
+            }
+        },
         deleteChatMessage: (state, action: PayloadAction<number>) => {
             let messageId = action.payload;
             let messageIndex = state.findIndex(e => e.timestamp === messageId);
@@ -44,6 +57,6 @@ export const chatMessageSlice = createSlice({
     },
 })
 
-export const { addChatMessage, deleteChatMessage, deleteAllMessages } = chatMessageSlice.actions
+export const { addChatMessage, addChatHistory, updateChatMessageState, deleteChatMessage, deleteAllMessages } = chatMessageSlice.actions
 
 export default chatMessageSlice.reducer
\ No newline at end of file
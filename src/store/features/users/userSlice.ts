@@ -148,6 +148,16 @@ export const userSlice = createSlice({
         state.users[userIndex].talking = action.payload.talking;
       }
     },
+    updateCurrentUserTalkingInfo(state, action: PayloadAction<boolean>) {
+      if (state.currentUser) {
+        let userId = state.currentUser.id;
+        state.currentUser.talking = action.payload;
+        let userIndex = state.users.findIndex(e => e.id === userId);
+        if (userIndex !== -1) {
+          state.users[userIndex].talking = action.payload;
+        }
+      }
+    },
     updateCurrentUserListeningInfo(state, action: PayloadAction<{ self_mute: boolean, self_deaf: boolean }>) {
       if (state.currentUser) {
         state.currentUser.self_mute = action.payload.self_mute;
@@ -221,6 +231,6 @@ export const userSlice = createSlice({
 })
 
 // Action creators are generated for each case reducer function
-export const { deleteUser, updateUserImage, updateCurrentUserById, updateConnected, updateUserTalkingInfo, updateUserSettings, updateCurrentUserListeningInfo } = userSlice.actions
+export const { deleteUser, updateUserImage, updateCurrentUserById, updateConnected, updateUserTalkingInfo, updateCurrentUserTalkingInfo, updateUserSettings, updateCurrentUserListeningInfo } = userSlice.actions
 
 export default userSlice.reducer
\ No newline at end of file